@@ -1,6 +1,6 @@
 use std::collections::BTreeMap;
 use std::fs::File;
-use std::io::{stdin, BufRead, BufReader, Cursor, Read, Write};
+use std::io::{stdin, stdout, BufRead, BufReader, BufWriter, Cursor, Read, Write};
 use std::path::PathBuf;
 use std::str::FromStr;
 use std::time::{Duration, Instant};
@@ -8,7 +8,7 @@ use std::time::{Duration, Instant};
 use byte_unit::Byte;
 use eyre::Result;
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
-use milli::documents::{DocumentsBatchBuilder, DocumentsBatchReader};
+use milli::documents::{DocumentsBatchBuilder, DocumentsBatchReader, DocumentsExport};
 use milli::update::UpdateIndexingStep::{
     ComputeIdsAndMergeDocuments, IndexDocuments, MergeDataIntoFinalDatabase, RemapDocumentAddition,
 };
@@ -128,12 +128,14 @@ impl Performer for Settings {
 #[derive(Debug, StructOpt)]
 enum Documents {
     Add(DocumentAddition),
+    Export(DocumentExport),
 }
 
 impl Performer for Documents {
     fn perform(self, index: Index) -> Result<()> {
         match self {
             Self::Add(addition) => addition.perform(index),
+            Self::Export(export) => export.perform(index),
         }
     }
 }
@@ -276,6 +278,75 @@ impl Performer for DocumentAddition {
     }
 }
 
+#[derive(Debug)]
+enum DocumentExportFormat {
+    Csv,
+    Jsonl,
+}
+
+impl FromStr for DocumentExportFormat {
+    type Err = eyre::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(Self::Csv),
+            "jsonl" => Ok(Self::Jsonl),
+            other => eyre::bail!("invalid format: {}", other),
+        }
+    }
+}
+
+#[derive(Debug, StructOpt)]
+struct DocumentExport {
+    /// The format of the export, the CSV format can only export strings and numbers,
+    /// use jsonl to export documents with arrays, objects or booleans.
+    #[structopt(short, long, default_value = "jsonl", possible_values = &["csv", "jsonl"])]
+    format: DocumentExportFormat,
+    /// Path to the export file, if not present, will write to stdout.
+    #[structopt(short, long)]
+    path: Option<PathBuf>,
+    /// Only export these top-level attributes of the documents.
+    #[structopt(long)]
+    fields: Vec<String>,
+    /// Only export the documents matching this filter.
+    #[structopt(long)]
+    filter: Option<String>,
+}
+
+impl Performer for DocumentExport {
+    fn perform(self, index: milli::Index) -> Result<()> {
+        let writer: Box<dyn Write> = match self.path {
+            Some(ref path) => {
+                let file = File::create(path)?;
+                Box::new(file)
+            }
+            None => Box::new(stdout()),
+        };
+        let writer = BufWriter::new(writer);
+
+        let txn = index.read_txn()?;
+        let mut export = DocumentsExport::new(&txn, &index);
+
+        if !self.fields.is_empty() {
+            export.fields(self.fields.clone());
+        }
+
+        if let Some(ref filter) = self.filter {
+            if let Some(condition) = milli::Filter::from_str(filter)? {
+                export.filter(condition);
+            }
+        }
+
+        let count = match self.format {
+            DocumentExportFormat::Csv => export.write_csv(writer)?,
+            DocumentExportFormat::Jsonl => export.write_ndjson(writer)?,
+        };
+
+        eprintln!("Exported {} documents.", count);
+        Ok(())
+    }
+}
+
 fn indexing_callback(step: milli::update::UpdateIndexingStep, bars: &[ProgressBar]) {
     let step_index = step.step();
     let bar = &bars[step_index];
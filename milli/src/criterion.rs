@@ -22,6 +22,8 @@ pub enum CriterionError {
 `{name}` can only be used for filtering at search time"
     )]
     ReservedNameForFilter { name: String },
+    #[error("`{name}` can't be the name of a custom ranking rule as it can be mistaken for a built-in ranking rule.")]
+    InvalidCustomName { name: String },
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
@@ -45,6 +47,9 @@ pub enum Criterion {
     Asc(String),
     /// Sorted by the decreasing value of the field specified.
     Desc(String),
+    /// Sorted by a ranking rule registered on the index under this name, written
+    /// `custom:<name>`, see [`Index::register_ranking_rule`](crate::Index::register_ranking_rule).
+    Custom(String),
 }
 
 impl Criterion {
@@ -55,6 +60,14 @@ impl Criterion {
             _otherwise => None,
         }
     }
+
+    /// Returns `true` if this name of a custom ranking rule can't be mistaken
+    /// for another ranking rule, alone or in its `custom:<name>` form.
+    pub(crate) fn is_valid_custom_name(name: &str) -> bool {
+        !name.is_empty()
+            && name.parse::<Criterion>().is_err()
+            && AscDesc::from_str(&format!("custom:{}", name)).is_err()
+    }
 }
 
 impl FromStr for Criterion {
@@ -68,12 +81,18 @@ impl FromStr for Criterion {
             "attribute" => Ok(Criterion::Attribute),
             "sort" => Ok(Criterion::Sort),
             "exactness" => Ok(Criterion::Exactness),
-            text => match AscDesc::from_str(text)? {
-                AscDesc::Asc(Member::Field(field)) => Ok(Criterion::Asc(field)),
-                AscDesc::Desc(Member::Field(field)) => Ok(Criterion::Desc(field)),
-                AscDesc::Asc(Member::Geo(_)) | AscDesc::Desc(Member::Geo(_)) => {
+            text => match AscDesc::from_str(text) {
+                Ok(AscDesc::Asc(Member::Field(field))) => Ok(Criterion::Asc(field)),
+                Ok(AscDesc::Desc(Member::Field(field))) => Ok(Criterion::Desc(field)),
+                Ok(AscDesc::Asc(Member::Geo(_))) | Ok(AscDesc::Desc(Member::Geo(_))) => {
                     Err(CriterionError::ReservedNameForSort { name: "_geoPoint".to_string() })?
                 }
+                Err(error) => match text.strip_prefix("custom:") {
+                    Some(name) if Criterion::is_valid_custom_name(name) => {
+                        Ok(Criterion::Custom(name.to_string()))
+                    }
+                    _ => Err(error)?,
+                },
             },
         }
     }
@@ -103,6 +122,7 @@ impl fmt::Display for Criterion {
             Exactness => f.write_str("exactness"),
             Asc(attr) => write!(f, "{}:asc", attr),
             Desc(attr) => write!(f, "{}:desc", attr),
+            Custom(name) => write!(f, "custom:{}", name),
         }
     }
 }
@@ -129,6 +149,9 @@ mod tests {
             ("truc:machin:desc", Criterion::Desc(S("truc:machin"))),
             ("hello-world!:desc", Criterion::Desc(S("hello-world!"))),
             ("it's spacy over there:asc", Criterion::Asc(S("it's spacy over there"))),
+            ("custom:popularity", Criterion::Custom(S("popularity"))),
+            ("custom:asc", Criterion::Asc(S("custom"))),
+            ("custom:price:asc", Criterion::Asc(S("custom:price"))),
         ];
 
         for (input, expected) in valid_criteria {
@@ -141,6 +164,7 @@ mod tests {
                 res
             );
             assert_eq!(res.unwrap(), expected);
+            assert_eq!(expected.to_string().parse::<Criterion>().unwrap(), expected);
         }
 
         let invalid_criteria = [
@@ -159,6 +183,14 @@ mod tests {
             ("_geoPoint(42, 75):asc", ReservedNameForSort { name: S("_geoPoint") }),
             ("_geoRadius:asc", ReservedNameForFilter { name: S("_geoRadius") }),
             ("_geoRadius(42, 75, 59):asc", ReservedNameForFilter { name: S("_geoRadius") }),
+            ("_geoBoundingBox:asc", ReservedNameForFilter { name: S("_geoBoundingBox") }),
+            (
+                "_geoBoundingBox([42, 75], [40, 70]):asc",
+                ReservedNameForFilter { name: S("_geoBoundingBox") },
+            ),
+            ("_geoPolygon:asc", ReservedNameForFilter { name: S("_geoPolygon") }),
+            ("_vectors:asc", ReservedName { name: S("_vectors") }),
+            ("custom:", InvalidName { name: S("custom:") }),
         ];
 
         for (input, expected) in invalid_criteria {
@@ -180,4 +212,14 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn custom_names() {
+        assert!(Criterion::is_valid_custom_name("popularity"));
+        assert!(!Criterion::is_valid_custom_name(""));
+        assert!(!Criterion::is_valid_custom_name("words"));
+        assert!(!Criterion::is_valid_custom_name("price:asc"));
+        assert!(!Criterion::is_valid_custom_name("desc"));
+        assert!(!Criterion::is_valid_custom_name("custom:popularity"));
+    }
 }
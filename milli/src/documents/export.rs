@@ -0,0 +1,214 @@
+use std::io::Write;
+
+use indexmap::IndexMap;
+use serde_json::Value;
+
+use super::Error;
+use crate::error::{InternalError, UserError};
+use crate::index::db_name;
+use crate::{obkv_to_json, Filter, Index, Object, Result, BEU32};
+
+/// Streams the documents of an index out, in the formats that can be read back
+/// by the [`DocumentsBatchBuilder`](super::DocumentsBatchBuilder).
+///
+/// Only the top-level attributes can be projected, the documents can be selected by a filter.
+pub struct DocumentsExport<'a> {
+    fields: Option<Vec<String>>,
+    filter: Option<Filter<'a>>,
+    rtxn: &'a heed::RoTxn<'a>,
+    index: &'a Index,
+}
+
+impl<'a> DocumentsExport<'a> {
+    pub fn new(rtxn: &'a heed::RoTxn, index: &'a Index) -> DocumentsExport<'a> {
+        DocumentsExport { fields: None, filter: None, rtxn, index }
+    }
+
+    /// Only exports the given attributes of the documents, in this order.
+    pub fn fields(&mut self, fields: Vec<String>) -> &mut DocumentsExport<'a> {
+        self.fields = Some(fields);
+        self
+    }
+
+    /// Only exports the documents matching the filter.
+    pub fn filter(&mut self, filter: Filter<'a>) -> &mut DocumentsExport<'a> {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// Returns an iterator over the exported documents, ordered by internal document id.
+    pub fn documents(&self) -> Result<impl Iterator<Item = Result<Object>> + 'a> {
+        let documents_ids = match &self.filter {
+            Some(filter) => filter.evaluate(self.rtxn, self.index)?,
+            None => self.index.documents_ids(self.rtxn)?,
+        };
+
+        let fields_ids_map = self.index.fields_ids_map(self.rtxn)?;
+        let fields_ids: Vec<_> = match &self.fields {
+            Some(fields) => fields.iter().filter_map(|name| fields_ids_map.id(name)).collect(),
+            None => fields_ids_map.ids().collect(),
+        };
+
+        let (rtxn, index) = (self.rtxn, self.index);
+        Ok(documents_ids.into_iter().map(move |docid| {
+            let obkv = index.documents.get(rtxn, &BEU32::new(docid))?.ok_or(
+                InternalError::DatabaseMissingEntry { db_name: db_name::DOCUMENTS, key: None },
+            )?;
+            obkv_to_json(&fields_ids, &fields_ids_map, obkv)
+        }))
+    }
+
+    /// Writes the documents as JSON objects separated by new lines (NDJSON),
+    /// returns the number of exported documents.
+    pub fn write_ndjson<W: Write>(&self, mut writer: W) -> Result<u64> {
+        let mut count = 0;
+        for result in self.documents()? {
+            let document = result?;
+            serde_json::to_writer(&mut writer, &document).map_err(InternalError::SerdeJson)?;
+            writer.write_all(b"\n")?;
+            count += 1;
+        }
+        writer.flush()?;
+        Ok(count)
+    }
+
+    /// Writes the documents as CSV records, returns the number of exported documents.
+    ///
+    /// The columns of which all the values are numbers are annotated with the `:number` type.
+    /// Only the strings, numbers and null values can be read back from CSV, an error is returned
+    /// before anything is written if the documents contain other values, use NDJSON instead.
+    pub fn write_csv<W: Write>(&self, writer: W) -> Result<u64> {
+        // We first need to go through the documents to know the columns and their types.
+        let mut columns: IndexMap<String, bool> = IndexMap::new();
+        if let Some(fields) = &self.fields {
+            columns.extend(fields.iter().map(|field| (field.clone(), true)));
+        }
+        for result in self.documents()? {
+            for (field, value) in result? {
+                if !(value.is_string() || value.is_number() || value.is_null()) {
+                    return Err(UserError::InvalidCsvExportValue(field).into());
+                }
+                let is_number = value.is_number() || value.is_null();
+                let column_is_number = columns.entry(field).or_insert(true);
+                *column_is_number &= is_number;
+            }
+        }
+
+        let mut writer = csv::Writer::from_writer(writer);
+        let headers: Vec<_> = columns
+            .iter()
+            .map(|(field, is_number)| match is_number {
+                true => format!("{}:number", field),
+                false => field.clone(),
+            })
+            .collect();
+        writer.write_record(&headers).map_err(Error::Csv)?;
+
+        let mut count = 0;
+        let mut record = Vec::with_capacity(columns.len());
+        for result in self.documents()? {
+            let mut document = result?;
+            record.clear();
+            for field in columns.keys() {
+                let value = match document.remove(field) {
+                    None | Some(Value::Null) => String::new(),
+                    Some(Value::String(string)) => string,
+                    Some(Value::Number(number)) => number.to_string(),
+                    Some(_) => return Err(UserError::InvalidCsvExportValue(field.clone()).into()),
+                };
+                record.push(value);
+            }
+            writer.write_record(&record).map_err(Error::Csv)?;
+            count += 1;
+        }
+        writer.flush()?;
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use big_s::S;
+    use maplit::hashset;
+    use serde_json::json;
+
+    use super::*;
+    use crate::documents::{
+        documents_batch_reader_from_objects, objects_from_json_value, DocumentsBatchBuilder,
+        DocumentsBatchReader,
+    };
+    use crate::index::tests::TempIndex;
+
+    fn temp_index() -> TempIndex {
+        let index = TempIndex::new();
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("price") });
+            })
+            .unwrap();
+        let documents = objects_from_json_value(json!([
+            { "id": 0, "title": "hello", "price": 10, "tags": ["a", "b"] },
+            { "id": 1, "title": "world, again", "price": 25.5 },
+            { "id": 2, "title": "bye", "price": null },
+        ]));
+        index.add_documents(documents_batch_reader_from_objects(documents)).unwrap();
+        index
+    }
+
+    #[test]
+    fn export_ndjson() {
+        let index = temp_index();
+        let rtxn = index.read_txn().unwrap();
+
+        let mut export = DocumentsExport::new(&rtxn, &index);
+        export.fields(vec![S("title"), S("id")]);
+        export.filter(Filter::from_str("price > 5").unwrap().unwrap());
+
+        let mut output = Vec::new();
+        assert_eq!(export.write_ndjson(&mut output).unwrap(), 2);
+        assert_eq!(
+            std::str::from_utf8(&output).unwrap(),
+            "{\"title\":\"hello\",\"id\":0}\n{\"title\":\"world, again\",\"id\":1}\n"
+        );
+    }
+
+    #[test]
+    fn export_csv() {
+        let index = temp_index();
+        let rtxn = index.read_txn().unwrap();
+
+        // The arrays can't be read back from CSV.
+        let mut output = Vec::new();
+        let error = DocumentsExport::new(&rtxn, &index).write_csv(&mut output).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "The `tags` attribute contains values that are neither strings nor numbers and can't be exported as CSV, export the documents as NDJSON instead."
+        );
+        assert!(output.is_empty());
+
+        let mut export = DocumentsExport::new(&rtxn, &index);
+        export.fields(vec![S("id"), S("title"), S("price")]);
+        assert_eq!(export.write_csv(&mut output).unwrap(), 3);
+        assert_eq!(
+            std::str::from_utf8(&output).unwrap(),
+            "id:number,title,price:number\n\
+             0,hello,10\n\
+             1,\"world, again\",25.5\n\
+             2,bye,\n"
+        );
+
+        // The exported documents can be read back.
+        let mut builder = DocumentsBatchBuilder::new(Vec::new());
+        builder.append_csv(csv::Reader::from_reader(Cursor::new(output))).unwrap();
+        let vector = builder.into_inner().unwrap();
+        let reader = DocumentsBatchReader::from_reader(Cursor::new(vector)).unwrap();
+        let (mut cursor, fields_index) = reader.into_cursor_and_fields_index();
+        let document = cursor.next_document().unwrap().unwrap();
+        assert_eq!(
+            Value::Object(fields_index.recreate_json(&document).unwrap()),
+            json!({ "id": 0, "title": "hello", "price": 10 })
+        );
+    }
+}
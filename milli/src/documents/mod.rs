@@ -1,5 +1,6 @@
 mod builder;
 mod enriched;
+mod export;
 mod reader;
 mod serde_impl;
 
@@ -10,6 +11,7 @@ use std::str::Utf8Error;
 use bimap::BiHashMap;
 pub use builder::DocumentsBatchBuilder;
 pub use enriched::{EnrichedDocument, EnrichedDocumentsBatchCursor, EnrichedDocumentsBatchReader};
+pub use export::DocumentsExport;
 use obkv::KvReader;
 pub use reader::{DocumentsBatchCursor, DocumentsBatchCursorError, DocumentsBatchReader};
 use serde::{Deserialize, Serialize};
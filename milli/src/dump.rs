@@ -0,0 +1,452 @@
+//! A portable dump of an index, it doesn't depend on the layout of the database
+//! and can be used to move an index from a version of the engine to another.
+//!
+//! A dump is an NDJSON stream: the first line is a [`DumpHeader`] describing the version
+//! of the dump, the primary key and the settings of the index, every other line is a document.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{BufRead, Seek, SeekFrom, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::documents::{DocumentsBatchBuilder, DocumentsBatchReader, DocumentsExport};
+use crate::error::{InternalError, UserError};
+use crate::update::{
+    fetch_matching_values_in_object, validate_document_id_value, validate_geo_from_value,
+    ClearDocuments, DocumentId, IndexDocuments, IndexDocumentsConfig, IndexDocumentsMethod,
+    IndexerConfig, Settings,
+};
+use crate::{Criterion, CriterionError, Index, Object, Result, SynonymGroup};
+
+/// The version of the dumps written by this version of the engine.
+pub const DUMP_VERSION: u32 = 1;
+
+/// The first line of a dump.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DumpHeader {
+    pub dump_version: u32,
+    /// The version of the engine that wrote the dump, for information only.
+    pub engine_version: String,
+    pub primary_key: Option<String>,
+    pub settings: DumpSettings,
+}
+
+/// Everything that can be set with the [`Settings`] update, the `None` values
+/// and the empty collections are reset when the dump is imported.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DumpSettings {
+    pub searchable_fields: Option<Vec<String>>,
+    pub attribute_weights: BTreeMap<String, u16>,
+    pub displayed_fields: Option<Vec<String>>,
+    pub filterable_fields: BTreeSet<String>,
+    pub sortable_fields: BTreeSet<String>,
+    pub criteria: Vec<String>,
+    pub stop_words: BTreeSet<String>,
+    pub distinct_field: Option<String>,
+    pub synonyms: Vec<SynonymGroup>,
+    pub authorize_typos: bool,
+    pub min_word_len_one_typo: u8,
+    pub min_word_len_two_typos: u8,
+    pub exact_words: BTreeSet<String>,
+    pub exact_attributes: BTreeSet<String>,
+    pub max_values_per_facet: Option<usize>,
+    pub pagination_max_total_hits: Option<usize>,
+}
+
+impl DumpSettings {
+    /// Reads the current settings of the index.
+    pub fn from_index(rtxn: &heed::RoTxn, index: &Index) -> Result<DumpSettings> {
+        let to_strings = |names: Vec<&str>| names.into_iter().map(String::from).collect();
+
+        Ok(DumpSettings {
+            searchable_fields: index.user_defined_searchable_fields(rtxn)?.map(to_strings),
+            attribute_weights: index.attribute_weights(rtxn)?,
+            displayed_fields: index.displayed_fields(rtxn)?.map(to_strings),
+            filterable_fields: index.filterable_fields(rtxn)?.into_iter().collect(),
+            sortable_fields: index.sortable_fields(rtxn)?.into_iter().collect(),
+            criteria: index.criteria(rtxn)?.iter().map(ToString::to_string).collect(),
+            stop_words: fst_words(index.stop_words(rtxn)?)?,
+            distinct_field: index.distinct_field(rtxn)?.map(String::from),
+            synonyms: index.synonym_groups(rtxn)?,
+            authorize_typos: index.authorize_typos(rtxn)?,
+            min_word_len_one_typo: index.min_word_len_one_typo(rtxn)?,
+            min_word_len_two_typos: index.min_word_len_two_typos(rtxn)?,
+            exact_words: fst_words(index.exact_words(rtxn)?)?,
+            exact_attributes: index.exact_attributes(rtxn)?.into_iter().map(String::from).collect(),
+            max_values_per_facet: index.max_values_per_facet(rtxn)?,
+            pagination_max_total_hits: index.pagination_max_total_hits(rtxn)?,
+        })
+    }
+
+    /// Sets or resets every setting of the builder.
+    pub fn apply(self, builder: &mut Settings) {
+        match self.searchable_fields {
+            Some(fields) => builder.set_searchable_fields(fields),
+            None => builder.reset_searchable_fields(),
+        }
+        match self.attribute_weights.is_empty() {
+            true => builder.reset_attribute_weights(),
+            false => builder.set_attribute_weights(self.attribute_weights),
+        }
+        match self.displayed_fields {
+            Some(fields) => builder.set_displayed_fields(fields),
+            None => builder.reset_displayed_fields(),
+        }
+        builder.set_filterable_fields(self.filterable_fields.into_iter().collect());
+        builder.set_sortable_fields(self.sortable_fields.into_iter().collect());
+        builder.set_criteria(self.criteria);
+        match self.stop_words.is_empty() {
+            true => builder.reset_stop_words(),
+            false => builder.set_stop_words(self.stop_words),
+        }
+        match self.distinct_field {
+            Some(field) => builder.set_distinct_field(field),
+            None => builder.reset_distinct_field(),
+        }
+        match self.synonyms.is_empty() {
+            true => builder.reset_synonyms(),
+            false => builder.set_synonym_groups(self.synonyms),
+        }
+        builder.set_autorize_typos(self.authorize_typos);
+        builder.set_min_word_len_one_typo(self.min_word_len_one_typo);
+        builder.set_min_word_len_two_typos(self.min_word_len_two_typos);
+        match self.exact_words.is_empty() {
+            true => builder.reset_exact_words(),
+            false => builder.set_exact_words(self.exact_words),
+        }
+        builder.set_exact_attributes(self.exact_attributes.into_iter().collect());
+        match self.max_values_per_facet {
+            Some(value) => builder.set_max_values_per_facet(value),
+            None => builder.reset_max_values_per_facet(),
+        }
+        match self.pagination_max_total_hits {
+            Some(value) => builder.set_pagination_max_total_hits(value),
+            None => builder.reset_pagination_max_total_hits(),
+        }
+    }
+}
+
+fn fst_words<A: AsRef<[u8]>>(set: Option<fst::Set<A>>) -> Result<BTreeSet<String>> {
+    match set {
+        Some(set) => Ok(set.stream().into_strs()?.into_iter().collect()),
+        None => Ok(BTreeSet::new()),
+    }
+}
+
+/// Writes a dump of the index, returns the number of dumped documents.
+pub fn write_dump<W: Write>(rtxn: &heed::RoTxn, index: &Index, mut writer: W) -> Result<u64> {
+    let header = DumpHeader {
+        dump_version: DUMP_VERSION,
+        engine_version: env!("CARGO_PKG_VERSION").to_string(),
+        primary_key: index.primary_key(rtxn)?.map(String::from),
+        settings: DumpSettings::from_index(rtxn, index)?,
+    };
+
+    serde_json::to_writer(&mut writer, &header).map_err(InternalError::SerdeJson)?;
+    writer.write_all(b"\n")?;
+    DocumentsExport::new(rtxn, index).write_ndjson(writer)
+}
+
+/// Replaces the content of the index by the content of the dump,
+/// returns the number of imported documents.
+///
+/// The ranking rules of the dump are checked and the documents of the dump are read, with
+/// their ids and `_geo` fields validated, first, then the documents of the index are cleared, all the settings are set as they are in the
+/// dump and the documents of the dump are indexed.
+///
+/// An invalid dump is rejected before the index is modified, but the indexing can still
+/// fail afterward, the transaction must be aborted when an error is returned.
+pub fn import_dump<'i, R: BufRead>(
+    wtxn: &mut heed::RwTxn<'i, '_>,
+    index: &'i Index,
+    indexer_config: &IndexerConfig,
+    mut reader: R,
+) -> Result<u64> {
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+
+    // The version is checked first as the rest of the header may be different.
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Version {
+        dump_version: u32,
+    }
+
+    let version: Version = serde_json::from_str(&line).map_err(UserError::InvalidDump)?;
+    if version.dump_version != DUMP_VERSION {
+        let version = version.dump_version;
+        return Err(UserError::UnsupportedDumpVersion { version, supported: DUMP_VERSION }.into());
+    }
+    let header: DumpHeader = serde_json::from_str(&line).map_err(UserError::InvalidDump)?;
+
+    // The custom ranking rules are not part of the dump, they must already be registered.
+    for name in &header.settings.criteria {
+        if let Criterion::Custom(rule) = name.parse::<Criterion>()? {
+            if index.custom_ranking_rule(&rule).is_none() {
+                return Err(CriterionError::InvalidName { name: name.clone() }.into());
+            }
+        }
+    }
+
+    let validate_geo = header.settings.sortable_fields.contains("_geo");
+    let mut builder = DocumentsBatchBuilder::new(tempfile::tempfile()?);
+    for result in serde_json::Deserializer::from_reader(reader).into_iter::<Object>() {
+        let document = result.map_err(UserError::InvalidDump)?;
+        if let Some(primary_key) = &header.primary_key {
+            let document_id = validate_dumped_document_id(primary_key, &document)?;
+            if let Some(geo) = document.get("_geo").filter(|_| validate_geo) {
+                let document_id = DocumentId::Retrieved { value: document_id };
+                validate_geo_from_value(&document_id, geo.clone()).map_err(UserError::from)?;
+            }
+        }
+        builder.append_json_object(&document)?;
+    }
+
+    ClearDocuments::new(wtxn, index).execute()?;
+
+    let mut settings = Settings::new(wtxn, index, indexer_config);
+    match header.primary_key {
+        Some(primary_key) => settings.set_primary_key(primary_key),
+        None => settings.reset_primary_key(),
+    }
+    header.settings.apply(&mut settings);
+    settings.execute(|_| ())?;
+
+    let documents_count = builder.documents_count() as u64;
+    if documents_count > 0 {
+        let mut file = builder.into_inner()?;
+        file.seek(SeekFrom::Start(0))?;
+        let reader = DocumentsBatchReader::from_reader(file)?;
+
+        let config = IndexDocumentsConfig {
+            update_method: IndexDocumentsMethod::ReplaceDocuments,
+            ..Default::default()
+        };
+        let indexing = IndexDocuments::new(wtxn, index, indexer_config, config, |_| ())?;
+        let (indexing, user_result) = indexing.add_documents(reader)?;
+        user_result?;
+        indexing.execute()?;
+    }
+
+    Ok(documents_count)
+}
+
+/// Returns the id of a dumped document, it must have a single valid id under the primary key.
+fn validate_dumped_document_id(primary_key: &str, document: &Object) -> Result<String> {
+    let mut values = Vec::new();
+    fetch_matching_values_in_object(document.clone(), primary_key, "", &mut values);
+    let primary_key = primary_key.to_string();
+    match values.pop() {
+        Some(value) if values.is_empty() => Ok(validate_document_id_value(value)??),
+        Some(_) => {
+            Err(UserError::TooManyDocumentIds { primary_key, document: document.clone() }.into())
+        }
+        None => {
+            Err(UserError::MissingDocumentId { primary_key, document: document.clone() }.into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use std::iter;
+
+    use big_s::S;
+    use maplit::{btreemap, btreeset, hashset};
+    use roaring::RoaringBitmap;
+
+    use super::*;
+    use crate::index::tests::TempIndex;
+    use crate::{AscDesc, CustomRankingRule, Member, Search, SearchResult};
+
+    #[test]
+    fn dump_and_import() {
+        let index = TempIndex::new();
+        index
+            .update_settings(|settings| {
+                settings.set_primary_key(S("id"));
+                settings.set_searchable_fields(vec![S("title"), S("description")]);
+                settings.set_attribute_weights(btreemap! { S("title") => 3 });
+                settings.set_filterable_fields(hashset! { S("genre") });
+                settings.set_criteria(vec![S("words"), S("typo"), S("year:desc")]);
+                settings.set_stop_words(btreeset! { S("the") });
+                settings.set_distinct_field(S("genre"));
+                settings.set_synonym_groups(vec![SynonymGroup::Bidirectional {
+                    synonyms: vec![S("movie"), S("film")],
+                }]);
+                settings.set_min_word_len_one_typo(4);
+                settings.set_exact_words(btreeset! { S("hello") });
+                settings.set_max_values_per_facet(20);
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 1, "title": "the hello movie", "genre": "drama", "year": 2001 },
+                { "id": 2, "title": "another film", "genre": "comedy", "year": 1999 },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let mut dump = Vec::new();
+        assert_eq!(write_dump(&rtxn, &index, &mut dump).unwrap(), 2);
+        let settings = DumpSettings::from_index(&rtxn, &index).unwrap();
+        drop(rtxn);
+
+        // The dump is imported in an index that already contains other documents.
+        let imported = TempIndex::new();
+        imported.add_documents(documents!([{ "docid": "a", "title": "removed" }])).unwrap();
+
+        let mut wtxn = imported.write_txn().unwrap();
+        let count = import_dump(&mut wtxn, &imported, &imported.indexer_config, Cursor::new(&dump))
+            .unwrap();
+        wtxn.commit().unwrap();
+        assert_eq!(count, 2);
+
+        let rtxn = imported.read_txn().unwrap();
+        assert_eq!(imported.primary_key(&rtxn).unwrap(), Some("id"));
+        assert_eq!(imported.number_of_documents(&rtxn).unwrap(), 2);
+        assert_eq!(DumpSettings::from_index(&rtxn, &imported).unwrap(), settings);
+
+        // The documents are the same, whatever the order of their attributes.
+        let mut reexported = Vec::new();
+        write_dump(&rtxn, &imported, &mut reexported).unwrap();
+        let parse = |dump: &[u8]| {
+            serde_json::Deserializer::from_slice(dump)
+                .into_iter::<serde_json::Value>()
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .unwrap()
+        };
+        assert_eq!(parse(&reexported), parse(&dump));
+    }
+
+    /// Ranks the documents by decreasing internal id.
+    struct ReversedIds;
+
+    impl CustomRankingRule for ReversedIds {
+        fn buckets(
+            &self,
+            _rtxn: &heed::RoTxn,
+            _index: &Index,
+            candidates: &RoaringBitmap,
+        ) -> Result<Vec<RoaringBitmap>> {
+            let mut docids: Vec<_> = candidates.iter().collect();
+            docids.reverse();
+            Ok(docids.into_iter().map(|docid| iter::once(docid).collect()).collect())
+        }
+    }
+
+    #[test]
+    fn dump_and_import_custom_rule_geo_and_vectors() {
+        let index = TempIndex::new();
+        index.register_ranking_rule("reversed", ReversedIds).unwrap();
+        index
+            .update_settings(|settings| {
+                settings.set_primary_key(S("id"));
+                settings.set_sortable_fields(hashset! { S("_geo") });
+                settings.set_criteria(vec![S("words"), S("custom:reversed")]);
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 1, "title": "hello", "_geo": { "lat": 12, "lng": 42 }, "_vectors": [0.5, 1.0] },
+                { "id": 2, "title": "hello", "_geo": [{ "lat": 31, "lng": 40 }], "_vectors": [[1.0, 0.0]] },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let mut dump = Vec::new();
+        write_dump(&rtxn, &index, &mut dump).unwrap();
+        let settings = DumpSettings::from_index(&rtxn, &index).unwrap();
+        assert_eq!(settings.criteria, vec![S("words"), S("custom:reversed")]);
+        drop(rtxn);
+
+        // The custom ranking rules must be registered before the dump is imported.
+        let imported = TempIndex::new();
+        let mut wtxn = imported.write_txn().unwrap();
+        let error = import_dump(&mut wtxn, &imported, &imported.indexer_config, Cursor::new(&dump))
+            .unwrap_err();
+        assert_eq!(error.to_string(), "`custom:reversed` ranking rule is invalid. Valid ranking rules are words, typo, sort, proximity, attribute, exactness and custom ranking rules.");
+        drop(wtxn);
+
+        imported.register_ranking_rule("reversed", ReversedIds).unwrap();
+        let mut wtxn = imported.write_txn().unwrap();
+        import_dump(&mut wtxn, &imported, &imported.indexer_config, Cursor::new(&dump)).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = imported.read_txn().unwrap();
+        assert_eq!(DumpSettings::from_index(&rtxn, &imported).unwrap(), settings);
+        assert_eq!(imported.geo_faceted_documents_ids(&rtxn).unwrap().len(), 2);
+
+        let external_ids = imported.external_documents_ids(&rtxn).unwrap();
+        let (first, second) = (external_ids.get("1").unwrap(), external_ids.get("2").unwrap());
+
+        let mut search = Search::new(&rtxn, &imported);
+        search.query("hello");
+        let SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![second, first]);
+
+        let mut search = Search::new(&rtxn, &imported);
+        search.sort_criteria(vec![AscDesc::Asc(Member::Geo([31., 40.]))]);
+        let SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![second, first]);
+
+        let mut search = Search::new(&rtxn, &imported);
+        search.vector(vec![0.5, 1.0]);
+        let SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![first, second]);
+
+        let mut reexported = Vec::new();
+        write_dump(&rtxn, &imported, &mut reexported).unwrap();
+        let parse = |dump: &[u8]| {
+            serde_json::Deserializer::from_slice(dump)
+                .into_iter::<serde_json::Value>()
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .unwrap()
+        };
+        assert_eq!(parse(&reexported), parse(&dump));
+    }
+
+    #[test]
+    fn invalid_dump_documents() {
+        let index = TempIndex::new();
+        index.add_documents(documents!([{ "id": 1, "title": "kept" }])).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let mut dump = Vec::new();
+        write_dump(&rtxn, &index, &mut dump).unwrap();
+        drop(rtxn);
+
+        // The documents are validated before the index is modified.
+        let imported = TempIndex::new();
+        imported.add_documents(documents!([{ "id": 1, "title": "kept" }])).unwrap();
+        let invalid_documents =
+            [r#"{"id": 2, "title":"#, r#"{"title": "no id"}"#, r#"{"id": "invalid id!"}"#];
+        for documents in &invalid_documents {
+            let mut invalid = dump.clone();
+            invalid.extend_from_slice(documents.as_bytes());
+
+            let mut wtxn = imported.write_txn().unwrap();
+            import_dump(&mut wtxn, &imported, &imported.indexer_config, Cursor::new(&invalid))
+                .unwrap_err();
+            assert_eq!(imported.number_of_documents(&wtxn).unwrap(), 1);
+            assert_eq!(imported.primary_key(&wtxn).unwrap(), Some("id"));
+        }
+    }
+
+    #[test]
+    fn unsupported_dump_version() {
+        let index = TempIndex::new();
+        let mut wtxn = index.write_txn().unwrap();
+        let dump = r#"{"dumpVersion":42,"somethingElse":true}"#;
+        let error =
+            import_dump(&mut wtxn, &index, &index.indexer_config, Cursor::new(dump)).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Unsupported dump version `42`, the supported version is `1`."
+        );
+    }
+}
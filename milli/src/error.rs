@@ -11,7 +11,16 @@ use crate::documents::{self, DocumentsBatchCursorError};
 use crate::{CriterionError, DocumentId, FieldId, Object, SortError};
 
 pub fn is_reserved_keyword(keyword: &str) -> bool {
-    ["_geo", "_geoDistance", "_geoPoint", "_geoRadius"].contains(&keyword)
+    [
+        "_geo",
+        "_geoDistance",
+        "_geoPoint",
+        "_geoRadius",
+        "_geoBoundingBox",
+        "_geoPolygon",
+        "_vectors",
+    ]
+    .contains(&keyword)
 }
 
 #[derive(Error, Debug)]
@@ -98,14 +107,51 @@ A document identifier can be of type integer or string, \
 only composed of alphanumeric characters (a-z A-Z 0-9), hyphens (-) and underscores (_).", .document_id.to_string()
     )]
     InvalidDocumentId { document_id: Value },
+    #[error("Could not apply the `{operation}` operation on the `{field}` attribute of the document with the id: `{document_id}`. {reason}")]
+    InvalidDocumentPatch {
+        document_id: String,
+        field: String,
+        operation: &'static str,
+        reason: String,
+    },
+    #[error("The `{0}` attribute contains values that are neither strings nor numbers and can't be exported as CSV, export the documents as NDJSON instead.")]
+    InvalidCsvExportValue(String),
+    #[error("The dump is invalid: {0}")]
+    InvalidDump(serde_json::Error),
     #[error("Invalid facet distribution, the fields `{}` are not set as filterable.",
         .invalid_facets_name.iter().map(AsRef::as_ref).collect::<Vec<&str>>().join(", ")
      )]
     InvalidFacetsDistribution { invalid_facets_name: BTreeSet<String> },
+    #[error("Attribute `{}` is not filterable. {}",
+        .field,
+        match .valid_fields.is_empty() {
+            true => "This index does not have configured filterable attributes.".to_string(),
+            false => format!("Available filterable attributes are: `{}`.",
+                    valid_fields.iter().map(AsRef::as_ref).collect::<Vec<&str>>().join(", ")
+                ),
+        }
+    )]
+    InvalidFacetSearchFacetName { field: String, valid_fields: BTreeSet<String> },
     #[error(transparent)]
     InvalidGeoField(#[from] GeoError),
     #[error("{0}")]
     InvalidFilter(String),
+    #[error("The `_vectors` field in the document with the id: `{document_id}` is not an array of numbers nor an array of arrays of numbers. Found `{value}`.")]
+    InvalidVectorsType { document_id: Value, value: Value },
+    #[error("Invalid vector dimensions{}: expected `{}` but found `{}`.",
+        match .document_id {
+            Some(document_id) => format!(" in the `_vectors` field of the document with the id: `{}`", document_id),
+            None => String::new(),
+        },
+        .expected,
+        .found,
+    )]
+    InvalidVectorDimensions { document_id: Option<Value>, expected: usize, found: usize },
+    #[error("Attribute `{}` is not searchable. Available searchable attributes are: `{}`.",
+        .field,
+        .valid_fields.iter().map(AsRef::as_ref).collect::<Vec<&str>>().join(", ")
+    )]
+    InvalidSearchableAttribute { field: String, valid_fields: BTreeSet<String> },
     #[error("Attribute `{}` is not sortable. {}",
         .field,
         match .valid_fields.is_empty() {
@@ -116,8 +162,20 @@ only composed of alphanumeric characters (a-z A-Z 0-9), hyphens (-) and undersco
         }
     )]
     InvalidSortableAttribute { field: String, valid_fields: BTreeSet<String> },
+    #[error("Attribute `{}` can't be used by a ranking rule at search time as it is not faceted. {}",
+        .field,
+        match .valid_fields.is_empty() {
+            true => "This index does not have faceted attributes.".to_string(),
+            false => format!("Available faceted attributes are: `{}`.",
+                    valid_fields.iter().map(AsRef::as_ref).collect::<Vec<&str>>().join(", ")
+                ),
+        }
+    )]
+    InvalidRankingRuleAttribute { field: String, valid_fields: BTreeSet<String> },
     #[error("{}", HeedError::BadOpenOptions)]
     InvalidLmdbOpenOptions,
+    #[error("The `{name}` ranking rule is not registered on this index.")]
+    UnregisteredRankingRule { name: String },
     #[error("The sort ranking rule must be specified in the ranking rules settings to use the sort parameter at search time.")]
     SortRankingRuleMissing,
     #[error("The database file is in an invalid state.")]
@@ -134,19 +192,25 @@ only composed of alphanumeric characters (a-z A-Z 0-9), hyphens (-) and undersco
     NoSpaceLeftOnDevice,
     #[error("Index already has a primary key: `{0}`.")]
     PrimaryKeyCannotBeChanged(String),
+    #[error("The primary key of the document with the id: `{document_id}` can't be modified by an edition.")]
+    PrimaryKeyCannotBeEdited { document_id: Value },
     #[error(transparent)]
     SerdeJson(serde_json::Error),
     #[error(transparent)]
     SortError(#[from] SortError),
+    #[error("Unsupported dump version `{version}`, the supported version is `{supported}`.")]
+    UnsupportedDumpVersion { version: u32, supported: u32 },
     #[error("An unknown internal document id have been used: `{document_id}`.")]
     UnknownInternalDocumentId { document_id: DocumentId },
     #[error("`minWordSizeForTypos` setting is invalid. `oneTypo` and `twoTypos` fields should be between `0` and `255`, and `twoTypos` should be greater or equals to `oneTypo` but found `oneTypo: {0}` and twoTypos: {1}`.")]
     InvalidMinTypoWordLenSetting(u8, u8),
+    #[error("The weight of the `{0}` attribute is invalid. The weights of the attributes should be greater than `0`.")]
+    InvalidAttributeWeight(String),
 }
 
 #[derive(Error, Debug)]
 pub enum GeoError {
-    #[error("The `_geo` field in the document with the id: `{document_id}` is not an object. Was expecting an object with the `_geo.lat` and `_geo.lng` fields but instead got `{value}`.")]
+    #[error("The `_geo` field in the document with the id: `{document_id}` is not an object nor an array of objects. Was expecting objects with the `_geo.lat` and `_geo.lng` fields but instead got `{value}`.")]
     NotAnObject { document_id: Value, value: Value },
     #[error("Could not find latitude nor longitude in the document with the id: `{document_id}`. Was expecting `_geo.lat` and `_geo.lng` fields.")]
     MissingLatitudeAndLongitude { document_id: Value },
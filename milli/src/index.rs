@@ -1,9 +1,11 @@
 use std::borrow::Cow;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs::File;
 use std::mem::size_of;
 use std::path::Path;
+use std::sync::{Arc, RwLock};
 
+use fst::Streamer;
 use heed::flags::Flags;
 use heed::types::*;
 use heed::{CompactionOption, Database, PolyDatabase, RoTxn, RwTxn};
@@ -19,15 +21,17 @@ use crate::heed_codec::facet::{
 };
 use crate::{
     default_criteria, BEU32StrCodec, BoRoaringBitmapCodec, CboRoaringBitmapCodec, Criterion,
-    DocumentId, ExternalDocumentsIds, FacetDistribution, FieldDistribution, FieldId,
-    FieldIdWordCountCodec, GeoPoint, ObkvCodec, Result, RoaringBitmapCodec, RoaringBitmapLenCodec,
-    Search, StrBEU32Codec, U8StrStrCodec, BEU16, BEU32,
+    CriterionError, CustomRankingRule, DocumentId, ExternalDocumentsIds, FacetDistribution,
+    FieldDistribution, FieldId, FieldIdWordCountCodec, GeoPoint, ObkvCodec, Result,
+    RoaringBitmapCodec, RoaringBitmapLenCodec, Search, StrBEU32Codec, SynonymGroup, U8StrStrCodec,
+    BEU16, BEU32,
 };
 
 pub const DEFAULT_MIN_WORD_LEN_ONE_TYPO: u8 = 5;
 pub const DEFAULT_MIN_WORD_LEN_TWO_TYPOS: u8 = 9;
 
 pub mod main_key {
+    pub const ATTRIBUTE_WEIGHTS_KEY: &str = "attribute-weights";
     pub const CRITERIA_KEY: &str = "criteria";
     pub const DISPLAYED_FIELDS_KEY: &str = "displayed-fields";
     pub const DISTINCT_FIELD_KEY: &str = "distinct-field-key";
@@ -48,7 +52,12 @@ pub mod main_key {
     pub const SOFT_EXTERNAL_DOCUMENTS_IDS_KEY: &str = "soft-external-documents-ids";
     pub const STOP_WORDS_KEY: &str = "stop-words";
     pub const STRING_FACETED_DOCUMENTS_IDS_PREFIX: &str = "string-faceted-documents-ids";
+    pub const STRING_FACETED_VALUES_FST_PREFIX: &str = "string-faceted-values-fst";
+    pub const SYNONYM_GROUPS_KEY: &str = "synonym-groups";
+    /// The synonyms written by the previous versions of the engine, they are
+    /// read until the synonyms are updated and then replaced by the synonym groups.
     pub const SYNONYMS_KEY: &str = "synonyms";
+    pub const SYNONYMS_FST_KEY: &str = "synonyms-fst";
     pub const WORDS_FST_KEY: &str = "words-fst";
     pub const WORDS_PREFIXES_FST_KEY: &str = "words-prefixes-fst";
     pub const CREATED_AT_KEY: &str = "created-at";
@@ -81,6 +90,8 @@ pub mod db_name {
     pub const FIELD_ID_DOCID_FACET_F64S: &str = "field-id-docid-facet-f64s";
     pub const FIELD_ID_DOCID_FACET_STRINGS: &str = "field-id-docid-facet-strings";
     pub const DOCUMENTS: &str = "documents";
+    pub const VECTORS: &str = "vectors";
+    pub const SYNONYM_SETS: &str = "synonym-sets";
 }
 
 #[derive(Clone)]
@@ -135,6 +146,15 @@ pub struct Index {
 
     /// Maps the document id to the document as an obkv store.
     pub(crate) documents: Database<OwnedType<BEU32>, ObkvCodec>,
+
+    /// Maps the document id to the vectors of its `_vectors` field.
+    pub vectors: Database<OwnedType<BEU32>, SerdeBincode<Vec<Vec<f32>>>>,
+
+    /// Maps the synonym set id to the phrases, split into words, of this set.
+    pub(crate) synonym_sets: Database<OwnedType<BEU32>, SerdeBincode<Vec<Vec<String>>>>,
+
+    /// The ranking rules implemented outside of the engine, they are not persisted.
+    pub(crate) custom_ranking_rules: Arc<RwLock<HashMap<String, Arc<dyn CustomRankingRule>>>>,
 }
 
 impl Index {
@@ -146,7 +166,7 @@ impl Index {
     ) -> Result<Index> {
         use db_name::*;
 
-        options.max_dbs(18);
+        options.max_dbs(20);
         unsafe { options.flag(Flags::MdbAlwaysFreePages) };
 
         let env = options.open(path)?;
@@ -172,6 +192,8 @@ impl Index {
         let field_id_docid_facet_strings =
             env.create_database(Some(FIELD_ID_DOCID_FACET_STRINGS))?;
         let documents = env.create_database(Some(DOCUMENTS))?;
+        let vectors = env.create_database(Some(VECTORS))?;
+        let synonym_sets = env.create_database(Some(SYNONYM_SETS))?;
 
         Index::set_creation_dates(&env, main, created_at, updated_at)?;
 
@@ -195,6 +217,9 @@ impl Index {
             field_id_docid_facet_f64s,
             field_id_docid_facet_strings,
             documents,
+            vectors,
+            synonym_sets,
+            custom_ranking_rules: Arc::default(),
         })
     }
 
@@ -253,6 +278,8 @@ impl Index {
         Ok(self.env.real_disk_size()?)
     }
 
+    /// Copies the raw database file, it can only be opened by the same version of the engine,
+    /// see the [`dump`](crate::dump) module to move an index to another version.
     pub fn copy_to_path<P: AsRef<Path>>(&self, path: P, option: CompactionOption) -> Result<File> {
         self.env.copy_to_path(path, option).map_err(Into::into)
     }
@@ -455,6 +482,25 @@ impl Index {
         }
     }
 
+    /* vectors */
+
+    /// Returns the number of dimensions of the vectors of the documents, the one of the first
+    /// vector of a document that is neither soft deleted nor excluded. All the vectors must have it.
+    pub(crate) fn vector_dimensions(
+        &self,
+        rtxn: &RoTxn,
+        excluded: &RoaringBitmap,
+    ) -> Result<Option<usize>> {
+        let excluded = self.soft_deleted_documents_ids(rtxn)? | excluded;
+        for result in self.vectors.iter(rtxn)? {
+            let (docid, vectors) = result?;
+            if !excluded.contains(docid.get()) {
+                return Ok(vectors.first().map(Vec::len));
+            }
+        }
+        Ok(None)
+    }
+
     /* field distribution */
 
     /// Writes the field distribution which associates every field name with
@@ -629,6 +675,51 @@ impl Index {
             .get::<_, Str, SerdeBincode<Vec<_>>>(rtxn, main_key::USER_DEFINED_SEARCHABLE_FIELDS_KEY)
     }
 
+    /* attribute weights */
+
+    /// Writes the weights of the searchable attributes.
+    pub(crate) fn put_attribute_weights(
+        &self,
+        wtxn: &mut RwTxn,
+        weights: &BTreeMap<String, u16>,
+    ) -> heed::Result<()> {
+        self.main.put::<_, Str, SerdeBincode<_>>(wtxn, main_key::ATTRIBUTE_WEIGHTS_KEY, weights)
+    }
+
+    pub(crate) fn delete_attribute_weights(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
+        self.main.delete::<_, Str>(wtxn, main_key::ATTRIBUTE_WEIGHTS_KEY)
+    }
+
+    /// Returns the weights of the searchable attributes, the attribute
+    /// ranking rule uses the order of the searchable attributes when empty.
+    pub fn attribute_weights(&self, rtxn: &RoTxn) -> heed::Result<BTreeMap<String, u16>> {
+        Ok(self
+            .main
+            .get::<_, Str, SerdeBincode<_>>(rtxn, main_key::ATTRIBUTE_WEIGHTS_KEY)?
+            .unwrap_or_default())
+    }
+
+    /// Identical to `attribute_weights`, but returns the weights of the fields ids, the nested
+    /// fields of an attribute share its weight unless they have their own.
+    pub fn attribute_weights_ids(&self, rtxn: &RoTxn) -> Result<HashMap<FieldId, u16>> {
+        let weights = self.attribute_weights(rtxn)?;
+        if weights.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let fields_ids_map = self.fields_ids_map(rtxn)?;
+        Ok(fields_ids_map
+            .iter()
+            .filter_map(|(field_id, name)| {
+                weights
+                    .iter()
+                    .filter(|(attribute, _)| crate::is_faceted_by(name, attribute))
+                    .max_by_key(|(attribute, _)| attribute.len())
+                    .map(|(_, &weight)| (field_id, weight))
+            })
+            .collect())
+    }
+
     /* filterable fields */
 
     /// Writes the filterable fields names in the database.
@@ -843,6 +934,44 @@ impl Index {
         }
     }
 
+    /// Writes the FST of the normalized string values of this field id.
+    pub(crate) fn put_string_faceted_values_fst<A: AsRef<[u8]>>(
+        &self,
+        wtxn: &mut RwTxn,
+        field_id: FieldId,
+        fst: &fst::Set<A>,
+    ) -> heed::Result<()> {
+        let mut buffer =
+            [0u8; main_key::STRING_FACETED_VALUES_FST_PREFIX.len() + size_of::<FieldId>()];
+        buffer[..main_key::STRING_FACETED_VALUES_FST_PREFIX.len()]
+            .copy_from_slice(main_key::STRING_FACETED_VALUES_FST_PREFIX.as_bytes());
+        buffer[main_key::STRING_FACETED_VALUES_FST_PREFIX.len()..]
+            .copy_from_slice(&field_id.to_be_bytes());
+        self.main.put::<_, ByteSlice, ByteSlice>(wtxn, &buffer, fst.as_fst().as_bytes())
+    }
+
+    /// Returns the FST of the normalized string values of this field id, it is `None` when
+    /// the facets of the field were written by a version of the engine that didn't store it.
+    ///
+    /// The values of the soft deleted documents are still part of the FST
+    /// until these documents are really deleted.
+    pub fn string_faceted_values_fst<'t>(
+        &self,
+        rtxn: &'t RoTxn,
+        field_id: FieldId,
+    ) -> Result<Option<fst::Set<Cow<'t, [u8]>>>> {
+        let mut buffer =
+            [0u8; main_key::STRING_FACETED_VALUES_FST_PREFIX.len() + size_of::<FieldId>()];
+        buffer[..main_key::STRING_FACETED_VALUES_FST_PREFIX.len()]
+            .copy_from_slice(main_key::STRING_FACETED_VALUES_FST_PREFIX.as_bytes());
+        buffer[main_key::STRING_FACETED_VALUES_FST_PREFIX.len()..]
+            .copy_from_slice(&field_id.to_be_bytes());
+        match self.main.get::<_, ByteSlice, ByteSlice>(rtxn, &buffer)? {
+            Some(bytes) => Ok(Some(fst::Set::new(bytes)?.map_data(Cow::Borrowed)?)),
+            None => Ok(None),
+        }
+    }
+
     /// Retrieve all the documents which contain this field id
     pub fn exists_faceted_documents_ids(
         &self,
@@ -894,6 +1023,32 @@ impl Index {
         }
     }
 
+    /// Registers a ranking rule under the given name, it can then be used in the ranking rules
+    /// settings of this index, as `<name>` or `custom:<name>`. The names that can be mistaken
+    /// for a built-in ranking rule, e.g. `words` or `price:asc`, are rejected.
+    ///
+    /// The registered rules are not persisted, they must be registered again every time the
+    /// index is opened, before searching it or importing a dump that uses them, searching
+    /// with an unregistered ranking rule returns an error.
+    pub fn register_ranking_rule(
+        &self,
+        name: impl Into<String>,
+        rule: impl CustomRankingRule + 'static,
+    ) -> Result<()> {
+        let name = name.into();
+        if !Criterion::is_valid_custom_name(&name) {
+            return Err(CriterionError::InvalidCustomName { name }.into());
+        }
+        let mut rules = self.custom_ranking_rules.write().unwrap();
+        rules.insert(name, Arc::new(rule));
+        Ok(())
+    }
+
+    /// Returns the ranking rule registered under the given name.
+    pub fn custom_ranking_rule(&self, name: &str) -> Option<Arc<dyn CustomRankingRule>> {
+        self.custom_ranking_rules.read().unwrap().get(name).cloned()
+    }
+
     /* words fst */
 
     /// Writes the FST which is the words dictionary of the engine.
@@ -936,32 +1091,137 @@ impl Index {
 
     /* synonyms */
 
-    pub(crate) fn put_synonyms(
+    /// Writes the synonym groups as defined in the settings along with the FST mapping the
+    /// normalized input phrases to the ids of the synonym sets they are expanded into.
+    pub(crate) fn put_synonyms<A: AsRef<[u8]>>(
         &self,
         wtxn: &mut RwTxn,
-        synonyms: &HashMap<Vec<String>, Vec<Vec<String>>>,
+        groups: &[SynonymGroup],
+        fst: &fst::Map<A>,
+        sets: &[Vec<Vec<String>>],
     ) -> heed::Result<()> {
-        self.main.put::<_, Str, SerdeBincode<_>>(wtxn, main_key::SYNONYMS_KEY, synonyms)
+        self.main.put::<_, Str, SerdeJson<&[SynonymGroup]>>(
+            wtxn,
+            main_key::SYNONYM_GROUPS_KEY,
+            &groups,
+        )?;
+        self.main.put::<_, Str, ByteSlice>(
+            wtxn,
+            main_key::SYNONYMS_FST_KEY,
+            fst.as_fst().as_bytes(),
+        )?;
+        self.synonym_sets.clear(wtxn)?;
+        for (id, set) in sets.iter().enumerate() {
+            self.synonym_sets.put(wtxn, &BEU32::new(id as u32), set)?;
+        }
+        self.main.delete::<_, Str>(wtxn, main_key::SYNONYMS_KEY)?;
+        Ok(())
     }
 
     pub(crate) fn delete_synonyms(&self, wtxn: &mut RwTxn) -> heed::Result<bool> {
-        self.main.delete::<_, Str>(wtxn, main_key::SYNONYMS_KEY)
+        let legacy_deleted = self.main.delete::<_, Str>(wtxn, main_key::SYNONYMS_KEY)?;
+        self.main.delete::<_, Str>(wtxn, main_key::SYNONYMS_FST_KEY)?;
+        self.synonym_sets.clear(wtxn)?;
+        let deleted = self.main.delete::<_, Str>(wtxn, main_key::SYNONYM_GROUPS_KEY)?;
+        Ok(deleted || legacy_deleted)
     }
 
-    pub fn synonyms(&self, rtxn: &RoTxn) -> heed::Result<HashMap<Vec<String>, Vec<Vec<String>>>> {
-        Ok(self
+    /// Returns the synonym groups as they were defined in the settings.
+    ///
+    /// The synonyms written by the previous versions of the engine are returned
+    /// as one-way groups, each normalized input being expanded into its synonyms.
+    pub fn synonym_groups(&self, rtxn: &RoTxn) -> heed::Result<Vec<SynonymGroup>> {
+        let groups = self
             .main
-            .get::<_, Str, SerdeBincode<_>>(rtxn, main_key::SYNONYMS_KEY)?
-            .unwrap_or_default())
+            .get::<_, Str, SerdeJson<Vec<SynonymGroup>>>(rtxn, main_key::SYNONYM_GROUPS_KEY)?;
+        match groups {
+            Some(groups) => Ok(groups),
+            None => Ok(self
+                .legacy_synonyms(rtxn)?
+                .unwrap_or_default()
+                .into_iter()
+                .collect::<BTreeMap<_, _>>()
+                .into_iter()
+                .map(|(input, synonyms)| SynonymGroup::OneWay {
+                    input: input.join(" "),
+                    synonyms: synonyms.iter().map(|words| words.join(" ")).collect(),
+                })
+                .collect()),
+        }
     }
 
+    /// Returns the synonyms written by the previous versions of the engine, if any.
+    fn legacy_synonyms(
+        &self,
+        rtxn: &RoTxn,
+    ) -> heed::Result<Option<HashMap<Vec<String>, Vec<Vec<String>>>>> {
+        self.main.get::<_, Str, SerdeBincode<_>>(rtxn, main_key::SYNONYMS_KEY)
+    }
+
+    /// Returns the FST mapping the normalized input phrases, their words joined by a space,
+    /// to the ids of the synonym sets they are expanded into.
+    pub fn synonyms_fst<'t>(&self, rtxn: &'t RoTxn) -> Result<fst::Map<Cow<'t, [u8]>>> {
+        match self.main.get::<_, Str, ByteSlice>(rtxn, main_key::SYNONYMS_FST_KEY)? {
+            Some(bytes) => Ok(fst::Map::new(bytes)?.map_data(Cow::Borrowed)?),
+            None => Ok(fst::Map::default().map_data(Cow::Owned)?),
+        }
+    }
+
+    /// Returns every normalized input phrase with the phrases it is expanded into.
+    pub fn synonyms(&self, rtxn: &RoTxn) -> Result<HashMap<Vec<String>, Vec<Vec<String>>>> {
+        if let Some(synonyms) = self.legacy_synonyms(rtxn)? {
+            return Ok(synonyms);
+        }
+
+        let fst = self.synonyms_fst(rtxn)?;
+        let mut synonyms = HashMap::new();
+        let mut stream = fst.stream();
+        while let Some((input, set_id)) = stream.next() {
+            let words: Vec<_> = std::str::from_utf8(input)?.split(' ').map(String::from).collect();
+            if let Some(expansions) = self.synonym_set_expansions(rtxn, set_id, &words)? {
+                synonyms.insert(words, expansions);
+            }
+        }
+        Ok(synonyms)
+    }
+
+    /// Returns the phrases the given words are expanded into, if any.
     pub fn words_synonyms<S: AsRef<str>>(
         &self,
         rtxn: &RoTxn,
         words: &[S],
     ) -> heed::Result<Option<Vec<Vec<String>>>> {
-        let words: Vec<_> = words.iter().map(|s| s.as_ref().to_owned()).collect();
-        Ok(self.synonyms(rtxn)?.remove(&words))
+        let bytes = match self.main.get::<_, Str, ByteSlice>(rtxn, main_key::SYNONYMS_FST_KEY)? {
+            Some(bytes) => bytes,
+            None => {
+                let words: Vec<_> = words.iter().map(|s| s.as_ref().to_string()).collect();
+                return Ok(self.legacy_synonyms(rtxn)?.and_then(|mut map| map.remove(&words)));
+            }
+        };
+        let fst = fst::Map::new(bytes).map_err(|_| heed::Error::Decoding)?;
+        let words: Vec<_> = words.iter().map(|s| s.as_ref()).collect();
+        match fst.get(words.join(" ")) {
+            Some(set_id) => self.synonym_set_expansions(rtxn, set_id, &words),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the phrases of the synonym set without the phrase that is being expanded.
+    fn synonym_set_expansions<S: AsRef<str>>(
+        &self,
+        rtxn: &RoTxn,
+        set_id: u64,
+        words: &[S],
+    ) -> heed::Result<Option<Vec<Vec<String>>>> {
+        let mut set = match self.synonym_sets.get(rtxn, &BEU32::new(set_id as u32))? {
+            Some(set) => set,
+            None => return Ok(None),
+        };
+        set.retain(|phrase| {
+            phrase.len() != words.len()
+                || phrase.iter().zip(words).any(|(a, b)| a.as_str() != b.as_ref())
+        });
+        Ok(if set.is_empty() { None } else { Some(set) })
     }
 
     /* words prefixes fst */
@@ -3,6 +3,7 @@ pub mod documents;
 
 mod asc_desc;
 mod criterion;
+pub mod dump;
 mod error;
 mod external_documents_ids;
 pub mod facet;
@@ -11,6 +12,7 @@ pub mod heed_codec;
 pub mod index;
 pub mod proximity;
 mod search;
+mod synonyms;
 pub mod update;
 
 #[cfg(test)]
@@ -41,9 +43,12 @@ pub use self::heed_codec::{
 };
 pub use self::index::Index;
 pub use self::search::{
-    FacetDistribution, Filter, FormatOptions, MatchBounds, MatcherBuilder, MatchingWord,
-    MatchingWords, Search, SearchResult, TermsMatchingStrategy, DEFAULT_VALUES_PER_FACET,
+    score_details, CustomRankingRule, FacetDistribution, FacetSearch, FacetValueHit, FederatedHit,
+    FederatedSearchResult, Filter, FormatOptions, MatchBounds, MatcherBuilder, MatchingWord,
+    MatchingWords, MultiSearch, OrderBy, ScoreDetails, Search, SearchResult, TermsMatchingStrategy,
+    VectorSimilarity, DEFAULT_VALUES_PER_FACET,
 };
+pub use self::synonyms::SynonymGroup;
 
 pub type Result<T> = std::result::Result<T, error::Error>;
 
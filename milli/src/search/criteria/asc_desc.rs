@@ -4,11 +4,13 @@ use itertools::Itertools;
 use log::debug;
 use ordered_float::OrderedFloat;
 use roaring::RoaringBitmap;
+use serde_json::Value;
 
 use super::{Criterion, CriterionParameters, CriterionResult};
-use crate::search::criteria::{resolve_query_tree, CriteriaBuilder};
+use crate::search::criteria::{resolve_query_tree, Context};
 use crate::search::facet::{FacetNumberIter, FacetStringIter};
 use crate::search::query_tree::Operation;
+use crate::search::score_details::{self, ScoreDetails};
 use crate::{FieldId, Index, Result};
 
 /// Threshold on the number of candidates that will make
@@ -16,39 +18,44 @@ use crate::{FieldId, Index, Result};
 const CANDIDATES_THRESHOLD: u64 = 1000;
 
 pub struct AscDesc<'t> {
+    ctx: &'t dyn Context<'t>,
     index: &'t Index,
     rtxn: &'t heed::RoTxn<'t>,
     field_name: String,
     field_id: Option<FieldId>,
     is_ascending: bool,
     query_tree: Option<Operation>,
-    candidates: Box<dyn Iterator<Item = heed::Result<RoaringBitmap>> + 't>,
+    candidates: Box<dyn Iterator<Item = heed::Result<(Value, RoaringBitmap)>> + 't>,
     allowed_candidates: RoaringBitmap,
     bucket_candidates: RoaringBitmap,
     faceted_candidates: RoaringBitmap,
     parent: Box<dyn Criterion + 't>,
+    score_details: Vec<ScoreDetails>,
 }
 
 impl<'t> AscDesc<'t> {
     pub fn asc(
+        ctx: &'t dyn Context<'t>,
         index: &'t Index,
         rtxn: &'t heed::RoTxn,
         parent: Box<dyn Criterion + 't>,
         field_name: String,
     ) -> Result<Self> {
-        Self::new(index, rtxn, parent, field_name, true)
+        Self::new(ctx, index, rtxn, parent, field_name, true)
     }
 
     pub fn desc(
+        ctx: &'t dyn Context<'t>,
         index: &'t Index,
         rtxn: &'t heed::RoTxn,
         parent: Box<dyn Criterion + 't>,
         field_name: String,
     ) -> Result<Self> {
-        Self::new(index, rtxn, parent, field_name, false)
+        Self::new(ctx, index, rtxn, parent, field_name, false)
     }
 
     fn new(
+        ctx: &'t dyn Context<'t>,
         index: &'t Index,
         rtxn: &'t heed::RoTxn,
         parent: Box<dyn Criterion + 't>,
@@ -67,6 +74,7 @@ impl<'t> AscDesc<'t> {
         };
 
         Ok(AscDesc {
+            ctx,
             index,
             rtxn,
             field_name,
@@ -78,8 +86,20 @@ impl<'t> AscDesc<'t> {
             faceted_candidates,
             bucket_candidates: RoaringBitmap::new(),
             parent,
+            score_details: Vec::new(),
         })
     }
+
+    /// Returns the score details of a bucket containing the documents with the given value.
+    fn bucket_score_details(&self, value: Value) -> Vec<ScoreDetails> {
+        let mut score_details = self.score_details.clone();
+        score_details.push(ScoreDetails::Sort(score_details::Sort {
+            field_name: self.field_name.clone(),
+            ascending: self.is_ascending,
+            value,
+        }));
+        score_details
+    }
 }
 
 impl<'t> Criterion for AscDesc<'t> {
@@ -97,11 +117,13 @@ impl<'t> Criterion for AscDesc<'t> {
 
             match self.candidates.next().transpose()? {
                 None if !self.allowed_candidates.is_empty() => {
+                    // the remaining candidates don't have any value for this field.
                     return Ok(Some(CriterionResult {
                         query_tree: self.query_tree.clone(),
                         candidates: Some(take(&mut self.allowed_candidates)),
                         filtered_candidates: None,
                         bucket_candidates: Some(take(&mut self.bucket_candidates)),
+                        score_details: self.bucket_score_details(Value::Null),
                     }));
                 }
                 None => match self.parent.next(params)? {
@@ -110,14 +132,13 @@ impl<'t> Criterion for AscDesc<'t> {
                         candidates,
                         filtered_candidates,
                         bucket_candidates,
+                        score_details,
                     }) => {
                         self.query_tree = query_tree;
+                        self.score_details = score_details;
                         let mut candidates = match (&self.query_tree, candidates) {
                             (_, Some(candidates)) => candidates,
-                            (Some(qt), None) => {
-                                let context = CriteriaBuilder::new(&self.rtxn, &self.index)?;
-                                resolve_query_tree(&context, qt, params.wdcache)?
-                            }
+                            (Some(qt), None) => resolve_query_tree(self.ctx, qt, params.wdcache)?,
                             (None, None) => self.index.documents_ids(self.rtxn)?,
                         };
 
@@ -148,7 +169,7 @@ impl<'t> Criterion for AscDesc<'t> {
                     }
                     None => return Ok(None),
                 },
-                Some(mut candidates) => {
+                Some((value, mut candidates)) => {
                     candidates -= params.excluded_candidates;
                     self.allowed_candidates -= &candidates;
                     return Ok(Some(CriterionResult {
@@ -156,6 +177,7 @@ impl<'t> Criterion for AscDesc<'t> {
                         candidates: Some(candidates),
                         filtered_candidates: None,
                         bucket_candidates: Some(take(&mut self.bucket_candidates)),
+                        score_details: self.bucket_score_details(value),
                     }));
                 }
             }
@@ -163,7 +185,8 @@ impl<'t> Criterion for AscDesc<'t> {
     }
 }
 
-/// Returns an iterator over groups of the given candidates in ascending or descending order.
+/// Returns an iterator over groups of the given candidates in ascending or descending order,
+/// along with the facet value shared by each group.
 ///
 /// It will either use an iterative or a recursive method on the whole facet database depending
 /// on the number of candidates to rank.
@@ -173,7 +196,7 @@ fn facet_ordered<'t>(
     field_id: FieldId,
     is_ascending: bool,
     candidates: RoaringBitmap,
-) -> Result<Box<dyn Iterator<Item = heed::Result<RoaringBitmap>> + 't>> {
+) -> Result<Box<dyn Iterator<Item = heed::Result<(Value, RoaringBitmap)>> + 't>> {
     if candidates.len() <= CANDIDATES_THRESHOLD {
         let number_iter = iterative_facet_number_ordered_iter(
             index,
@@ -192,7 +215,7 @@ fn facet_ordered<'t>(
             FacetNumberIter::new_reverse_reducing
         };
         let number_iter = facet_number_fn(rtxn, index, field_id, candidates.clone())?
-            .map(|res| res.map(|(_, docids)| docids));
+            .map(|res| res.map(|(value, docids)| (number_value(value), docids)));
 
         let facet_string_fn = if is_ascending {
             FacetStringIter::new_reducing
@@ -200,7 +223,7 @@ fn facet_ordered<'t>(
             FacetStringIter::new_reverse_reducing
         };
         let string_iter = facet_string_fn(rtxn, index, field_id, candidates)?
-            .map(|res| res.map(|(_, _, docids)| docids));
+            .map(|res| res.map(|(_, original, docids)| (Value::from(original), docids)));
 
         Ok(Box::new(number_iter.chain(string_iter)))
     }
@@ -215,7 +238,7 @@ fn iterative_facet_number_ordered_iter<'t>(
     field_id: FieldId,
     is_ascending: bool,
     candidates: RoaringBitmap,
-) -> Result<impl Iterator<Item = RoaringBitmap> + 't> {
+) -> Result<impl Iterator<Item = (Value, RoaringBitmap)> + 't> {
     let mut docids_values = Vec::with_capacity(candidates.len() as usize);
     for docid in candidates.iter() {
         let left = (field_id, docid, f64::MIN);
@@ -240,7 +263,7 @@ fn iterative_facet_number_ordered_iter<'t>(
     let vec: Vec<_> = iter
         .group_by(|(_, v)| *v)
         .into_iter()
-        .map(|(_, ids)| ids.map(|(id, _)| id).collect())
+        .map(|(value, ids)| (number_value(value.0), ids.map(|(id, _)| id).collect()))
         .collect();
 
     Ok(vec.into_iter())
@@ -255,7 +278,7 @@ fn iterative_facet_string_ordered_iter<'t>(
     field_id: FieldId,
     is_ascending: bool,
     candidates: RoaringBitmap,
-) -> Result<impl Iterator<Item = RoaringBitmap> + 't> {
+) -> Result<impl Iterator<Item = (Value, RoaringBitmap)> + 't> {
     let mut docids_values = Vec::with_capacity(candidates.len() as usize);
     for docid in candidates.iter() {
         let left = (field_id, docid, "");
@@ -264,11 +287,11 @@ fn iterative_facet_string_ordered_iter<'t>(
         //       the document with id 2^32, not sure this is a real problem.
         let mut iter = index.field_id_docid_facet_strings.range(rtxn, &(left..right))?;
         let entry = if is_ascending { iter.next() } else { iter.last() };
-        if let Some(((_, _, value), _)) = entry.transpose()? {
-            docids_values.push((docid, value));
+        if let Some(((_, _, value), original)) = entry.transpose()? {
+            docids_values.push((docid, value, original));
         }
     }
-    docids_values.sort_unstable_by_key(|(_, v)| *v);
+    docids_values.sort_unstable_by_key(|(_, v, _)| *v);
     let iter = docids_values.into_iter();
     let iter = if is_ascending {
         Box::new(iter) as Box<dyn Iterator<Item = _>>
@@ -280,10 +303,20 @@ fn iterative_facet_string_ordered_iter<'t>(
     // required to collect the result into an owned collection (a Vec).
     // https://github.com/rust-itertools/itertools/issues/499
     let vec: Vec<_> = iter
-        .group_by(|(_, v)| *v)
+        .group_by(|(_, v, _)| *v)
         .into_iter()
-        .map(|(_, ids)| ids.map(|(id, _)| id).collect())
+        .map(|(_, mut ids)| {
+            // all the documents of this group share the same normalized value,
+            // we keep the original value of the first one.
+            let (first_id, _, original) = ids.next().unwrap();
+            let docids = std::iter::once(first_id).chain(ids.map(|(id, _, _)| id)).collect();
+            (Value::from(original), docids)
+        })
         .collect();
 
     Ok(vec.into_iter())
 }
+
+fn number_value(value: f64) -> Value {
+    serde_json::Number::from_f64(value).map_or(Value::Null, Value::Number)
+}
@@ -9,8 +9,9 @@ use roaring::RoaringBitmap;
 use super::{resolve_query_tree, Context, Criterion, CriterionParameters, CriterionResult};
 use crate::search::criteria::Query;
 use crate::search::query_tree::{Operation, QueryKind};
+use crate::search::score_details::{self, ScoreDetails};
 use crate::search::{build_dfa, word_derivations, WordDerivationsCache};
-use crate::Result;
+use crate::{relative_from_absolute_position, FieldId, Result};
 
 /// To be able to divide integers by the number of words in the query
 /// we want to find a multiplier that allow us to divide by any number between 1 and 10.
@@ -30,18 +31,70 @@ pub struct Attribute<'t> {
     parent: Box<dyn Criterion + 't>,
     linear_buckets: Option<btree_map::IntoIter<u64, RoaringBitmap>>,
     set_buckets: Option<BinaryHeap<Branch<'t>>>,
+    score_details: Vec<ScoreDetails>,
+    /// The maximum position the words of the current query tree have in the searched attributes.
+    max_position: Option<u32>,
+    /// The weights of the searchable attributes, if any.
+    weights: Option<AttributeWeights>,
+}
+
+/// The weights of the searchable attributes, when they are set the positions of the words are
+/// compared regardless of their attribute and the positions in an attribute with a weight `w`
+/// count `max_weight / w` times. The attributes without weight have a weight of `1`.
+struct AttributeWeights {
+    weights: HashMap<FieldId, u16>,
+    max_weight: u32,
+}
+
+impl AttributeWeights {
+    fn new(weights: HashMap<FieldId, u16>) -> Option<Self> {
+        let max_weight = weights.values().copied().max()?.max(1) as u32;
+        Some(AttributeWeights { weights, max_weight })
+    }
+
+    /// Returns the position of a word scaled by the weight of its attribute.
+    fn position(&self, absolute: u32) -> u32 {
+        let (field_id, relative) = relative_from_absolute_position(absolute);
+        let weight = self.weights.get(&field_id).copied().unwrap_or(1).max(1) as u32;
+        (relative as u32 + 1) * self.max_weight / weight - 1
+    }
+
+    /// Replaces the absolute positions by the weighted ones.
+    fn positions(&self, positions: &RoaringBitmap) -> RoaringBitmap {
+        positions.iter().map(|position| self.position(position)).collect()
+    }
 }
 
 impl<'t> Attribute<'t> {
-    pub fn new(ctx: &'t dyn Context<'t>, parent: Box<dyn Criterion + 't>) -> Self {
-        Attribute {
+    pub fn new(ctx: &'t dyn Context<'t>, parent: Box<dyn Criterion + 't>) -> Result<Self> {
+        Ok(Attribute {
             ctx,
             state: None,
             bucket_candidates: RoaringBitmap::new(),
             parent,
             linear_buckets: None,
             set_buckets: None,
-        }
+            score_details: Vec::new(),
+            max_position: None,
+            weights: AttributeWeights::new(ctx.attribute_weights()?),
+        })
+    }
+
+    /// Returns the score details of a bucket with the given rank,
+    /// a bucket without rank is considered to be the worst one.
+    fn bucket_score_details(&self, rank: Option<u64>) -> Vec<ScoreDetails> {
+        let max_position = self.max_position.unwrap_or(u32::MAX);
+        let average_position = match rank {
+            Some(rank) => (rank / LCM_10_FIRST_NUMBERS as u64).min(max_position as u64) as u32,
+            None => max_position,
+        };
+
+        let mut score_details = self.score_details.clone();
+        score_details.push(ScoreDetails::Attribute(score_details::Attribute {
+            average_position,
+            max_position,
+        }));
+        score_details
     }
 }
 
@@ -61,10 +114,11 @@ impl<'t> Criterion for Attribute<'t> {
                         candidates: Some(RoaringBitmap::new()),
                         filtered_candidates: None,
                         bucket_candidates: Some(take(&mut self.bucket_candidates)),
+                        score_details: self.bucket_score_details(None),
                     }));
                 }
                 Some((query_tree, flattened_query_tree, mut allowed_candidates)) => {
-                    let found_candidates = if allowed_candidates.len() < CANDIDATES_THRESHOLD {
+                    let bucket = if allowed_candidates.len() < CANDIDATES_THRESHOLD {
                         let linear_buckets = match self.linear_buckets.as_mut() {
                             Some(linear_buckets) => linear_buckets,
                             None => {
@@ -72,19 +126,21 @@ impl<'t> Criterion for Attribute<'t> {
                                     self.ctx,
                                     &flattened_query_tree,
                                     &allowed_candidates,
+                                    self.weights.as_ref(),
                                 )?;
                                 self.linear_buckets.get_or_insert(new_buckets.into_iter())
                             }
                         };
 
                         match linear_buckets.next() {
-                            Some((_score, candidates)) => candidates,
+                            Some((rank, candidates)) => (rank, candidates),
                             None => {
                                 return Ok(Some(CriterionResult {
                                     query_tree: Some(query_tree),
                                     candidates: Some(RoaringBitmap::new()),
                                     filtered_candidates: None,
                                     bucket_candidates: Some(take(&mut self.bucket_candidates)),
+                                    score_details: self.bucket_score_details(None),
                                 }));
                             }
                         }
@@ -97,24 +153,27 @@ impl<'t> Criterion for Attribute<'t> {
                                     &flattened_query_tree,
                                     &allowed_candidates,
                                     params.wdcache,
+                                    self.weights.as_ref(),
                                 )?;
                                 self.set_buckets.get_or_insert(new_buckets)
                             }
                         };
 
                         match set_compute_candidates(&mut set_buckets, &allowed_candidates)? {
-                            Some((_score, candidates)) => candidates,
+                            Some((rank, candidates)) => (rank as u64, candidates),
                             None => {
                                 return Ok(Some(CriterionResult {
                                     query_tree: Some(query_tree),
                                     candidates: Some(RoaringBitmap::new()),
                                     filtered_candidates: None,
                                     bucket_candidates: Some(take(&mut self.bucket_candidates)),
+                                    score_details: self.bucket_score_details(None),
                                 }));
                             }
                         }
                     };
 
+                    let (rank, found_candidates) = bucket;
                     allowed_candidates -= &found_candidates;
 
                     self.state =
@@ -125,6 +184,7 @@ impl<'t> Criterion for Attribute<'t> {
                         candidates: Some(found_candidates),
                         filtered_candidates: None,
                         bucket_candidates: Some(take(&mut self.bucket_candidates)),
+                        score_details: self.bucket_score_details(Some(rank)),
                     }));
                 }
                 None => match self.parent.next(params)? {
@@ -133,6 +193,7 @@ impl<'t> Criterion for Attribute<'t> {
                         candidates,
                         filtered_candidates,
                         bucket_candidates,
+                        score_details,
                     }) => {
                         let mut candidates = match candidates {
                             Some(candidates) => candidates,
@@ -153,7 +214,15 @@ impl<'t> Criterion for Attribute<'t> {
                             None => self.bucket_candidates |= &candidates,
                         }
 
+                        self.max_position = Some(max_position(
+                            self.ctx,
+                            &flattened_query_tree,
+                            params.wdcache,
+                            self.weights.as_ref(),
+                        )?);
+
                         self.state = Some((query_tree, flattened_query_tree, candidates));
+                        self.score_details = score_details;
                         self.linear_buckets = None;
                     }
                     Some(CriterionResult {
@@ -161,12 +230,14 @@ impl<'t> Criterion for Attribute<'t> {
                         candidates,
                         filtered_candidates,
                         bucket_candidates,
+                        score_details,
                     }) => {
                         return Ok(Some(CriterionResult {
                             query_tree: None,
                             candidates,
                             filtered_candidates,
                             bucket_candidates,
+                            score_details,
                         }));
                     }
                     None => return Ok(None),
@@ -188,39 +259,102 @@ impl<'t> QueryPositionIterator<'t> {
         ctx: &'t dyn Context<'t>,
         queries: &[Query],
         wdcache: &mut WordDerivationsCache,
+        weights: Option<&AttributeWeights>,
     ) -> Result<Self> {
+        let word_position_iterator = |word: &str, in_prefix_cache: bool| -> Result<_> {
+            let iter = ctx.word_position_iterator(word, in_prefix_cache)?;
+            match weights {
+                Some(weights) => Ok(weighted_position_iterator(iter, weights)?.peekable()),
+                None => Ok(iter.peekable()),
+            }
+        };
+
         let mut inner = Vec::with_capacity(queries.len());
         for query in queries {
-            let in_prefix_cache = query.prefix && ctx.in_prefix_cache(query.kind.word());
-            match &query.kind {
-                QueryKind::Exact { word, .. } => {
-                    if !query.prefix || in_prefix_cache {
-                        let word = query.kind.word();
-                        let iter = ctx.word_position_iterator(word, in_prefix_cache)?;
-                        inner.push(iter.peekable());
-                    } else {
-                        for (word, _) in word_derivations(&word, true, 0, ctx.words_fst(), wdcache)?
-                        {
-                            let iter = ctx.word_position_iterator(&word, in_prefix_cache)?;
-                            inner.push(iter.peekable());
-                        }
-                    }
-                }
-                QueryKind::Tolerant { typo, word } => {
-                    for (word, _) in
-                        word_derivations(&word, query.prefix, *typo, ctx.words_fst(), wdcache)?
-                    {
-                        let iter = ctx.word_position_iterator(&word, in_prefix_cache)?;
-                        inner.push(iter.peekable());
-                    }
-                }
-            };
+            for (word, in_prefix_cache) in query_words(ctx, query, wdcache)? {
+                inner.push(word_position_iterator(&word, in_prefix_cache)?);
+            }
         }
 
         Ok(Self { inner })
     }
 }
 
+/// Returns the words whose positions must be read to find the positions of a query,
+/// along with whether they must be read from the prefix cache.
+fn query_words(
+    ctx: &dyn Context,
+    query: &Query,
+    wdcache: &mut WordDerivationsCache,
+) -> Result<Vec<(String, bool)>> {
+    let in_prefix_cache = query.prefix && ctx.in_prefix_cache(query.kind.word());
+    let words = match &query.kind {
+        QueryKind::Exact { word, .. } => {
+            if !query.prefix || in_prefix_cache {
+                vec![(word.clone(), in_prefix_cache)]
+            } else {
+                word_derivations(&word, true, 0, ctx.words_fst(), wdcache)?
+                    .iter()
+                    .map(|(word, _)| (word.clone(), in_prefix_cache))
+                    .collect()
+            }
+        }
+        QueryKind::Tolerant { typo, word } => {
+            word_derivations(&word, query.prefix, *typo, ctx.words_fst(), wdcache)?
+                .iter()
+                .map(|(word, _)| (word.clone(), in_prefix_cache))
+                .collect()
+        }
+    };
+
+    Ok(words)
+}
+
+/// Returns the maximum position, scaled by the weights of the attributes if any, the words
+/// of the query tree have in the searched attributes. The average position of the words of
+/// a document can't be greater, it bounds the rank of the buckets.
+fn max_position(
+    ctx: &dyn Context,
+    flattened_query_tree: &FlattenedQueryTree,
+    wdcache: &mut WordDerivationsCache,
+    weights: Option<&AttributeWeights>,
+) -> Result<u32> {
+    let mut max_position = 0;
+    for query in flattened_query_tree.iter().flatten().flatten() {
+        for (word, in_prefix_cache) in query_words(ctx, query, wdcache)? {
+            for position in ctx.word_last_positions(&word, in_prefix_cache)? {
+                let position = match weights {
+                    Some(weights) => weights.position(position),
+                    None => position,
+                };
+                max_position = cmp::max(max_position, position);
+            }
+        }
+    }
+
+    Ok(max_position)
+}
+
+/// Returns the positions of a word ordered by their weighted position, the
+/// positions of the attributes with the same weighted position are merged.
+fn weighted_position_iterator<'t>(
+    iter: Box<dyn Iterator<Item = heed::Result<((&'t str, u32), RoaringBitmap)>> + 't>,
+    weights: &AttributeWeights,
+) -> heed::Result<Box<dyn Iterator<Item = heed::Result<((&'t str, u32), RoaringBitmap)>> + 't>> {
+    let mut positions = BTreeMap::new();
+    for result in iter {
+        let ((word, position), docids) = result?;
+        let (_, weighted_docids) = positions
+            .entry(weights.position(position))
+            .or_insert_with(|| (word, RoaringBitmap::new()));
+        *weighted_docids |= docids;
+    }
+
+    Ok(Box::new(
+        positions.into_iter().map(|(position, (word, docids))| Ok(((word, position), docids))),
+    ))
+}
+
 impl<'t> Iterator for QueryPositionIterator<'t> {
     type Item = heed::Result<(u32, RoaringBitmap)>;
 
@@ -273,10 +407,11 @@ impl<'t> Branch<'t> {
         flatten_branch: &[Vec<Query>],
         wdcache: &mut WordDerivationsCache,
         allowed_candidates: &RoaringBitmap,
+        weights: Option<&AttributeWeights>,
     ) -> Result<Self> {
         let mut query_level_iterator = Vec::new();
         for queries in flatten_branch {
-            let mut qli = QueryPositionIterator::new(ctx, queries, wdcache)?.peekable();
+            let mut qli = QueryPositionIterator::new(ctx, queries, wdcache, weights)?.peekable();
             let (pos, docids) = qli.next().transpose()?.unwrap_or((0, RoaringBitmap::new()));
             query_level_iterator.push((pos, docids & allowed_candidates, qli));
         }
@@ -396,10 +531,11 @@ fn initialize_set_buckets<'t>(
     branches: &FlattenedQueryTree,
     allowed_candidates: &RoaringBitmap,
     wdcache: &mut WordDerivationsCache,
+    weights: Option<&AttributeWeights>,
 ) -> Result<BinaryHeap<Branch<'t>>> {
     let mut heap = BinaryHeap::new();
     for flatten_branch in branches {
-        let branch = Branch::new(ctx, flatten_branch, wdcache, allowed_candidates)?;
+        let branch = Branch::new(ctx, flatten_branch, wdcache, allowed_candidates, weights)?;
         heap.push(branch);
     }
 
@@ -454,6 +590,7 @@ fn initialize_linear_buckets(
     ctx: &dyn Context,
     branches: &FlattenedQueryTree,
     allowed_candidates: &RoaringBitmap,
+    weights: Option<&AttributeWeights>,
 ) -> Result<BTreeMap<u64, RoaringBitmap>> {
     fn compute_candidate_rank(
         branches: &FlattenedQueryTree,
@@ -508,8 +645,11 @@ fn initialize_linear_buckets(
                 branch_rank.sort_unstable();
                 // because several words in same query can't match all a the position 0,
                 // we substract the word index to the position.
-                let branch_rank: u64 =
-                    branch_rank.into_iter().enumerate().map(|(i, r)| r - i as u64).sum();
+                let branch_rank: u64 = branch_rank
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, r)| r.saturating_sub(i as u64))
+                    .sum();
                 // here we do the means of the words of the branch
                 min_rank =
                     min_rank.min(branch_rank * LCM_10_FIRST_NUMBERS as u64 / branch_len as u64);
@@ -537,7 +677,12 @@ fn initialize_linear_buckets(
 
     let mut candidates = BTreeMap::new();
     for docid in allowed_candidates {
-        let words_positions = ctx.docid_words_positions(docid)?;
+        let mut words_positions = ctx.docid_words_positions(docid)?;
+        if let Some(weights) = weights {
+            for positions in words_positions.values_mut() {
+                *positions = weights.positions(positions);
+            }
+        }
         let rank = compute_candidate_rank(branches, words_positions);
         candidates.entry(rank).or_insert_with(RoaringBitmap::new).insert(docid);
     }
@@ -596,6 +741,7 @@ mod tests {
     use big_s::S;
 
     use super::*;
+    use crate::absolute_from_relative_position;
     use crate::search::criteria::QueryKind;
 
     #[test]
@@ -687,4 +833,151 @@ mod tests {
         ]
         "###);
     }
+
+    #[test]
+    fn weighted_positions() {
+        let weights = AttributeWeights::new(maplit::hashmap! { 1 => 3, 2 => 1 }).unwrap();
+        assert_eq!(weights.position(absolute_from_relative_position(1, 0)), 0);
+        assert_eq!(weights.position(absolute_from_relative_position(1, 5)), 5);
+        assert_eq!(weights.position(absolute_from_relative_position(2, 0)), 2);
+        // the attributes without weight have a weight of 1.
+        assert_eq!(weights.position(absolute_from_relative_position(3, 1)), 5);
+
+        assert!(AttributeWeights::new(HashMap::new()).is_none());
+    }
+
+    #[test]
+    fn attribute_weights() {
+        use crate::index::tests::TempIndex;
+        use crate::{Search, SearchResult};
+
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_searchable_fields(vec![S("title"), S("description")]);
+                settings.set_criteria(vec![S("attribute")]);
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "title": "a long story about a great many things and rust", "description": "nothing" },
+                { "id": 1, "title": "hello", "description": "rust is great" },
+            ]))
+            .unwrap();
+
+        let search = |index: &TempIndex| {
+            let rtxn = index.read_txn().unwrap();
+            let mut search = Search::new(&rtxn, index);
+            search.query("rust");
+            let SearchResult { documents_ids, .. } = search.execute().unwrap();
+            documents_ids
+        };
+
+        // without weights the order of the searchable attributes is a strict priority.
+        assert_eq!(search(&index), vec![0, 1]);
+
+        // a match at the start of the description beats a match at the end of the title.
+        index
+            .update_settings(|settings| {
+                settings.set_attribute_weights(
+                    maplit::btreemap! { S("title") => 2, S("description") => 1 },
+                );
+            })
+            .unwrap();
+        assert_eq!(search(&index), vec![1, 0]);
+
+        let error = index
+            .update_settings(|settings| {
+                settings.set_attribute_weights(maplit::btreemap! { S("title") => 0 });
+            })
+            .unwrap_err();
+        assert!(error.to_string().starts_with("The weight of the `title` attribute is invalid."));
+
+        // the weighted attributes must be searchable.
+        let error = index
+            .update_settings(|settings| {
+                settings.set_attribute_weights(maplit::btreemap! { S("id") => 2 });
+            })
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Attribute `id` is not searchable. Available searchable attributes are: `description, title`."
+        );
+
+        // the searchable attributes of the same update are taken into account.
+        index
+            .update_settings(|settings| {
+                settings.set_searchable_fields(vec![S("id"), S("title")]);
+                settings.set_attribute_weights(maplit::btreemap! { S("id") => 2 });
+            })
+            .unwrap();
+
+        // the weights of the attributes that are no longer searchable are dropped.
+        index
+            .update_settings(|settings| {
+                settings.set_searchable_fields(vec![S("title"), S("description")]);
+            })
+            .unwrap();
+        let rtxn = index.read_txn().unwrap();
+        assert!(index.attribute_weights(&rtxn).unwrap().is_empty());
+        drop(rtxn);
+
+        index
+            .update_settings(|settings| {
+                settings.set_searchable_fields(vec![S("id"), S("title")]);
+                settings.set_attribute_weights(maplit::btreemap! { S("id") => 2, S("title") => 1 });
+            })
+            .unwrap();
+        index
+            .update_settings(|settings| {
+                settings.set_searchable_fields(vec![S("title"), S("description")]);
+            })
+            .unwrap();
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.attribute_weights(&rtxn).unwrap(), maplit::btreemap! { S("title") => 1 });
+    }
+
+    #[test]
+    fn attribute_score_details() {
+        use crate::index::tests::TempIndex;
+        use crate::{Search, SearchResult};
+
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_searchable_fields(vec![S("title"), S("description")]);
+                settings.set_criteria(vec![S("attribute")]);
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "title": "rust", "description": "nothing" },
+                { "id": 1, "title": "hello", "description": "hello rust" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let mut search = Search::new(&rtxn, &index);
+        search.query("rust");
+        let SearchResult { documents_ids, document_scores, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![0, 1]);
+
+        let attributes: Vec<_> = document_scores
+            .iter()
+            .map(|details| match details[..] {
+                [ScoreDetails::Attribute(attribute)] => attribute,
+                _ => panic!("unexpected score details {:?}", details),
+            })
+            .collect();
+
+        // the rank is bounded by the positions the query words really have.
+        assert_eq!(attributes[0].average_position, 0);
+        assert_eq!(attributes[1].average_position, attributes[1].max_position);
+        assert_eq!(attributes[0].max_position, attributes[1].max_position);
+        assert_eq!(attributes[1].rank().rank, 1);
+    }
 }
@@ -0,0 +1,270 @@
+use std::mem::take;
+use std::sync::Arc;
+
+use log::debug;
+use roaring::RoaringBitmap;
+
+use super::{resolve_query_tree, Context, Criterion, CriterionParameters, CriterionResult};
+use crate::search::query_tree::Operation;
+use crate::search::score_details::{self, ScoreDetails};
+use crate::{Index, Result};
+
+/// A ranking rule implemented outside of the engine.
+///
+/// It must be registered on the index with [`Index::register_ranking_rule`] and can then
+/// be referenced by its name in the ranking rules settings, like the built-in rules.
+pub trait CustomRankingRule: Send + Sync {
+    /// Splits the candidates into buckets, from the best documents to the worst ones.
+    ///
+    /// The documents that are returned in many buckets are only kept in the first one
+    /// and the candidates that are not returned in any bucket are ranked last.
+    fn buckets(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: &Index,
+        candidates: &RoaringBitmap,
+    ) -> Result<Vec<RoaringBitmap>>;
+}
+
+pub struct Custom<'t> {
+    ctx: &'t dyn Context<'t>,
+    index: &'t Index,
+    rtxn: &'t heed::RoTxn<'t>,
+    name: String,
+    rule: Arc<dyn CustomRankingRule>,
+    query_tree: Option<Operation>,
+    buckets: std::vec::IntoIter<RoaringBitmap>,
+    max_rank: u32,
+    bucket_candidates: RoaringBitmap,
+    parent: Box<dyn Criterion + 't>,
+    score_details: Vec<ScoreDetails>,
+}
+
+impl<'t> Custom<'t> {
+    pub fn new(
+        ctx: &'t dyn Context<'t>,
+        index: &'t Index,
+        rtxn: &'t heed::RoTxn,
+        parent: Box<dyn Criterion + 't>,
+        name: String,
+        rule: Arc<dyn CustomRankingRule>,
+    ) -> Self {
+        Custom {
+            ctx,
+            index,
+            rtxn,
+            name,
+            rule,
+            query_tree: None,
+            buckets: Vec::new().into_iter(),
+            max_rank: 0,
+            bucket_candidates: RoaringBitmap::new(),
+            parent,
+            score_details: Vec::new(),
+        }
+    }
+
+    /// Asks the rule to split the candidates and makes sure the buckets are disjoint
+    /// and cover all the candidates.
+    fn compute_buckets(&self, mut candidates: RoaringBitmap) -> Result<Vec<RoaringBitmap>> {
+        let mut buckets = Vec::new();
+        for mut bucket in self.rule.buckets(self.rtxn, self.index, &candidates)? {
+            bucket &= &candidates;
+            candidates -= &bucket;
+            if !bucket.is_empty() {
+                buckets.push(bucket);
+            }
+        }
+
+        if !candidates.is_empty() {
+            buckets.push(candidates);
+        }
+
+        Ok(buckets)
+    }
+}
+
+impl<'t> Criterion for Custom<'t> {
+    #[logging_timer::time("Custom::{}")]
+    fn next(&mut self, params: &mut CriterionParameters) -> Result<Option<CriterionResult>> {
+        loop {
+            debug!("Custom({}) iteration", self.name);
+
+            match self.buckets.next() {
+                Some(mut candidates) => {
+                    let rank = self.buckets.len() as u32 + 1;
+                    candidates -= params.excluded_candidates;
+                    if candidates.is_empty() {
+                        continue;
+                    }
+
+                    let mut score_details = self.score_details.clone();
+                    score_details.push(ScoreDetails::Custom(score_details::Custom {
+                        name: self.name.clone(),
+                        rank,
+                        max_rank: self.max_rank,
+                    }));
+
+                    return Ok(Some(CriterionResult {
+                        query_tree: self.query_tree.clone(),
+                        candidates: Some(candidates),
+                        filtered_candidates: None,
+                        bucket_candidates: Some(take(&mut self.bucket_candidates)),
+                        score_details,
+                    }));
+                }
+                None => match self.parent.next(params)? {
+                    Some(CriterionResult {
+                        query_tree,
+                        candidates,
+                        filtered_candidates,
+                        bucket_candidates,
+                        score_details,
+                    }) => {
+                        self.query_tree = query_tree;
+                        self.score_details = score_details;
+                        let mut candidates = match (&self.query_tree, candidates) {
+                            (_, Some(candidates)) => candidates,
+                            (Some(qt), None) => resolve_query_tree(self.ctx, qt, params.wdcache)?,
+                            (None, None) => self.index.documents_ids(self.rtxn)?,
+                        };
+
+                        if let Some(filtered_candidates) = filtered_candidates {
+                            candidates &= filtered_candidates;
+                        }
+
+                        match bucket_candidates {
+                            Some(bucket_candidates) => self.bucket_candidates |= bucket_candidates,
+                            None => self.bucket_candidates |= &candidates,
+                        }
+
+                        candidates -= params.excluded_candidates;
+                        if candidates.is_empty() {
+                            continue;
+                        }
+
+                        let buckets = self.compute_buckets(candidates)?;
+                        self.max_rank = buckets.len() as u32;
+                        self.buckets = buckets.into_iter();
+                    }
+                    None => return Ok(None),
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+
+    use super::*;
+    use crate::index::tests::TempIndex;
+    use crate::{Criterion, Search, SearchResult};
+
+    /// Ranks the documents by decreasing popularity, by buckets of ten.
+    struct Popularity;
+
+    impl CustomRankingRule for Popularity {
+        fn buckets(
+            &self,
+            rtxn: &heed::RoTxn,
+            index: &Index,
+            candidates: &RoaringBitmap,
+        ) -> Result<Vec<RoaringBitmap>> {
+            let fid = index.fields_ids_map(rtxn)?.id("popularity").unwrap();
+            let mut buckets = vec![RoaringBitmap::new(); 10];
+            for (docid, obkv) in index.documents(rtxn, candidates)? {
+                let popularity: f64 = serde_json::from_slice(obkv.get(fid).unwrap()).unwrap();
+                let bucket = 9 - (popularity as usize / 10).min(9);
+                buckets[bucket].insert(docid);
+            }
+            Ok(buckets)
+        }
+    }
+
+    #[test]
+    fn custom_ranking_rule() {
+        let index = TempIndex::new();
+        index.register_ranking_rule("popularity", Popularity).unwrap();
+
+        index
+            .update_settings(|settings| settings.set_criteria(vec![S("words"), S("popularity")]))
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "name": "blue shoes", "popularity": 12 },
+                { "id": 1, "name": "red shoes", "popularity": 95 },
+                { "id": 2, "name": "red socks", "popularity": 40 },
+                { "id": 3, "name": "red shoes", "popularity": 44 },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(
+            index.criteria(&rtxn).unwrap(),
+            vec![Criterion::Words, Criterion::Custom(S("popularity"))]
+        );
+        drop(rtxn);
+
+        // the rules are written with their prefix, to be read back as settings.
+        let criteria: Vec<_> = index.criteria(&index.read_txn().unwrap()).unwrap();
+        let criteria: Vec<_> = criteria.iter().map(ToString::to_string).collect();
+        assert_eq!(criteria, vec![S("words"), S("custom:popularity")]);
+        index.update_settings(|settings| settings.set_criteria(criteria.clone())).unwrap();
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(
+            index.criteria(&rtxn).unwrap(),
+            vec![Criterion::Words, Criterion::Custom(S("popularity"))]
+        );
+
+        // the rule sorts the documents of each bucket of the previous rule.
+        let mut search = Search::new(&rtxn, &index);
+        search.query("red shoes");
+        let SearchResult { documents_ids, document_scores, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![1, 3, 2]);
+        assert_eq!(
+            document_scores[0][1],
+            ScoreDetails::Custom(score_details::Custom {
+                name: S("popularity"),
+                rank: 2,
+                max_rank: 2,
+            })
+        );
+    }
+
+    #[test]
+    fn unregistered_ranking_rule() {
+        let index = TempIndex::new();
+
+        let error = index
+            .update_settings(|settings| settings.set_criteria(vec![S("popularity")]))
+            .unwrap_err();
+        assert!(error.to_string().starts_with("`popularity` ranking rule is invalid."));
+
+        let error = index
+            .update_settings(|settings| settings.set_criteria(vec![S("custom:popularity")]))
+            .unwrap_err();
+        assert!(error.to_string().starts_with("`custom:popularity` ranking rule is invalid."));
+
+        // the names that can be mistaken for a built-in ranking rule are rejected.
+        let error = index.register_ranking_rule("popularity:desc", Popularity).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "`popularity:desc` can't be the name of a custom ranking rule as it can be mistaken for a built-in ranking rule."
+        );
+
+        // the rules are not persisted and must be registered before searching.
+        let mut wtxn = index.write_txn().unwrap();
+        index.put_criteria(&mut wtxn, &[Criterion::Custom(S("popularity"))]).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let error = Search::new(&rtxn, &index).execute().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "The `popularity` ranking rule is not registered on this index."
+        );
+    }
+}
@@ -10,6 +10,7 @@ use crate::search::criteria::{
     resolve_phrase, resolve_query_tree, Context, Criterion, CriterionParameters, CriterionResult,
 };
 use crate::search::query_tree::{Operation, PrimitiveQueryPart};
+use crate::search::score_details::{self, ScoreDetails};
 use crate::{absolute_from_relative_position, FieldId, Result};
 
 pub struct Exactness<'t> {
@@ -19,6 +20,7 @@ pub struct Exactness<'t> {
     bucket_candidates: RoaringBitmap,
     parent: Box<dyn Criterion + 't>,
     query: Vec<ExactQueryPart>,
+    score_details: Vec<ScoreDetails>,
 }
 
 impl<'t> Exactness<'t> {
@@ -39,6 +41,7 @@ impl<'t> Exactness<'t> {
             bucket_candidates: RoaringBitmap::new(),
             parent,
             query,
+            score_details: Vec::new(),
         })
     }
 }
@@ -61,14 +64,19 @@ impl<'t> Criterion for Exactness<'t> {
                     self.query_tree = None;
                 }
                 Some(state) => {
-                    let (candidates, state) = resolve_state(self.ctx, take(state), &self.query)?;
+                    let (candidates, exactness, state) =
+                        resolve_state(self.ctx, take(state), &self.query)?;
                     self.state = state;
 
+                    let mut score_details = self.score_details.clone();
+                    score_details.push(ScoreDetails::Exactness(exactness));
+
                     return Ok(Some(CriterionResult {
                         query_tree: self.query_tree.clone(),
                         candidates: Some(candidates),
                         filtered_candidates: None,
                         bucket_candidates: Some(take(&mut self.bucket_candidates)),
+                        score_details,
                     }));
                 }
                 None => match self.parent.next(params)? {
@@ -77,6 +85,7 @@ impl<'t> Criterion for Exactness<'t> {
                         candidates,
                         filtered_candidates,
                         bucket_candidates,
+                        score_details,
                     }) => {
                         let mut candidates = match candidates {
                             Some(candidates) => candidates,
@@ -97,18 +106,21 @@ impl<'t> Criterion for Exactness<'t> {
 
                         self.state = Some(State::new(candidates));
                         self.query_tree = Some(query_tree);
+                        self.score_details = score_details;
                     }
                     Some(CriterionResult {
                         query_tree: None,
                         candidates,
                         filtered_candidates,
                         bucket_candidates,
+                        score_details,
                     }) => {
                         return Ok(Some(CriterionResult {
                             query_tree: None,
                             candidates,
                             filtered_candidates,
                             bucket_candidates,
+                            score_details,
                         }));
                     }
                     None => return Ok(None),
@@ -126,7 +138,8 @@ enum State {
     AttributeStartsWith(RoaringBitmap),
     /// Rank the remaining documents by the number of exact words contained.
     ExactWords(RoaringBitmap),
-    Remainings(Vec<RoaringBitmap>),
+    /// The remaining documents associated with the number of exact words they contain.
+    Remainings(Vec<(u32, RoaringBitmap)>),
 }
 
 impl State {
@@ -140,8 +153,8 @@ impl State {
             | Self::AttributeStartsWith(candidates)
             | Self::ExactWords(candidates) => *candidates -= lhs,
             Self::Remainings(candidates_array) => {
-                candidates_array.iter_mut().for_each(|candidates| *candidates -= lhs);
-                candidates_array.retain(|candidates| !candidates.is_empty());
+                candidates_array.iter_mut().for_each(|(_, candidates)| *candidates -= lhs);
+                candidates_array.retain(|(_, candidates)| !candidates.is_empty());
             }
         }
     }
@@ -152,7 +165,7 @@ impl State {
             | Self::AttributeStartsWith(candidates)
             | Self::ExactWords(candidates) => candidates.is_empty(),
             Self::Remainings(candidates_array) => {
-                candidates_array.iter().all(RoaringBitmap::is_empty)
+                candidates_array.iter().all(|(_, candidates)| candidates.is_empty())
             }
         }
     }
@@ -169,8 +182,11 @@ fn resolve_state(
     ctx: &dyn Context,
     state: State,
     query: &[ExactQueryPart],
-) -> Result<(RoaringBitmap, Option<State>)> {
+) -> Result<(RoaringBitmap, score_details::Exactness, Option<State>)> {
+    use score_details::Exactness as Details;
     use State::*;
+
+    let max_matching_words = query.len() as u32;
     match state {
         ExactAttribute(mut allowed_candidates) => {
             let mut candidates = RoaringBitmap::new();
@@ -193,7 +209,8 @@ fn resolve_state(
                 allowed_candidates -= &candidates;
             }
 
-            Ok((candidates, Some(AttributeStartsWith(allowed_candidates))))
+            let exactness = Details::ExactAttribute { max_matching_words };
+            Ok((candidates, exactness, Some(AttributeStartsWith(allowed_candidates))))
         }
         AttributeStartsWith(mut allowed_candidates) => {
             let mut candidates = RoaringBitmap::new();
@@ -207,7 +224,8 @@ fn resolve_state(
             candidates &= &allowed_candidates;
             // remove current candidates from allowed candidates
             allowed_candidates -= &candidates;
-            Ok((candidates, Some(ExactWords(allowed_candidates))))
+            let exactness = Details::AttributeStartsWith { max_matching_words };
+            Ok((candidates, exactness, Some(ExactWords(allowed_candidates))))
         }
         ExactWords(mut allowed_candidates) => {
             let number_of_part = query.len();
@@ -253,23 +271,26 @@ fn resolve_state(
                 combinations_candidates &= &allowed_candidates;
                 // remove current candidates from allowed candidates
                 allowed_candidates -= &combinations_candidates;
-                candidates_array.push(combinations_candidates);
+                candidates_array.push((c_count as u32, combinations_candidates));
             }
 
             // push remainings allowed candidates as the worst valid candidates
-            candidates_array.push(allowed_candidates);
+            candidates_array.push((0, allowed_candidates));
             // reverse the array to be able to pop candidates from the best to the worst.
             candidates_array.reverse();
 
-            Ok((all_exact_candidates, Some(Remainings(candidates_array))))
+            let exactness =
+                Details::ExactWords { matching_words: max_matching_words, max_matching_words };
+            Ok((all_exact_candidates, exactness, Some(Remainings(candidates_array))))
         }
         // pop remainings candidates until the emptiness
         Remainings(mut candidates_array) => {
-            let candidates = candidates_array.pop().unwrap_or_default();
+            let (matching_words, candidates) = candidates_array.pop().unwrap_or_default();
+            let exactness = Details::ExactWords { matching_words, max_matching_words };
             if !candidates_array.is_empty() {
-                Ok((candidates, Some(Remainings(candidates_array))))
+                Ok((candidates, exactness, Some(Remainings(candidates_array))))
             } else {
-                Ok((candidates, None))
+                Ok((candidates, exactness, None))
             }
         }
     }
@@ -3,7 +3,7 @@ use roaring::RoaringBitmap;
 
 use super::{resolve_query_tree, Context, Criterion, CriterionParameters, CriterionResult};
 use crate::search::query_tree::Operation;
-use crate::search::WordDerivationsCache;
+use crate::search::{ScoreDetails, WordDerivationsCache};
 use crate::Result;
 
 /// The result of a call to the fetcher.
@@ -15,6 +15,8 @@ pub struct FinalResult {
     pub candidates: RoaringBitmap,
     /// Candidates that comes from the current bucket of the initial criterion.
     pub bucket_candidates: RoaringBitmap,
+    /// The details of the buckets of every criterion these candidates come from.
+    pub score_details: Vec<ScoreDetails>,
 }
 
 pub struct Final<'t> {
@@ -50,6 +52,7 @@ impl<'t> Final<'t> {
                 candidates,
                 filtered_candidates,
                 bucket_candidates,
+                score_details,
             }) => {
                 let mut candidates = match (candidates, query_tree.as_ref()) {
                     (Some(candidates), _) => candidates,
@@ -67,7 +70,7 @@ impl<'t> Final<'t> {
 
                 self.returned_candidates |= &candidates;
 
-                Ok(Some(FinalResult { query_tree, candidates, bucket_candidates }))
+                Ok(Some(FinalResult { query_tree, candidates, bucket_candidates, score_details }))
             }
             None => Ok(None),
         }
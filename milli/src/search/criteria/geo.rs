@@ -4,41 +4,47 @@ use roaring::RoaringBitmap;
 use rstar::RTree;
 
 use super::{Criterion, CriterionParameters, CriterionResult};
-use crate::search::criteria::{resolve_query_tree, CriteriaBuilder};
+use crate::search::criteria::{resolve_query_tree, Context};
+use crate::search::score_details::{self, ScoreDetails};
 use crate::{lat_lng_to_xyz, GeoPoint, Index, Result};
 
 pub struct Geo<'t> {
+    ctx: &'t dyn Context<'t>,
     index: &'t Index,
     rtxn: &'t heed::RoTxn<'t>,
     ascending: bool,
     parent: Box<dyn Criterion + 't>,
-    candidates: Box<dyn Iterator<Item = RoaringBitmap>>,
+    candidates: Box<dyn Iterator<Item = ([f64; 2], RoaringBitmap)>>,
     allowed_candidates: RoaringBitmap,
     bucket_candidates: RoaringBitmap,
     rtree: Option<RTree<GeoPoint>>,
     point: [f64; 2],
+    score_details: Vec<ScoreDetails>,
 }
 
 impl<'t> Geo<'t> {
     pub fn asc(
+        ctx: &'t dyn Context<'t>,
         index: &'t Index,
         rtxn: &'t heed::RoTxn<'t>,
         parent: Box<dyn Criterion + 't>,
         point: [f64; 2],
     ) -> Result<Self> {
-        Self::new(index, rtxn, parent, point, true)
+        Self::new(ctx, index, rtxn, parent, point, true)
     }
 
     pub fn desc(
+        ctx: &'t dyn Context<'t>,
         index: &'t Index,
         rtxn: &'t heed::RoTxn<'t>,
         parent: Box<dyn Criterion + 't>,
         point: [f64; 2],
     ) -> Result<Self> {
-        Self::new(index, rtxn, parent, point, false)
+        Self::new(ctx, index, rtxn, parent, point, false)
     }
 
     fn new(
+        ctx: &'t dyn Context<'t>,
         index: &'t Index,
         rtxn: &'t heed::RoTxn<'t>,
         parent: Box<dyn Criterion + 't>,
@@ -51,6 +57,7 @@ impl<'t> Geo<'t> {
         let rtree = index.geo_rtree(rtxn)?;
 
         Ok(Self {
+            ctx,
             index,
             rtxn,
             ascending,
@@ -60,6 +67,7 @@ impl<'t> Geo<'t> {
             bucket_candidates,
             rtree,
             point,
+            score_details: Vec::new(),
         })
     }
 }
@@ -70,14 +78,23 @@ impl Criterion for Geo<'_> {
 
         loop {
             match self.candidates.next() {
-                Some(mut candidates) => {
+                Some((value, mut candidates)) => {
                     candidates -= params.excluded_candidates;
                     self.allowed_candidates -= &candidates;
+
+                    let mut score_details = self.score_details.clone();
+                    score_details.push(ScoreDetails::GeoSort(score_details::GeoSort {
+                        target_point: self.point,
+                        ascending: self.ascending,
+                        value: Some(value),
+                    }));
+
                     return Ok(Some(CriterionResult {
                         query_tree: None,
                         candidates: Some(candidates),
                         filtered_candidates: None,
                         bucket_candidates: Some(self.bucket_candidates.clone()),
+                        score_details,
                     }));
                 }
                 None => match self.parent.next(params)? {
@@ -86,13 +103,11 @@ impl Criterion for Geo<'_> {
                         candidates,
                         filtered_candidates,
                         bucket_candidates,
+                        score_details,
                     }) => {
                         let mut candidates = match (&query_tree, candidates) {
                             (_, Some(candidates)) => candidates,
-                            (Some(qt), None) => {
-                                let context = CriteriaBuilder::new(&self.rtxn, &self.index)?;
-                                resolve_query_tree(&context, qt, params.wdcache)?
-                            }
+                            (Some(qt), None) => resolve_query_tree(self.ctx, qt, params.wdcache)?,
                             (None, None) => self.index.documents_ids(self.rtxn)?,
                         };
 
@@ -108,6 +123,7 @@ impl Criterion for Geo<'_> {
                         if candidates.is_empty() {
                             continue;
                         }
+                        self.score_details = score_details;
                         self.allowed_candidates = &candidates - params.excluded_candidates;
                         self.candidates = match rtree {
                             Some(rtree) => geo_point(
@@ -131,13 +147,13 @@ fn geo_point(
     mut candidates: RoaringBitmap,
     point: [f64; 2],
     ascending: bool,
-) -> Box<dyn Iterator<Item = RoaringBitmap>> {
+) -> Box<dyn Iterator<Item = ([f64; 2], RoaringBitmap)>> {
     let point = lat_lng_to_xyz(&point);
 
     let mut results = Vec::new();
     for point in rtree.nearest_neighbor_iter(&point) {
         if candidates.remove(point.data.0) {
-            results.push(std::iter::once(point.data.0).collect());
+            results.push((point.data.1, std::iter::once(point.data.0).collect()));
             if candidates.is_empty() {
                 break;
             }
@@ -1,23 +1,33 @@
 use std::borrow::Cow;
 use std::collections::HashMap;
 
+use heed::types::DecodeIgnore;
 use roaring::RoaringBitmap;
 
 use self::asc_desc::AscDesc;
 use self::attribute::Attribute;
+use self::custom::Custom;
+pub use self::custom::CustomRankingRule;
 use self::exactness::Exactness;
 use self::initial::Initial;
 use self::proximity::Proximity;
 use self::r#final::Final;
 use self::typo::Typo;
 use self::words::Words;
-use super::query_tree::{Operation, PrimitiveQueryPart, Query, QueryKind};
+use super::query_tree::{
+    Operation, PrimitiveQueryPart, Query, QueryKind, QueryRestrictions, ScopedRestriction,
+};
+use crate::error::UserError;
 use crate::search::criteria::geo::Geo;
-use crate::search::{word_derivations, Distinct, WordDerivationsCache};
-use crate::{AscDesc as AscDescName, DocumentId, FieldId, Index, Member, Result};
+use crate::search::{word_derivations, Distinct, ScoreDetails, WordDerivationsCache};
+use crate::{
+    absolute_from_relative_position, relative_from_absolute_position, AscDesc as AscDescName,
+    DocumentId, FieldId, Index, Member, RelativePosition, Result,
+};
 
 mod asc_desc;
 mod attribute;
+mod custom;
 mod exactness;
 pub mod r#final;
 mod geo;
@@ -42,6 +52,8 @@ pub struct CriterionResult {
     filtered_candidates: Option<RoaringBitmap>,
     /// Candidates that comes from the current bucket of the initial criterion.
     bucket_candidates: Option<RoaringBitmap>,
+    /// The details of the buckets these candidates come from, one by ranking rule.
+    score_details: Vec<ScoreDetails>,
 }
 
 #[derive(Debug, PartialEq)]
@@ -101,6 +113,8 @@ pub trait Context<'c> {
         word: &str,
         in_prefix_cache: bool,
     ) -> heed::Result<Box<dyn Iterator<Item = heed::Result<((&'c str, u32), RoaringBitmap)>> + 'c>>;
+    /// Returns the last position of the word in each of the searched attributes it appears in.
+    fn word_last_positions(&self, word: &str, in_prefix_cache: bool) -> heed::Result<Vec<u32>>;
     fn synonyms(&self, word: &str) -> heed::Result<Option<Vec<Vec<String>>>>;
     fn searchable_fields_ids(&self) -> Result<Vec<FieldId>>;
     fn field_id_word_count_docids(
@@ -109,6 +123,7 @@ pub trait Context<'c> {
         word_count: u8,
     ) -> heed::Result<Option<RoaringBitmap>>;
     fn word_position_docids(&self, word: &str, pos: u32) -> heed::Result<Option<RoaringBitmap>>;
+    fn attribute_weights(&self) -> Result<HashMap<FieldId, u16>>;
 }
 
 pub struct CriteriaBuilder<'t> {
@@ -116,6 +131,8 @@ pub struct CriteriaBuilder<'t> {
     index: &'t Index,
     words_fst: fst::Set<Cow<'t, [u8]>>,
     words_prefixes_fst: fst::Set<Cow<'t, [u8]>>,
+    /// The fields the query words must be found in, all the searchable fields if `None`.
+    fields_to_search_on: Option<Vec<FieldId>>,
 }
 
 /// Return the docids for the following word pairs and proximities using [`Context::word_pair_proximity_docids`].
@@ -186,19 +203,23 @@ impl<'c> Context<'c> for CriteriaBuilder<'c> {
     }
 
     fn word_docids(&self, word: &str) -> heed::Result<Option<RoaringBitmap>> {
-        self.index.word_docids.get(self.rtxn, &word)
+        let docids = self.index.word_docids.get(self.rtxn, &word)?;
+        self.restrict_to_fields(docids, &[(word, false)])
     }
 
     fn exact_word_docids(&self, word: &str) -> heed::Result<Option<RoaringBitmap>> {
-        self.index.exact_word_docids.get(self.rtxn, &word)
+        let docids = self.index.exact_word_docids.get(self.rtxn, &word)?;
+        self.restrict_to_fields(docids, &[(word, false)])
     }
 
     fn word_prefix_docids(&self, word: &str) -> heed::Result<Option<RoaringBitmap>> {
-        self.index.word_prefix_docids.get(self.rtxn, &word)
+        let docids = self.index.word_prefix_docids.get(self.rtxn, &word)?;
+        self.restrict_to_fields(docids, &[(word, true)])
     }
 
     fn exact_word_prefix_docids(&self, word: &str) -> heed::Result<Option<RoaringBitmap>> {
-        self.index.exact_word_prefix_docids.get(self.rtxn, &word)
+        let docids = self.index.exact_word_prefix_docids.get(self.rtxn, &word)?;
+        self.restrict_to_fields(docids, &[(word, true)])
     }
 
     fn word_pair_proximity_docids(
@@ -207,7 +228,9 @@ impl<'c> Context<'c> for CriteriaBuilder<'c> {
         right: &str,
         proximity: u8,
     ) -> heed::Result<Option<RoaringBitmap>> {
-        self.index.word_pair_proximity_docids.get(self.rtxn, &(proximity, left, right))
+        let docids =
+            self.index.word_pair_proximity_docids.get(self.rtxn, &(proximity, left, right))?;
+        self.restrict_to_fields(docids, &[(left, false), (right, false)])
     }
 
     fn word_prefix_pair_proximity_docids(
@@ -216,15 +239,24 @@ impl<'c> Context<'c> for CriteriaBuilder<'c> {
         prefix: &str,
         proximity: u8,
     ) -> heed::Result<Option<RoaringBitmap>> {
-        self.index.word_prefix_pair_proximity_docids.get(self.rtxn, &(proximity, left, prefix))
+        let docids = self
+            .index
+            .word_prefix_pair_proximity_docids
+            .get(self.rtxn, &(proximity, left, prefix))?;
+        self.restrict_to_fields(docids, &[(left, false), (prefix, true)])
     }
+
     fn prefix_word_pair_proximity_docids(
         &self,
         prefix: &str,
         right: &str,
         proximity: u8,
     ) -> heed::Result<Option<RoaringBitmap>> {
-        self.index.prefix_word_pair_proximity_docids.get(self.rtxn, &(proximity, prefix, right))
+        let docids = self
+            .index
+            .prefix_word_pair_proximity_docids
+            .get(self.rtxn, &(proximity, prefix, right))?;
+        self.restrict_to_fields(docids, &[(prefix, true), (right, false)])
     }
 
     fn words_fst<'t>(&self) -> &'t fst::Set<Cow<[u8]>> {
@@ -241,7 +273,18 @@ impl<'c> Context<'c> for CriteriaBuilder<'c> {
     ) -> heed::Result<HashMap<String, RoaringBitmap>> {
         let mut words_positions = HashMap::new();
         for result in self.index.docid_word_positions.prefix_iter(self.rtxn, &(docid, ""))? {
-            let ((_, word), positions) = result?;
+            let ((_, word), mut positions) = result?;
+            if let Some(fields_ids) = &self.fields_to_search_on {
+                positions = positions
+                    .into_iter()
+                    .filter(|&position| {
+                        fields_ids.contains(&relative_from_absolute_position(position).0)
+                    })
+                    .collect();
+                if positions.is_empty() {
+                    continue;
+                }
+            }
             words_positions.insert(word.to_string(), positions);
         }
         Ok(words_positions)
@@ -265,7 +308,47 @@ impl<'c> Context<'c> for CriteriaBuilder<'c> {
             false => self.index.word_position_docids,
         };
 
-        Ok(Box::new(db.range(self.rtxn, &range)?))
+        let iter = db.range(self.rtxn, &range)?;
+        match self.fields_to_search_on.clone() {
+            Some(fields_ids) => Ok(Box::new(iter.filter(move |result| match result {
+                Ok(((_, position), _)) => {
+                    fields_ids.contains(&relative_from_absolute_position(*position).0)
+                }
+                Err(_) => true,
+            }))),
+            None => Ok(Box::new(iter)),
+        }
+    }
+
+    fn word_last_positions(&self, word: &str, in_prefix_cache: bool) -> heed::Result<Vec<u32>> {
+        let db = match in_prefix_cache {
+            true => self.index.word_prefix_position_docids,
+            false => self.index.word_position_docids,
+        };
+        let db = db.remap_data_type::<DecodeIgnore>();
+
+        // jump from the last position of an attribute to the end of the previous attribute.
+        let mut last_positions = Vec::new();
+        let mut right = u32::max_value();
+        while let Some(result) = db.rev_range(self.rtxn, &((word, 0)..=(word, right)))?.next() {
+            let ((_, position), ()) = result?;
+            let (field_id, _) = relative_from_absolute_position(position);
+            let searched = match &self.fields_to_search_on {
+                Some(fields_ids) => fields_ids.contains(&field_id),
+                None => true,
+            };
+            if searched {
+                last_positions.push(position);
+            }
+            match field_id.checked_sub(1) {
+                Some(field_id) => {
+                    right = absolute_from_relative_position(field_id, RelativePosition::MAX)
+                }
+                None => break,
+            }
+        }
+
+        Ok(last_positions)
     }
 
     fn synonyms(&self, word: &str) -> heed::Result<Option<Vec<Vec<String>>>> {
@@ -273,10 +356,14 @@ impl<'c> Context<'c> for CriteriaBuilder<'c> {
     }
 
     fn searchable_fields_ids(&self) -> Result<Vec<FieldId>> {
-        match self.index.searchable_fields_ids(self.rtxn)? {
-            Some(searchable_fields_ids) => Ok(searchable_fields_ids),
-            None => Ok(self.index.fields_ids_map(self.rtxn)?.ids().collect()),
+        let mut searchable_fields_ids = match self.index.searchable_fields_ids(self.rtxn)? {
+            Some(searchable_fields_ids) => searchable_fields_ids,
+            None => self.index.fields_ids_map(self.rtxn)?.ids().collect(),
+        };
+        if let Some(fields_ids) = &self.fields_to_search_on {
+            searchable_fields_ids.retain(|field_id| fields_ids.contains(field_id));
         }
+        Ok(searchable_fields_ids)
     }
 
     fn field_id_word_count_docids(
@@ -284,21 +371,159 @@ impl<'c> Context<'c> for CriteriaBuilder<'c> {
         field_id: FieldId,
         word_count: u8,
     ) -> heed::Result<Option<RoaringBitmap>> {
+        if let Some(fields_ids) = &self.fields_to_search_on {
+            if !fields_ids.contains(&field_id) {
+                return Ok(None);
+            }
+        }
         let key = (field_id, word_count);
         self.index.field_id_word_count_docids.get(self.rtxn, &key)
     }
 
     fn word_position_docids(&self, word: &str, pos: u32) -> heed::Result<Option<RoaringBitmap>> {
+        if let Some(fields_ids) = &self.fields_to_search_on {
+            if !fields_ids.contains(&relative_from_absolute_position(pos).0) {
+                return Ok(None);
+            }
+        }
         let key = (word, pos);
         self.index.word_position_docids.get(self.rtxn, &key)
     }
+
+    fn attribute_weights(&self) -> Result<HashMap<FieldId, u16>> {
+        self.index.attribute_weights_ids(self.rtxn)
+    }
 }
 
 impl<'t> CriteriaBuilder<'t> {
     pub fn new(rtxn: &'t heed::RoTxn<'t>, index: &'t Index) -> Result<Self> {
         let words_fst = index.words_fst(rtxn)?;
         let words_prefixes_fst = index.words_prefixes_fst(rtxn)?;
-        Ok(Self { rtxn, index, words_fst, words_prefixes_fst })
+        Ok(Self { rtxn, index, words_fst, words_prefixes_fst, fields_to_search_on: None })
+    }
+
+    /// Only matches the query words in these fields instead of all the searchable fields.
+    pub fn fields_to_search_on(&mut self, fields_ids: Vec<FieldId>) -> &mut Self {
+        self.fields_to_search_on = Some(fields_ids);
+        self
+    }
+
+    /// Keeps the documents that contain all the given words, or prefixes, in the same
+    /// field to search on, if any, so that the words of a pair are not matched in
+    /// different fields.
+    fn restrict_to_fields(
+        &self,
+        docids: Option<RoaringBitmap>,
+        words: &[(&str, bool)],
+    ) -> heed::Result<Option<RoaringBitmap>> {
+        let (fields_ids, docids) = match (&self.fields_to_search_on, docids) {
+            (Some(fields_ids), Some(docids)) => (fields_ids, docids),
+            (_, docids) => return Ok(docids),
+        };
+
+        let mut docids_in_fields = RoaringBitmap::new();
+        for &field_id in fields_ids {
+            let mut field_docids = docids.clone();
+            for &(word, is_prefix) in words {
+                let db = match is_prefix {
+                    true => self.index.word_prefix_position_docids,
+                    false => self.index.word_position_docids,
+                };
+
+                let left = (word, absolute_from_relative_position(field_id, 0));
+                let right = (word, absolute_from_relative_position(field_id, u16::MAX));
+                let mut word_docids = RoaringBitmap::new();
+                for result in db.range(self.rtxn, &(left..=right))? {
+                    let (_, position_docids) = result?;
+                    word_docids |= position_docids;
+                }
+                field_docids &= word_docids;
+            }
+            docids_in_fields |= field_docids;
+        }
+
+        Ok(Some(docids_in_fields))
+    }
+
+    /// Returns the documents containing one of the derivations of the word, or the consecutive
+    /// words of the phrase, in one of the given fields.
+    fn fields_words_docids(
+        &self,
+        fields_ids: &[FieldId],
+        queries: &[Query],
+        wdcache: &mut WordDerivationsCache,
+    ) -> Result<RoaringBitmap> {
+        let mut derivations = Vec::with_capacity(queries.len());
+        for query in queries {
+            let max_typo = match query.kind {
+                QueryKind::Tolerant { typo, .. } => typo,
+                QueryKind::Exact { .. } => 0,
+            };
+            let word = query.kind.word();
+            let words = word_derivations(word, query.prefix, max_typo, &self.words_fst, wdcache)?;
+            derivations.push(words.iter().map(|(word, _)| word.clone()).collect::<Vec<_>>());
+        }
+
+        let (first, tail) = match derivations.split_first() {
+            Some(split) => split,
+            None => return Ok(RoaringBitmap::new()),
+        };
+
+        let mut docids = RoaringBitmap::new();
+        for &field_id in fields_ids {
+            for first in first {
+                let left = (first.as_str(), absolute_from_relative_position(field_id, 0));
+                let right = (first.as_str(), absolute_from_relative_position(field_id, u16::MAX));
+                for result in self.index.word_position_docids.range(self.rtxn, &(left..=right))? {
+                    let ((_, position), mut position_docids) = result?;
+                    // the following words of the phrase must be at the following positions,
+                    // in the same field.
+                    let (_, relative) = relative_from_absolute_position(position);
+                    if relative as usize + tail.len() > u16::MAX as usize {
+                        continue;
+                    }
+                    for (i, words) in tail.iter().enumerate() {
+                        let position = position + i as u32 + 1;
+                        let mut words_docids = RoaringBitmap::new();
+                        for word in words {
+                            let key = (word.as_str(), position);
+                            if let Some(word_docids) =
+                                self.index.word_position_docids.get(self.rtxn, &key)?
+                            {
+                                words_docids |= word_docids;
+                            }
+                        }
+                        position_docids &= words_docids;
+                        if position_docids.is_empty() {
+                            break;
+                        }
+                    }
+                    docids |= position_docids;
+                }
+            }
+        }
+
+        Ok(docids)
+    }
+
+    /// Removes from the candidates the documents that don't respect the negated
+    /// and field-scoped words and phrases of the query.
+    pub fn restrict_candidates(
+        &self,
+        mut candidates: RoaringBitmap,
+        restrictions: &QueryRestrictions,
+    ) -> Result<RoaringBitmap> {
+        candidates -= resolve_negative_query(self, &restrictions.negative)?;
+        let mut wdcache = WordDerivationsCache::new();
+        for ScopedRestriction { fields_ids, queries, negative } in &restrictions.scoped {
+            let docids = self.fields_words_docids(fields_ids, queries, &mut wdcache)?;
+            if *negative {
+                candidates -= docids;
+            } else {
+                candidates &= docids;
+            }
+        }
+        Ok(candidates)
     }
 
     pub fn build<D: 't + Distinct>(
@@ -307,6 +532,7 @@ impl<'t> CriteriaBuilder<'t> {
         primitive_query: Option<Vec<PrimitiveQueryPart>>,
         filtered_candidates: Option<RoaringBitmap>,
         sort_criteria: Option<Vec<AscDescName>>,
+        ranking_rules: Vec<crate::criterion::Criterion>,
         exhaustive_number_hits: bool,
         distinct: Option<D>,
     ) -> Result<Final<'t>> {
@@ -321,33 +547,37 @@ impl<'t> CriteriaBuilder<'t> {
             exhaustive_number_hits,
             distinct,
         )) as Box<dyn Criterion>;
-        for name in self.index.criteria(&self.rtxn)? {
+        for name in ranking_rules {
             criterion = match name {
-                Name::Words => Box::new(Words::new(self, criterion)),
+                Name::Words => Box::new(Words::new(self, criterion, &primitive_query)),
                 Name::Typo => Box::new(Typo::new(self, criterion)),
                 Name::Sort => match sort_criteria {
                     Some(ref sort_criteria) => {
                         for asc_desc in sort_criteria {
                             criterion = match asc_desc {
                                 AscDescName::Asc(Member::Field(field)) => Box::new(AscDesc::asc(
+                                    self,
                                     &self.index,
                                     &self.rtxn,
                                     criterion,
                                     field.to_string(),
                                 )?),
                                 AscDescName::Desc(Member::Field(field)) => Box::new(AscDesc::desc(
+                                    self,
                                     &self.index,
                                     &self.rtxn,
                                     criterion,
                                     field.to_string(),
                                 )?),
                                 AscDescName::Asc(Member::Geo(point)) => Box::new(Geo::asc(
+                                    self,
                                     &self.index,
                                     &self.rtxn,
                                     criterion,
                                     point.clone(),
                                 )?),
                                 AscDescName::Desc(Member::Geo(point)) => Box::new(Geo::desc(
+                                    self,
                                     &self.index,
                                     &self.rtxn,
                                     criterion,
@@ -360,14 +590,20 @@ impl<'t> CriteriaBuilder<'t> {
                     None => criterion,
                 },
                 Name::Proximity => Box::new(Proximity::new(self, criterion)),
-                Name::Attribute => Box::new(Attribute::new(self, criterion)),
+                Name::Attribute => Box::new(Attribute::new(self, criterion)?),
                 Name::Exactness => Box::new(Exactness::new(self, criterion, &primitive_query)?),
                 Name::Asc(field) => {
-                    Box::new(AscDesc::asc(&self.index, &self.rtxn, criterion, field)?)
+                    Box::new(AscDesc::asc(self, &self.index, &self.rtxn, criterion, field)?)
                 }
                 Name::Desc(field) => {
-                    Box::new(AscDesc::desc(&self.index, &self.rtxn, criterion, field)?)
+                    Box::new(AscDesc::desc(self, &self.index, &self.rtxn, criterion, field)?)
                 }
+                Name::Custom(name) => match self.index.custom_ranking_rule(&name) {
+                    Some(rule) => {
+                        Box::new(Custom::new(self, &self.index, &self.rtxn, criterion, name, rule))
+                    }
+                    None => return Err(UserError::UnregisteredRankingRule { name }.into()),
+                },
             };
         }
 
@@ -424,6 +660,29 @@ pub fn resolve_query_tree(
     resolve_operation(ctx, query_tree, wdcache)
 }
 
+/// Returns the documents containing any of the words or phrases of the negative query.
+pub fn resolve_negative_query(
+    ctx: &dyn Context,
+    negative_query: &[PrimitiveQueryPart],
+) -> Result<RoaringBitmap> {
+    let mut docids = RoaringBitmap::new();
+    for part in negative_query {
+        match part {
+            PrimitiveQueryPart::Word(word, _) => {
+                docids |= ctx.word_docids(word)?.unwrap_or_default();
+                docids |= ctx.exact_word_docids(word)?.unwrap_or_default();
+            }
+            // a single word phrase is an exact word.
+            PrimitiveQueryPart::Phrase(words) if words.len() == 1 => {
+                docids |= ctx.word_docids(&words[0])?.unwrap_or_default();
+                docids |= ctx.exact_word_docids(&words[0])?.unwrap_or_default();
+            }
+            PrimitiveQueryPart::Phrase(words) => docids |= resolve_phrase(ctx, words)?,
+        }
+    }
+    Ok(docids)
+}
+
 pub fn resolve_phrase(ctx: &dyn Context, phrase: &[String]) -> Result<RoaringBitmap> {
     let mut candidates = RoaringBitmap::new();
     let mut first_iter = true;
@@ -742,6 +1001,14 @@ pub mod test {
             todo!()
         }
 
+        fn word_last_positions(
+            &self,
+            _word: &str,
+            _in_prefix_cache: bool,
+        ) -> heed::Result<Vec<u32>> {
+            todo!()
+        }
+
         fn synonyms(&self, _word: &str) -> heed::Result<Option<Vec<Vec<String>>>> {
             todo!()
         }
@@ -758,6 +1025,10 @@ pub mod test {
             todo!()
         }
 
+        fn attribute_weights(&self) -> Result<HashMap<FieldId, u16>> {
+            todo!()
+        }
+
         fn field_id_word_count_docids(
             &self,
             _field_id: FieldId,
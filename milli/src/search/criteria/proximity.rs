@@ -10,6 +10,7 @@ use super::{
     Criterion, CriterionParameters, CriterionResult,
 };
 use crate::search::query_tree::{maximum_proximity, Operation, Query, QueryKind};
+use crate::search::score_details::{self, ScoreDetails};
 use crate::search::{build_dfa, WordDerivationsCache};
 use crate::{Position, Result};
 
@@ -32,6 +33,7 @@ pub struct Proximity<'t> {
     parent: Box<dyn Criterion + 't>,
     candidates_cache: Cache,
     plane_sweep_cache: Option<btree_map::IntoIter<u8, RoaringBitmap>>,
+    score_details: Vec<ScoreDetails>,
 }
 
 impl<'t> Proximity<'t> {
@@ -44,6 +46,7 @@ impl<'t> Proximity<'t> {
             parent,
             candidates_cache: Cache::new(),
             plane_sweep_cache: None,
+            score_details: Vec::new(),
         }
     }
 }
@@ -70,7 +73,8 @@ impl<'t> Criterion for Proximity<'t> {
                 {
                     self.state = None; // reset state
                 }
-                Some((_, query_tree, allowed_candidates)) => {
+                Some((max_prox, query_tree, allowed_candidates)) => {
+                    let max_prox = *max_prox;
                     let mut new_candidates = if allowed_candidates.len() <= CANDIDATES_THRESHOLD
                         && self.proximity > PROXIMITY_THRESHOLD
                     {
@@ -108,6 +112,13 @@ impl<'t> Criterion for Proximity<'t> {
 
                     new_candidates &= &*allowed_candidates;
                     *allowed_candidates -= &new_candidates;
+
+                    let mut score_details = self.score_details.clone();
+                    score_details.push(ScoreDetails::Proximity(score_details::Proximity {
+                        proximity: self.proximity as u32,
+                        max_proximity: max_prox as u32,
+                    }));
+
                     self.proximity += 1;
 
                     return Ok(Some(CriterionResult {
@@ -115,6 +126,7 @@ impl<'t> Criterion for Proximity<'t> {
                         candidates: Some(new_candidates),
                         filtered_candidates: None,
                         bucket_candidates: Some(take(&mut self.bucket_candidates)),
+                        score_details,
                     }));
                 }
                 None => match self.parent.next(params)? {
@@ -123,6 +135,7 @@ impl<'t> Criterion for Proximity<'t> {
                         candidates,
                         filtered_candidates,
                         bucket_candidates,
+                        score_details,
                     }) => {
                         let mut candidates = match candidates {
                             Some(candidates) => candidates,
@@ -143,6 +156,7 @@ impl<'t> Criterion for Proximity<'t> {
 
                         let maximum_proximity = maximum_proximity(&query_tree);
                         self.state = Some((maximum_proximity as u8, query_tree, candidates));
+                        self.score_details = score_details;
                         self.proximity = 0;
                         self.plane_sweep_cache = None;
                     }
@@ -151,12 +165,14 @@ impl<'t> Criterion for Proximity<'t> {
                         candidates,
                         filtered_candidates,
                         bucket_candidates,
+                        score_details,
                     }) => {
                         return Ok(Some(CriterionResult {
                             query_tree: None,
                             candidates,
                             filtered_candidates,
                             bucket_candidates,
+                            score_details,
                         }));
                     }
                     None => return Ok(None),
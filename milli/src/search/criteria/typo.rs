@@ -10,6 +10,7 @@ use super::{
     CriterionResult,
 };
 use crate::search::query_tree::{maximum_typo, Operation, Query, QueryKind};
+use crate::search::score_details::{self, ScoreDetails};
 use crate::search::{word_derivations, WordDerivationsCache};
 use crate::Result;
 
@@ -24,6 +25,7 @@ pub struct Typo<'t> {
     bucket_candidates: Option<RoaringBitmap>,
     parent: Box<dyn Criterion + 't>,
     candidates_cache: HashMap<(Operation, u8), RoaringBitmap>,
+    score_details: Vec<ScoreDetails>,
 }
 
 impl<'t> Typo<'t> {
@@ -35,6 +37,7 @@ impl<'t> Typo<'t> {
             bucket_candidates: None,
             parent,
             candidates_cache: HashMap::new(),
+            score_details: Vec::new(),
         }
     }
 }
@@ -65,7 +68,8 @@ impl<'t> Criterion for Typo<'t> {
                 Some((_, _, Allowed(allowed_candidates))) if allowed_candidates.is_empty() => {
                     self.state = None; // reset state
                 }
-                Some((_, query_tree, candidates_authorization)) => {
+                Some((max_typos, query_tree, candidates_authorization)) => {
+                    let max_typos = *max_typos;
                     let fst = self.ctx.words_fst();
                     let new_query_tree = match self.typos {
                         typos if typos < MAX_TYPOS_PER_WORD => alterate_query_tree(
@@ -124,6 +128,12 @@ impl<'t> Criterion for Typo<'t> {
                         None => candidates.clone(),
                     };
 
+                    let mut score_details = self.score_details.clone();
+                    score_details.push(ScoreDetails::Typo(score_details::Typo {
+                        typo_count: self.typos as u32,
+                        max_typo_count: max_typos as u32,
+                    }));
+
                     self.typos += 1;
 
                     return Ok(Some(CriterionResult {
@@ -131,6 +141,7 @@ impl<'t> Criterion for Typo<'t> {
                         candidates: Some(candidates),
                         filtered_candidates: None,
                         bucket_candidates: Some(bucket_candidates),
+                        score_details,
                     }));
                 }
                 None => match self.parent.next(params)? {
@@ -139,6 +150,7 @@ impl<'t> Criterion for Typo<'t> {
                         candidates,
                         filtered_candidates,
                         bucket_candidates,
+                        score_details,
                     }) => {
                         self.bucket_candidates =
                             match (self.bucket_candidates.take(), bucket_candidates) {
@@ -155,6 +167,7 @@ impl<'t> Criterion for Typo<'t> {
 
                         let maximum_typos = maximum_typo(&query_tree) as u8;
                         self.state = Some((maximum_typos, query_tree, candidates));
+                        self.score_details = score_details;
                         self.typos = 0;
                     }
                     Some(CriterionResult {
@@ -162,12 +175,14 @@ impl<'t> Criterion for Typo<'t> {
                         candidates,
                         filtered_candidates,
                         bucket_candidates,
+                        score_details,
                     }) => {
                         return Ok(Some(CriterionResult {
                             query_tree: None,
                             candidates,
                             filtered_candidates,
                             bucket_candidates,
+                            score_details,
                         }));
                     }
                     None => return Ok(None),
@@ -375,7 +390,7 @@ mod test {
 
         let result = display_criteria(criteria, criterion_parameters);
         insta::assert_snapshot!(result, @r###"
-        CriterionResult { query_tree: None, candidates: None, filtered_candidates: None, bucket_candidates: None }
+        CriterionResult { query_tree: None, candidates: None, filtered_candidates: None, bucket_candidates: None, score_details: [] }
 
         "###);
     }
@@ -418,7 +433,7 @@ mod test {
             Exact { word: "split" }
             Exact { word: "this" }
             Exact { word: "world" }
-        ), candidates: Some(RoaringBitmap<[]>), filtered_candidates: None, bucket_candidates: Some(RoaringBitmap<[]>) }
+        ), candidates: Some(RoaringBitmap<[]>), filtered_candidates: None, bucket_candidates: Some(RoaringBitmap<[]>), score_details: [Typo(Typo { typo_count: 0, max_typo_count: 1 })] }
 
         CriterionResult { query_tree: Some(OR
           AND
@@ -427,7 +442,7 @@ mod test {
             OR
               Exact { word: "word" }
               Exact { word: "world" }
-        ), candidates: Some(RoaringBitmap<[]>), filtered_candidates: None, bucket_candidates: Some(RoaringBitmap<[]>) }
+        ), candidates: Some(RoaringBitmap<[]>), filtered_candidates: None, bucket_candidates: Some(RoaringBitmap<[]>), score_details: [Typo(Typo { typo_count: 1, max_typo_count: 1 })] }
 
         "###);
     }
@@ -453,7 +468,7 @@ mod test {
 
         let result = display_criteria(criteria, criterion_parameters);
         insta::assert_snapshot!(result, @r###"
-        CriterionResult { query_tree: None, candidates: None, filtered_candidates: Some(RoaringBitmap<8000 values between 986424 and 4294786076>), bucket_candidates: None }
+        CriterionResult { query_tree: None, candidates: None, filtered_candidates: Some(RoaringBitmap<8000 values between 986424 and 4294786076>), bucket_candidates: None, score_details: [] }
 
         "###);
     }
@@ -501,7 +516,7 @@ mod test {
             Exact { word: "split" }
             Exact { word: "this" }
             Exact { word: "world" }
-        ), candidates: Some(RoaringBitmap<[]>), filtered_candidates: None, bucket_candidates: Some(RoaringBitmap<[]>) }
+        ), candidates: Some(RoaringBitmap<[]>), filtered_candidates: None, bucket_candidates: Some(RoaringBitmap<[]>), score_details: [Typo(Typo { typo_count: 0, max_typo_count: 1 })] }
 
         CriterionResult { query_tree: Some(OR
           AND
@@ -510,7 +525,7 @@ mod test {
             OR
               Exact { word: "word" }
               Exact { word: "world" }
-        ), candidates: Some(RoaringBitmap<[]>), filtered_candidates: None, bucket_candidates: Some(RoaringBitmap<[]>) }
+        ), candidates: Some(RoaringBitmap<[]>), filtered_candidates: None, bucket_candidates: Some(RoaringBitmap<[]>), score_details: [Typo(Typo { typo_count: 1, max_typo_count: 1 })] }
 
         "###);
     }
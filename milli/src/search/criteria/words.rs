@@ -4,20 +4,30 @@ use log::debug;
 use roaring::RoaringBitmap;
 
 use super::{resolve_query_tree, Context, Criterion, CriterionParameters, CriterionResult};
-use crate::search::query_tree::Operation;
+use crate::search::query_tree::{Operation, PrimitiveQueryPart};
+use crate::search::score_details::{self, ScoreDetails};
 use crate::Result;
 
 pub struct Words<'t> {
     ctx: &'t dyn Context<'t>,
-    query_trees: Vec<Operation>,
+    /// The query trees of the buckets, with the number of query words they match.
+    query_trees: Vec<(Operation, usize)>,
     candidates: Option<RoaringBitmap>,
     bucket_candidates: Option<RoaringBitmap>,
     filtered_candidates: Option<RoaringBitmap>,
     parent: Box<dyn Criterion + 't>,
+    score_details: Vec<ScoreDetails>,
+    primitive_query: Vec<PrimitiveQueryPart>,
+    /// The number of query words matched by the query tree matching the most words.
+    max_matching_words: usize,
 }
 
 impl<'t> Words<'t> {
-    pub fn new(ctx: &'t dyn Context<'t>, parent: Box<dyn Criterion + 't>) -> Self {
+    pub fn new(
+        ctx: &'t dyn Context<'t>,
+        parent: Box<dyn Criterion + 't>,
+        primitive_query: &[PrimitiveQueryPart],
+    ) -> Self {
         Words {
             ctx,
             query_trees: Vec::default(),
@@ -25,6 +35,9 @@ impl<'t> Words<'t> {
             bucket_candidates: None,
             parent,
             filtered_candidates: None,
+            score_details: Vec::new(),
+            primitive_query: primitive_query.to_vec(),
+            max_matching_words: 0,
         }
     }
 }
@@ -41,7 +54,7 @@ impl<'t> Criterion for Words<'t> {
             debug!("Words at iteration {} ({:?})", self.query_trees.len(), self.candidates);
 
             match self.query_trees.pop() {
-                Some(query_tree) => {
+                Some((query_tree, matching_words)) => {
                     let candidates = match self.candidates.as_mut() {
                         Some(allowed_candidates) => {
                             let mut candidates =
@@ -58,11 +71,18 @@ impl<'t> Criterion for Words<'t> {
                         None => None,
                     };
 
+                    let mut score_details = self.score_details.clone();
+                    score_details.push(ScoreDetails::Words(score_details::Words {
+                        matching_words: matching_words.max(1) as u32,
+                        max_matching_words: self.max_matching_words.max(1) as u32,
+                    }));
+
                     return Ok(Some(CriterionResult {
                         query_tree: Some(query_tree),
                         candidates,
                         filtered_candidates: self.filtered_candidates.clone(),
                         bucket_candidates,
+                        score_details,
                     }));
                 }
                 None => match self.parent.next(params)? {
@@ -71,10 +91,20 @@ impl<'t> Criterion for Words<'t> {
                         candidates,
                         filtered_candidates,
                         bucket_candidates,
+                        score_details,
                     }) => {
-                        self.query_trees = explode_query_tree(query_tree);
+                        self.query_trees = explode_query_tree(query_tree)
+                            .into_iter()
+                            .map(|tree| {
+                                let count = matching_words(&tree, &self.primitive_query);
+                                (tree, count)
+                            })
+                            .collect();
+                        self.max_matching_words =
+                            self.query_trees.iter().map(|(_, count)| *count).max().unwrap_or(0);
                         self.candidates = candidates;
                         self.filtered_candidates = filtered_candidates;
+                        self.score_details = score_details;
 
                         self.bucket_candidates =
                             match (self.bucket_candidates.take(), bucket_candidates) {
@@ -87,12 +117,14 @@ impl<'t> Criterion for Words<'t> {
                         candidates,
                         filtered_candidates,
                         bucket_candidates,
+                        score_details,
                     }) => {
                         return Ok(Some(CriterionResult {
                             query_tree: None,
                             candidates,
                             filtered_candidates,
                             bucket_candidates,
+                            score_details,
                         }));
                     }
                     None => return Ok(None),
@@ -108,3 +140,38 @@ fn explode_query_tree(query_tree: Operation) -> Vec<Operation> {
         otherwise => vec![otherwise],
     }
 }
+
+/// Returns the number of parts of the primitive query that the documents matching this
+/// query tree contain at least. The alternatives that are not made of the query words,
+/// e.g. the synonyms or the splits of a word, are ignored.
+fn matching_words(operation: &Operation, query: &[PrimitiveQueryPart]) -> usize {
+    match operation {
+        Operation::And(ops) => ops.iter().map(|op| matching_words(op, query)).sum(),
+        Operation::Or(_, ops) => ops
+            .iter()
+            .map(|op| matching_words(op, query))
+            .filter(|&count| count != 0)
+            .min()
+            .unwrap_or(0),
+        Operation::Phrase(words) => query
+            .iter()
+            .any(|part| matches!(part, PrimitiveQueryPart::Phrase(phrase) if phrase == words))
+            as usize,
+        Operation::Query(leaf) => {
+            let word = |part: &PrimitiveQueryPart| match part {
+                PrimitiveQueryPart::Word(word, _) => Some(word.as_str()),
+                PrimitiveQueryPart::Phrase(words) if words.len() == 1 => Some(words[0].as_str()),
+                PrimitiveQueryPart::Phrase(_) => None,
+            };
+            // the concatenation of consecutive query words is an ngram of these words.
+            (1..=3)
+                .find(|&n| {
+                    query.windows(n).any(|parts| {
+                        let words: Option<Vec<_>> = parts.iter().map(word).collect();
+                        words.map_or(false, |words| words.concat() == leaf.kind.word())
+                    })
+                })
+                .unwrap_or(0)
+        }
+    }
+}
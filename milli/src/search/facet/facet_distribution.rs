@@ -1,9 +1,12 @@
-use std::collections::{BTreeMap, HashSet};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::ops::Bound::Unbounded;
 use std::{fmt, mem};
 
 use heed::types::ByteSlice;
+use indexmap::IndexMap;
 use roaring::RoaringBitmap;
+use serde::{Deserialize, Serialize};
 
 use crate::error::UserError;
 use crate::facet::FacetType;
@@ -21,8 +24,25 @@ pub const DEFAULT_VALUES_PER_FACET: usize = 100;
 /// the system to choose between one algorithm or another.
 const CANDIDATES_THRESHOLD: u64 = 3000;
 
+/// The order in which the values of a facet are returned by the facet distribution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OrderBy {
+    /// Ordered by the facet values, truncated to the first ones.
+    Lexicographic,
+    /// Ordered by the number of documents, truncated to the most frequent values.
+    Count,
+}
+
+impl Default for OrderBy {
+    fn default() -> Self {
+        OrderBy::Lexicographic
+    }
+}
+
 pub struct FacetDistribution<'a> {
     facets: Option<HashSet<String>>,
+    facets_order_by: HashMap<String, OrderBy>,
     candidates: Option<RoaringBitmap>,
     max_values_per_facet: usize,
     rtxn: &'a heed::RoTxn<'a>,
@@ -33,6 +53,7 @@ impl<'a> FacetDistribution<'a> {
     pub fn new(rtxn: &'a heed::RoTxn, index: &'a Index) -> FacetDistribution<'a> {
         FacetDistribution {
             facets: None,
+            facets_order_by: HashMap::new(),
             candidates: None,
             max_values_per_facet: DEFAULT_VALUES_PER_FACET,
             rtxn,
@@ -45,6 +66,13 @@ impl<'a> FacetDistribution<'a> {
         self
     }
 
+    /// Defines the order of the values of the given facet, the values
+    /// of the other facets are ordered lexicographically.
+    pub fn order_by(&mut self, facet: impl Into<String>, order_by: OrderBy) -> &mut Self {
+        self.facets_order_by.insert(facet.into(), order_by);
+        self
+    }
+
     pub fn max_values_per_facet(&mut self, max: usize) -> &mut Self {
         self.max_values_per_facet = max;
         self
@@ -62,6 +90,7 @@ impl<'a> FacetDistribution<'a> {
         field_id: FieldId,
         facet_type: FacetType,
         candidates: &RoaringBitmap,
+        max_values: usize,
         distribution: &mut BTreeMap<String, u64>,
     ) -> heed::Result<()> {
         match facet_type {
@@ -82,8 +111,7 @@ impl<'a> FacetDistribution<'a> {
                         let ((_, _, value), ()) = result?;
                         *distribution.entry(value.to_string()).or_insert(0) += 1;
 
-                        if distribution.len() - distribution_prelength == self.max_values_per_facet
-                        {
+                        if distribution.len() - distribution_prelength == max_values {
                             break;
                         }
                     }
@@ -109,7 +137,7 @@ impl<'a> FacetDistribution<'a> {
                             .or_insert_with(|| (original_value, 0));
                         *count += 1;
 
-                        if normalized_distribution.len() == self.max_values_per_facet {
+                        if normalized_distribution.len() == max_values {
                             break;
                         }
                     }
@@ -131,6 +159,7 @@ impl<'a> FacetDistribution<'a> {
         &self,
         field_id: FieldId,
         candidates: &RoaringBitmap,
+        max_values: usize,
         distribution: &mut BTreeMap<String, u64>,
     ) -> heed::Result<()> {
         let iter =
@@ -142,7 +171,7 @@ impl<'a> FacetDistribution<'a> {
             if !docids.is_empty() {
                 distribution.insert(value.to_string(), docids.len());
             }
-            if distribution.len() == self.max_values_per_facet {
+            if distribution.len() == max_values {
                 break;
             }
         }
@@ -154,6 +183,7 @@ impl<'a> FacetDistribution<'a> {
         &self,
         field_id: FieldId,
         candidates: &RoaringBitmap,
+        max_values: usize,
         distribution: &mut BTreeMap<String, u64>,
     ) -> heed::Result<()> {
         let iter =
@@ -165,7 +195,7 @@ impl<'a> FacetDistribution<'a> {
             if !docids.is_empty() {
                 distribution.insert(original.to_string(), docids.len());
             }
-            if distribution.len() == self.max_values_per_facet {
+            if distribution.len() == max_values {
                 break;
             }
         }
@@ -178,6 +208,7 @@ impl<'a> FacetDistribution<'a> {
     fn facet_values_from_raw_facet_database(
         &self,
         field_id: FieldId,
+        max_values: usize,
     ) -> heed::Result<BTreeMap<String, u64>> {
         let mut distribution = BTreeMap::new();
 
@@ -187,7 +218,7 @@ impl<'a> FacetDistribution<'a> {
         for result in range {
             let ((_, _, value, _), docids) = result?;
             distribution.insert(value.to_string(), docids.len());
-            if distribution.len() == self.max_values_per_facet {
+            if distribution.len() == max_values {
                 break;
             }
         }
@@ -203,7 +234,7 @@ impl<'a> FacetDistribution<'a> {
         for result in iter {
             let ((_, normalized_value), (original_value, docids)) = result?;
             normalized_distribution.insert(normalized_value, (original_value, docids.len()));
-            if normalized_distribution.len() == self.max_values_per_facet {
+            if normalized_distribution.len() == max_values {
                 break;
             }
         }
@@ -216,7 +247,35 @@ impl<'a> FacetDistribution<'a> {
         Ok(distribution)
     }
 
-    fn facet_values(&self, field_id: FieldId) -> heed::Result<BTreeMap<String, u64>> {
+    fn facet_values(
+        &self,
+        field_id: FieldId,
+        order_by: OrderBy,
+    ) -> heed::Result<IndexMap<String, u64>> {
+        // we must see all the values to find the most frequent ones.
+        let max_values = match order_by {
+            OrderBy::Lexicographic => self.max_values_per_facet,
+            OrderBy::Count => usize::MAX,
+        };
+
+        let distribution = self.facet_values_in_lexicographic_order(field_id, max_values)?;
+        match order_by {
+            OrderBy::Lexicographic => Ok(distribution.into_iter().collect()),
+            OrderBy::Count => {
+                let mut distribution: Vec<_> = distribution.into_iter().collect();
+                // the sort is stable, values with the same count stay lexicographically ordered.
+                distribution.sort_by_key(|(_, count)| Reverse(*count));
+                distribution.truncate(self.max_values_per_facet);
+                Ok(distribution.into_iter().collect())
+            }
+        }
+    }
+
+    fn facet_values_in_lexicographic_order(
+        &self,
+        field_id: FieldId,
+        max_values: usize,
+    ) -> heed::Result<BTreeMap<String, u64>> {
         use FacetType::{Number, String};
 
         match self.candidates {
@@ -229,33 +288,80 @@ impl<'a> FacetDistribution<'a> {
                         field_id,
                         Number,
                         candidates,
+                        max_values,
                         &mut distribution,
                     )?;
                     self.facet_distribution_from_documents(
                         field_id,
                         String,
                         candidates,
+                        max_values,
                         &mut distribution,
                     )?;
                 } else {
                     self.facet_numbers_distribution_from_facet_levels(
                         field_id,
                         candidates,
+                        max_values,
                         &mut distribution,
                     )?;
                     self.facet_strings_distribution_from_facet_levels(
                         field_id,
                         candidates,
+                        max_values,
                         &mut distribution,
                     )?;
                 }
                 Ok(distribution)
             }
-            None => self.facet_values_from_raw_facet_database(field_id),
+            None => self.facet_values_from_raw_facet_database(field_id, max_values),
         }
     }
 
-    pub fn execute(&self) -> Result<BTreeMap<String, BTreeMap<String, u64>>> {
+    /// Returns the minimum and maximum number of the numeric facets, computed on the candidates
+    /// by using the facet levels. Facets without any number are not returned.
+    pub fn compute_stats(&self) -> Result<BTreeMap<String, (f64, f64)>> {
+        let candidates = match self.candidates {
+            Some(ref candidates) => candidates.clone(),
+            None => self.index.documents_ids(self.rtxn)?,
+        };
+
+        let mut stats = BTreeMap::new();
+        for (fid, name) in self.faceted_fields()? {
+            let min =
+                FacetNumberIter::new_reducing(self.rtxn, self.index, fid, candidates.clone())?
+                    .next()
+                    .transpose()?;
+            let max = FacetNumberIter::new_reverse_reducing(
+                self.rtxn,
+                self.index,
+                fid,
+                candidates.clone(),
+            )?
+            .next()
+            .transpose()?;
+
+            if let (Some((min, _)), Some((max, _))) = (min, max) {
+                stats.insert(name, (min, max));
+            }
+        }
+
+        Ok(stats)
+    }
+
+    pub fn execute(&self) -> Result<BTreeMap<String, IndexMap<String, u64>>> {
+        let mut distribution = BTreeMap::new();
+        for (fid, name) in self.faceted_fields()? {
+            let order_by = self.facets_order_by.get(&name).copied().unwrap_or_default();
+            let values = self.facet_values(fid, order_by)?;
+            distribution.insert(name, values);
+        }
+
+        Ok(distribution)
+    }
+
+    /// Returns the fields to compute the distribution of, checking that they are filterable.
+    fn faceted_fields(&self) -> Result<Vec<(FieldId, String)>> {
         let fields_ids_map = self.index.fields_ids_map(self.rtxn)?;
         let filterable_fields = self.index.filterable_fields(self.rtxn)?;
 
@@ -277,25 +383,28 @@ impl<'a> FacetDistribution<'a> {
             None => filterable_fields,
         };
 
-        let mut distribution = BTreeMap::new();
-        for (fid, name) in fields_ids_map.iter() {
-            if crate::is_faceted(name, &fields) {
-                let values = self.facet_values(fid)?;
-                distribution.insert(name.to_string(), values);
-            }
-        }
-
-        Ok(distribution)
+        Ok(fields_ids_map
+            .iter()
+            .filter(|(_, name)| crate::is_faceted(name, &fields))
+            .map(|(fid, name)| (fid, name.to_string()))
+            .collect())
     }
 }
 
 impl fmt::Debug for FacetDistribution<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let FacetDistribution { facets, candidates, max_values_per_facet, rtxn: _, index: _ } =
-            self;
+        let FacetDistribution {
+            facets,
+            facets_order_by,
+            candidates,
+            max_values_per_facet,
+            rtxn: _,
+            index: _,
+        } = self;
 
         f.debug_struct("FacetDistribution")
             .field("facets", facets)
+            .field("facets_order_by", facets_order_by)
             .field("candidates", candidates)
             .field("max_values_per_facet", max_values_per_facet)
             .finish()
@@ -0,0 +1,170 @@
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+use std::fmt;
+
+use roaring::RoaringBitmap;
+use serde::Serialize;
+
+use crate::error::UserError;
+use crate::search::{word_derivations, WordDerivationsCache};
+use crate::update::compute_facet_strings_fst;
+use crate::{Index, Result};
+
+/// The default number of facet values returned by a facet search.
+pub const DEFAULT_MAX_FACET_HITS: usize = 100;
+
+/// A facet value matching the query of a [`FacetSearch`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FacetValueHit {
+    /// The original facet value, as found in the documents.
+    pub value: String,
+    /// The number of candidates containing this facet value.
+    pub count: u64,
+}
+
+/// Searches for the string values of a filterable field matching a query,
+/// the query is considered as a prefix and can contain typos.
+pub struct FacetSearch<'a> {
+    facet: String,
+    query: Option<String>,
+    candidates: Option<RoaringBitmap>,
+    max_values: usize,
+    authorize_typos: bool,
+    rtxn: &'a heed::RoTxn<'a>,
+    index: &'a Index,
+}
+
+impl<'a> FacetSearch<'a> {
+    pub fn new(
+        rtxn: &'a heed::RoTxn,
+        index: &'a Index,
+        facet: impl Into<String>,
+    ) -> FacetSearch<'a> {
+        FacetSearch {
+            facet: facet.into(),
+            query: None,
+            candidates: None,
+            max_values: DEFAULT_MAX_FACET_HITS,
+            authorize_typos: true,
+            rtxn,
+            index,
+        }
+    }
+
+    pub fn query(&mut self, query: impl Into<String>) -> &mut Self {
+        self.query = Some(query.into());
+        self
+    }
+
+    /// Only count the documents of these candidates, facet values
+    /// that none of the candidates contain are not returned.
+    pub fn candidates(&mut self, candidates: RoaringBitmap) -> &mut Self {
+        self.candidates = Some(candidates);
+        self
+    }
+
+    pub fn max_values(&mut self, max: usize) -> &mut Self {
+        self.max_values = max;
+        self
+    }
+
+    pub fn authorize_typos(&mut self, value: bool) -> &mut Self {
+        self.authorize_typos = value;
+        self
+    }
+
+    /// Returns the number of typos allowed for the given normalized query,
+    /// following the same rules as the words of a search query.
+    fn max_typo(&self, query: &str) -> Result<u8> {
+        if query.is_empty() || !self.authorize_typos || !self.index.authorize_typos(self.rtxn)? {
+            return Ok(0);
+        }
+
+        let count = query.chars().count().min(u8::MAX as usize) as u8;
+        if count < self.index.min_word_len_one_typo(self.rtxn)? {
+            Ok(0)
+        } else if count < self.index.min_word_len_two_typos(self.rtxn)? {
+            Ok(1)
+        } else {
+            Ok(2)
+        }
+    }
+
+    pub fn execute(&self) -> Result<Vec<FacetValueHit>> {
+        let filterable_fields = self.index.filterable_fields(self.rtxn)?;
+        if !crate::is_faceted(&self.facet, &filterable_fields) {
+            let valid_fields: BTreeSet<_> = filterable_fields.into_iter().collect();
+            return Err(UserError::InvalidFacetSearchFacetName {
+                field: self.facet.clone(),
+                valid_fields,
+            }
+            .into());
+        }
+
+        let fields_ids_map = self.index.fields_ids_map(self.rtxn)?;
+        let field_id = match fields_ids_map.id(&self.facet) {
+            Some(field_id) => field_id,
+            None => return Ok(Vec::new()),
+        };
+
+        // facet values are normalized the same way at indexing time.
+        let query = self.query.as_deref().unwrap_or_default().trim().to_lowercase();
+        let max_typo = self.max_typo(&query)?;
+        let fst = match self.index.string_faceted_values_fst(self.rtxn, field_id)? {
+            Some(fst) => fst,
+            None => {
+                let db = self.index.facet_id_string_docids;
+                let fst = compute_facet_strings_fst(self.rtxn, db, field_id)?;
+                fst.map_data(Cow::Owned)?
+            }
+        };
+
+        let mut cache = WordDerivationsCache::new();
+        let mut derivations = word_derivations(&query, true, max_typo, &fst, &mut cache)?.to_vec();
+        // the values with the fewest typos come first, the order
+        // of the FST is kept for the values with the same typos.
+        derivations.sort_by_key(|(_, typo)| *typo);
+
+        let mut hits = Vec::new();
+        for (normalized, _typo) in derivations {
+            if hits.len() == self.max_values {
+                break;
+            }
+
+            let key = (field_id, normalized.as_str());
+            let value = self.index.facet_id_string_docids.get(self.rtxn, &key)?;
+            if let Some((original, mut docids)) = value {
+                if let Some(candidates) = &self.candidates {
+                    docids &= candidates;
+                }
+                if !docids.is_empty() {
+                    hits.push(FacetValueHit { value: original.to_string(), count: docids.len() });
+                }
+            }
+        }
+
+        Ok(hits)
+    }
+}
+
+impl fmt::Debug for FacetSearch<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let FacetSearch {
+            facet,
+            query,
+            candidates,
+            max_values,
+            authorize_typos,
+            rtxn: _,
+            index: _,
+        } = self;
+
+        f.debug_struct("FacetSearch")
+            .field("facet", facet)
+            .field("query", query)
+            .field("candidates", candidates)
+            .field("max_values", max_values)
+            .field("authorize_typos", authorize_typos)
+            .finish()
+    }
+}
@@ -7,12 +7,14 @@ pub use filter_parser::{Condition, Error as FPError, FilterCondition, Span, Toke
 use heed::types::DecodeIgnore;
 use log::debug;
 use roaring::RoaringBitmap;
+use rstar::{RTree, AABB};
 
 use super::FacetNumberRange;
 use crate::error::{Error, UserError};
 use crate::heed_codec::facet::FacetLevelValueF64Codec;
 use crate::{
-    distance_between_two_points, lat_lng_to_xyz, CboRoaringBitmapCodec, FieldId, Index, Result,
+    distance_between_two_points, lat_lng_to_xyz, CboRoaringBitmapCodec, FieldId, GeoPoint, Index,
+    Result,
 };
 
 /// The maximum number of filters the filter AST can process.
@@ -29,6 +31,7 @@ enum FilterError<'a> {
     BadGeo(&'a str),
     BadGeoLat(f64),
     BadGeoLng(f64),
+    BadGeoBoundingBoxTopIsBelowBottom(f64, f64),
     Reserved(&'a str),
     TooDeep,
 }
@@ -64,9 +67,10 @@ impl<'a> Display for FilterError<'a> {
                 "`{}` is a reserved keyword and thus can't be used as a filter expression.",
                 keyword
             ),
-            Self::BadGeo(keyword) => write!(f, "`{}` is a reserved keyword and thus can't be used as a filter expression. Use the _geoRadius(latitude, longitude, distance) built-in rule to filter on _geo field coordinates.", keyword),
+            Self::BadGeo(keyword) => write!(f, "`{}` is a reserved keyword and thus can't be used as a filter expression. Use the _geoRadius(latitude, longitude, distance), _geoBoundingBox([latitude, longitude], [latitude, longitude]) or _geoPolygon([[latitude, longitude], ...]) built-in rules to filter on _geo field coordinates.", keyword),
             Self::BadGeoLat(lat) => write!(f, "Bad latitude `{}`. Latitude must be contained between -90 and 90 degrees. ", lat),
             Self::BadGeoLng(lng) => write!(f, "Bad longitude `{}`. Longitude must be contained between -180 and 180 degrees. ", lng),
+            Self::BadGeoBoundingBoxTopIsBelowBottom(top, bottom) => write!(f, "The top latitude `{}` is below the bottom latitude `{}`.", top, bottom),
         }
     }
 }
@@ -142,6 +146,62 @@ impl<'a> Filter<'a> {
 
         Ok(Some(Self { condition }))
     }
+
+    /// Returns the center and the radius of the `_geoRadius` that every document
+    /// matching this filter must satisfy, if any.
+    pub(crate) fn geo_radius(&self) -> Option<([f64; 2], f64)> {
+        fn inner(condition: &FilterCondition) -> Option<([f64; 2], f64)> {
+            match condition {
+                FilterCondition::GeoLowerThan { point, radius } => {
+                    let point = parse_geo_point(point).ok()?;
+                    let radius = radius.parse().ok()?;
+                    Some((point, radius))
+                }
+                FilterCondition::And(subfilters) => subfilters.iter().find_map(inner),
+                _ => None,
+            }
+        }
+
+        inner(&self.condition)
+    }
+
+    /// Returns this filter without its top-level `AND` clauses that only filter
+    /// on the given field, `None` if there is no clause left.
+    pub(crate) fn without_field(&self, field: &str) -> Option<Filter<'a>> {
+        fn only_filters_on(condition: &FilterCondition, field: &str) -> bool {
+            match condition {
+                FilterCondition::Not(condition) => only_filters_on(condition, field),
+                FilterCondition::Condition { fid, .. } | FilterCondition::In { fid, .. } => {
+                    fid.value() == field
+                }
+                FilterCondition::Or(subfilters) | FilterCondition::And(subfilters) => {
+                    subfilters.iter().all(|condition| only_filters_on(condition, field))
+                }
+                FilterCondition::GeoLowerThan { .. }
+                | FilterCondition::GeoBoundingBox { .. }
+                | FilterCondition::GeoPolygon { .. } => field == "_geo",
+            }
+        }
+
+        let condition = match &self.condition {
+            FilterCondition::And(subfilters) => {
+                let mut subfilters: Vec<_> = subfilters
+                    .iter()
+                    .filter(|condition| !only_filters_on(condition, field))
+                    .cloned()
+                    .collect();
+                match subfilters.len() {
+                    0 => return None,
+                    1 => subfilters.pop().unwrap(),
+                    _ => FilterCondition::And(subfilters),
+                }
+            }
+            condition if only_filters_on(condition, field) => return None,
+            condition => condition.clone(),
+        };
+
+        Some(Filter { condition })
+    }
 }
 
 impl<'a> Filter<'a> {
@@ -448,17 +508,7 @@ impl<'a> Filter<'a> {
             }
             FilterCondition::GeoLowerThan { point, radius } => {
                 if filterable_fields.contains("_geo") {
-                    let base_point: [f64; 2] = [point[0].parse()?, point[1].parse()?];
-                    if !(-90.0..=90.0).contains(&base_point[0]) {
-                        return Err(
-                            point[0].as_external_error(FilterError::BadGeoLat(base_point[0]))
-                        )?;
-                    }
-                    if !(-180.0..=180.0).contains(&base_point[1]) {
-                        return Err(
-                            point[1].as_external_error(FilterError::BadGeoLng(base_point[1]))
-                        )?;
-                    }
+                    let base_point = parse_geo_point(point)?;
                     let radius = radius.parse()?;
                     let rtree = match index.geo_rtree(rtxn)? {
                         Some(rtree) => rtree,
@@ -483,8 +533,183 @@ impl<'a> Filter<'a> {
                     }))?;
                 }
             }
+            FilterCondition::GeoBoundingBox { top_right_point, bottom_left_point } => {
+                if filterable_fields.contains("_geo") {
+                    let top_right = parse_geo_point(top_right_point)?;
+                    let bottom_left = parse_geo_point(bottom_left_point)?;
+                    if top_right[0] < bottom_left[0] {
+                        return Err(top_right_point[0].as_external_error(
+                            FilterError::BadGeoBoundingBoxTopIsBelowBottom(
+                                top_right[0],
+                                bottom_left[0],
+                            ),
+                        ))?;
+                    }
+                    let rtree = match index.geo_rtree(rtxn)? {
+                        Some(rtree) => rtree,
+                        None => return Ok(RoaringBitmap::new()),
+                    };
+
+                    let points = geo_points_in_bounding_box(&rtree, bottom_left, top_right);
+                    Ok(points.into_iter().map(|point| point.data.0).collect())
+                } else {
+                    return Err(top_right_point[0].as_external_error(
+                        FilterError::AttributeNotFilterable {
+                            attribute: "_geo",
+                            filterable_fields: filterable_fields.clone(),
+                        },
+                    ))?;
+                }
+            }
+            FilterCondition::GeoPolygon { points } => {
+                if filterable_fields.contains("_geo") {
+                    let polygon: Vec<_> =
+                        points.iter().map(parse_geo_point).collect::<Result<_>>()?;
+                    let rtree = match index.geo_rtree(rtxn)? {
+                        Some(rtree) => rtree,
+                        None => return Ok(RoaringBitmap::new()),
+                    };
+
+                    Ok(geo_points_in_polygon(&rtree, &polygon))
+                } else {
+                    return Err(points[0][0].as_external_error(
+                        FilterError::AttributeNotFilterable {
+                            attribute: "_geo",
+                            filterable_fields: filterable_fields.clone(),
+                        },
+                    ))?;
+                }
+            }
+        }
+    }
+}
+
+/// Parses a `[latitude, longitude]` point and checks that it is a valid coordinate.
+fn parse_geo_point(point: &[Token; 2]) -> Result<[f64; 2]> {
+    let [lat, lng] = point;
+    let geo_point: [f64; 2] = [lat.parse()?, lng.parse()?];
+    if !(-90.0..=90.0).contains(&geo_point[0]) {
+        return Err(lat.as_external_error(FilterError::BadGeoLat(geo_point[0])))?;
+    }
+    if !(-180.0..=180.0).contains(&geo_point[1]) {
+        return Err(lng.as_external_error(FilterError::BadGeoLng(geo_point[1])))?;
+    }
+    Ok(geo_point)
+}
+
+/// Returns the geo points inside of the box, the box crosses the antimeridian
+/// when its left longitude is greater than its right longitude.
+fn geo_points_in_bounding_box(
+    rtree: &RTree<GeoPoint>,
+    [bottom, left]: [f64; 2],
+    [top, right]: [f64; 2],
+) -> Vec<&GeoPoint> {
+    let longitudes =
+        if left <= right { vec![(left, right)] } else { vec![(left, 180.0), (-180.0, right)] };
+
+    let mut points = Vec::new();
+    for (left, right) in longitudes {
+        let envelope = xyz_envelope(bottom, top, left, right);
+        for point in rtree.locate_in_envelope(&envelope) {
+            let [lat, lng] = point.data.1;
+            if (bottom..=top).contains(&lat) && (left..=right).contains(&lng) {
+                points.push(point);
+            }
+        }
+    }
+    points
+}
+
+/// Returns the documents with a geo point inside of the polygon. An edge of the polygon takes
+/// the shortest way between its two points, it crosses the antimeridian when their longitudes
+/// are more than 180 degrees apart.
+fn geo_points_in_polygon(rtree: &RTree<GeoPoint>, polygon: &[[f64; 2]]) -> RoaringBitmap {
+    // We unwrap the longitudes for the polygon to never cross the antimeridian,
+    // the longitudes can therefore go beyond the [-180, 180] range.
+    let mut unwrapped = Vec::with_capacity(polygon.len());
+    let mut previous_lng = polygon[0][1];
+    for &[lat, mut lng] in polygon {
+        while lng - previous_lng > 180.0 {
+            lng -= 360.0;
+        }
+        while previous_lng - lng > 180.0 {
+            lng += 360.0;
+        }
+        unwrapped.push([lat, lng]);
+        previous_lng = lng;
+    }
+
+    let bottom = unwrapped.iter().map(|[lat, _]| *lat).fold(f64::INFINITY, f64::min);
+    let top = unwrapped.iter().map(|[lat, _]| *lat).fold(f64::NEG_INFINITY, f64::max);
+    let mut left = unwrapped.iter().map(|[_, lng]| *lng).fold(f64::INFINITY, f64::min);
+    let mut right = unwrapped.iter().map(|[_, lng]| *lng).fold(f64::NEG_INFINITY, f64::max);
+
+    if right - left >= 360.0 {
+        left = -180.0;
+        right = 180.0;
+    } else {
+        // We move the envelope back into the [-180, 180] range,
+        // it crosses the antimeridian if its right side is beyond.
+        let shift = ((left + 180.0) / 360.0).floor() * 360.0;
+        left -= shift;
+        right -= shift;
+        if right > 180.0 {
+            right -= 360.0;
+        }
+    }
+
+    geo_points_in_bounding_box(rtree, [bottom, left], [top, right])
+        .into_iter()
+        .filter(|point| {
+            let [lat, lng] = point.data.1;
+            [lng - 360.0, lng, lng + 360.0]
+                .iter()
+                .any(|lng| polygon_contains(&unwrapped, [lat, *lng]))
+        })
+        .map(|point| point.data.0)
+        .collect()
+}
+
+/// Returns `true` if the point is inside of the polygon, using the even-odd rule
+/// on the latitudes and longitudes as if they were planar coordinates.
+fn polygon_contains(polygon: &[[f64; 2]], [lat, lng]: [f64; 2]) -> bool {
+    let previous_points = polygon.iter().cycle().skip(polygon.len() - 1);
+
+    let mut inside = false;
+    for (&[lat_a, lng_a], &[lat_b, lng_b]) in polygon.iter().zip(previous_points) {
+        if (lat_a > lat) != (lat_b > lat)
+            && lng < (lng_b - lng_a) * (lat - lat_a) / (lat_b - lat_a) + lng_a
+        {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+/// Returns the smallest envelope, in the cartesian coordinates of the rtree,
+/// containing all the points between the given latitudes and longitudes.
+fn xyz_envelope(bottom: f64, top: f64, left: f64, right: f64) -> AABB<[f64; 3]> {
+    // The cartesian coordinates are products of the sines and cosines of the latitude and
+    // the longitude, they are extremal either on the bounds or where these functions are.
+    let lats = [bottom, top, 0.0];
+    let lngs = [left, right, -180.0, -90.0, 0.0, 90.0, 180.0];
+
+    let mut min = [f64::INFINITY; 3];
+    let mut max = [f64::NEG_INFINITY; 3];
+    for lat in lats.iter().filter(|lat| (bottom..=top).contains(*lat)) {
+        for lng in lngs.iter().filter(|lng| (left..=right).contains(*lng)) {
+            let xyz = lat_lng_to_xyz(&[*lat, *lng]);
+            for ((min, max), c) in min.iter_mut().zip(max.iter_mut()).zip(xyz.iter()) {
+                *min = min.min(*c);
+                *max = max.max(*c);
+            }
         }
     }
+
+    // protects the points on the bounds from the rounding errors.
+    let min = min.map(|c| c - f64::EPSILON * 4.0);
+    let max = max.map(|c| c + f64::EPSILON * 4.0);
+    AABB::from_corners(min, max)
 }
 
 impl<'a> From<FilterCondition<'a>> for Filter<'a> {
@@ -676,6 +901,122 @@ mod tests {
         assert_eq!(documents_ids, vec![2]);
     }
 
+    #[test]
+    fn geo_bounding_box() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("_geo") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": "paris",     "_geo": { "lat": 48.8566, "lng": 2.3522 } },
+                { "id": "lille",     "_geo": { "lat": 50.6299, "lng": 3.0569 } },
+                { "id": "brussels",  "_geo": { "lat": 50.8503, "lng": 4.3517 } },
+                { "id": "fiji",      "_geo": { "lat": -17.7134, "lng": 178.0650 } },
+                { "id": "samoa",     "_geo": { "lat": -13.7590, "lng": -172.1046 } },
+                { "id": "sydney",    "_geo": { "lat": -33.8688, "lng": 151.2093 } },
+                { "id": "no_geo" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter: &str| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.evaluate(&rtxn, &index).unwrap().into_iter().collect::<Vec<_>>()
+        };
+
+        // the box contains Lille and Brussels but not Paris.
+        assert_eq!(evaluate("_geoBoundingBox([51, 5], [50, 2])"), vec![1, 2]);
+        // the points on the bounds are part of the box.
+        assert_eq!(evaluate("_geoBoundingBox([50.6299, 3.0569], [48.8566, 2.3522])"), vec![0, 1]);
+        assert_eq!(evaluate("NOT _geoBoundingBox([51, 5], [50, 2])"), vec![0, 3, 4, 5, 6]);
+        // the box crosses the antimeridian, it contains Fiji and Samoa.
+        assert_eq!(evaluate("_geoBoundingBox([-10, -170], [-20, 170])"), vec![3, 4]);
+        // the whole world.
+        assert_eq!(evaluate("_geoBoundingBox([90, 180], [-90, -180])"), vec![0, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn geo_polygon() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("_geo") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": "paris",     "_geo": { "lat": 48.8566, "lng": 2.3522 } },
+                { "id": "lille",     "_geo": { "lat": 50.6299, "lng": 3.0569 } },
+                { "id": "brussels",  "_geo": { "lat": 50.8503, "lng": 4.3517 } },
+                { "id": "reims",     "_geo": { "lat": 49.2583, "lng": 4.0317 } },
+                { "id": "fiji",      "_geo": { "lat": -17.7134, "lng": 178.0650 } },
+                { "id": "samoa",     "_geo": { "lat": -13.7590, "lng": -172.1046 } },
+                { "id": "sydney",    "_geo": { "lat": -33.8688, "lng": 151.2093 } },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let evaluate = |filter: &str| {
+            let filter = Filter::from_str(filter).unwrap().unwrap();
+            filter.evaluate(&rtxn, &index).unwrap().into_iter().collect::<Vec<_>>()
+        };
+
+        assert_eq!(evaluate("_geoPolygon([[52, 1], [52, 6], [48, 6], [48, 1]])"), vec![0, 1, 2, 3]);
+        // Reims is in the envelope of the triangle but not in the triangle itself.
+        assert_eq!(evaluate("_geoPolygon([[51, 2], [51, 5], [49, 5]])"), vec![1, 2]);
+        assert_eq!(evaluate("NOT _geoPolygon([[51, 2], [51, 5], [49, 5]])"), vec![0, 3, 4, 5, 6]);
+        // the polygon crosses the antimeridian, it contains Fiji and Samoa.
+        assert_eq!(
+            evaluate("_geoPolygon([[-10, 170], [-10, -170], [-20, -170], [-20, 170]])"),
+            vec![4, 5]
+        );
+        assert_eq!(evaluate("_geoPolygon([[-10, 170], [-20, -170], [-20, 170]])"), vec![4]);
+
+        let filter = Filter::from_str("_geoPolygon([[10, 10], [10, 20], [100, 10]])").unwrap();
+        let error = filter.unwrap().evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().starts_with(
+            "Bad latitude `100`. Latitude must be contained between -90 and 90 degrees."
+        ));
+    }
+
+    #[test]
+    fn geo_bounding_box_error() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("_geo") });
+            })
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        let filter = Filter::from_str("_geoBoundingBox([-100, 10], [10, 10])").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().starts_with(
+            "Bad latitude `-100`. Latitude must be contained between -90 and 90 degrees."
+        ));
+
+        let filter = Filter::from_str("_geoBoundingBox([10, 10], [10, 181])").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error.to_string().starts_with(
+            "Bad longitude `181`. Longitude must be contained between -180 and 180 degrees."
+        ));
+
+        let filter = Filter::from_str("_geoBoundingBox([10, 10], [20, 0])").unwrap().unwrap();
+        let error = filter.evaluate(&rtxn, &index).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("The top latitude `10` is below the bottom latitude `20`."));
+    }
+
     #[test]
     fn geo_radius_error() {
         let index = TempIndex::new();
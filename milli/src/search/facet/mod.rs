@@ -1,9 +1,11 @@
-pub use self::facet_distribution::{FacetDistribution, DEFAULT_VALUES_PER_FACET};
+pub use self::facet_distribution::{FacetDistribution, OrderBy, DEFAULT_VALUES_PER_FACET};
 pub use self::facet_number::{FacetNumberIter, FacetNumberRange, FacetNumberRevRange};
+pub use self::facet_search::{FacetSearch, FacetValueHit};
 pub use self::facet_string::FacetStringIter;
 pub use self::filter::Filter;
 
 mod facet_distribution;
 mod facet_number;
+mod facet_search;
 mod facet_string;
 mod filter;
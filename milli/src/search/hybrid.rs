@@ -0,0 +1,151 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use roaring::RoaringBitmap;
+
+use super::{ScoreDetails, Search, SearchResult};
+use crate::{DocumentId, Result};
+
+/// The constant of the reciprocal rank fusion, it reduces the
+/// weight of the first ranks compared to the following ones.
+const RRF_K: f64 = 60.0;
+
+impl<'a> Search<'a> {
+    /// Merges the keyword and the vector rankings using a reciprocal rank fusion, each
+    /// ranking is weighted by the semantic ratio and a document ranked by both is only
+    /// returned once, with the score details of both rankings.
+    ///
+    /// The ranking score threshold is applied to both rankings. The candidates are the ones
+    /// of the keyword ranking along with the documents of the window of the vector ranking,
+    /// as every document with a vector is a candidate of the vector ranking, the candidates
+    /// are estimated when the vector ranking has more documents than its window.
+    pub(super) fn hybrid_search(&self, vector: &[f32]) -> Result<SearchResult> {
+        // both rankings must contain the documents of the requested page.
+        let window = self.offset + self.limit;
+
+        let mut keyword_search = self.clone();
+        keyword_search.vector = None;
+        keyword_search.offset = 0;
+        keyword_search.limit = window;
+        let keyword = keyword_search.keyword_search()?;
+
+        let mut vector_search = self.clone();
+        vector_search.query = None;
+        vector_search.offset = 0;
+        vector_search.limit = window;
+        let semantic = vector_search.vector_search(vector)?;
+
+        let semantic_window = semantic.documents_ids.iter().collect::<RoaringBitmap>();
+        // the documents of the vector ranking after its window are not counted.
+        let candidates_estimated =
+            keyword.candidates_estimated || semantic.candidates.len() > semantic_window.len();
+        let candidates = keyword.candidates | semantic_window;
+
+        let semantic_ratio = self.semantic_ratio as f64;
+        let rankings = [
+            (keyword.documents_ids, keyword.document_scores, 1.0 - semantic_ratio),
+            (semantic.documents_ids, semantic.document_scores, semantic_ratio),
+        ];
+
+        let mut fused: HashMap<DocumentId, (f64, Vec<ScoreDetails>)> = HashMap::new();
+        for (documents_ids, document_scores, weight) in rankings {
+            for (rank, (docid, details)) in
+                documents_ids.into_iter().zip(document_scores).enumerate()
+            {
+                let (score, fused_details) = fused.entry(docid).or_default();
+                *score += weight / (RRF_K + rank as f64 + 1.0);
+                fused_details.extend(details);
+            }
+        }
+
+        let mut fused: Vec<_> = fused.into_iter().collect();
+        // the best scores first, the smallest ids first in case of equality.
+        fused.sort_by(|(aid, (a, _)), (bid, (b, _))| {
+            b.partial_cmp(a).unwrap_or(Ordering::Equal).then_with(|| aid.cmp(bid))
+        });
+
+        let (documents_ids, document_scores): (Vec<_>, Vec<_>) = fused
+            .into_iter()
+            .skip(self.offset)
+            .take(self.limit)
+            .map(|(docid, (_, details))| (docid, details))
+            .unzip();
+        let geo_distances = self.geo_distances(&documents_ids, &document_scores)?;
+
+        Ok(SearchResult {
+            matching_words: keyword.matching_words,
+            candidates,
+            candidates_estimated,
+            documents_ids,
+            document_scores,
+            geo_distances,
+            facet_distribution: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use roaring::RoaringBitmap;
+
+    use crate::index::tests::TempIndex;
+    use crate::{ScoreDetails, Search, SearchResult};
+
+    #[test]
+    fn hybrid_search() {
+        let index = TempIndex::new();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "title": "apple pie", "_vectors": [1.0, 0.0] },
+                { "id": 1, "title": "banana bread", "_vectors": [0.6, 0.8] },
+                { "id": 2, "title": "apple tart", "_vectors": [0.0, 1.0] },
+                { "id": 3, "title": "cherry cake" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let search = |ratio: f32, offset: usize, limit: usize| {
+            let mut search = Search::new(&rtxn, &index);
+            search.query("apple").vector(vec![0.0, 1.0]).semantic_ratio(ratio);
+            search.offset(offset).limit(limit);
+            search.execute().unwrap()
+        };
+
+        // only the keyword ranking.
+        let SearchResult { documents_ids, candidates, .. } = search(0.0, 0, 20);
+        assert_eq!(documents_ids, vec![0, 2]);
+        assert_eq!(candidates, [0, 2].iter().copied().collect::<RoaringBitmap>());
+
+        // only the vector ranking.
+        let SearchResult { documents_ids, .. } = search(1.0, 0, 20);
+        assert_eq!(documents_ids, vec![2, 1, 0]);
+
+        // the document ranked by both is returned first and only once.
+        let SearchResult { documents_ids, candidates, document_scores, .. } = search(0.5, 0, 20);
+        assert_eq!(documents_ids, vec![2, 0, 1]);
+        assert_eq!(candidates, (0..3).collect::<RoaringBitmap>());
+        // the documents only ranked by their vector are not perfect matches.
+        assert!(ScoreDetails::global_score(document_scores[2].iter()) < 1.0);
+
+        // the pages are computed on the merged ranking.
+        let SearchResult { documents_ids, .. } = search(0.5, 1, 1);
+        assert_eq!(documents_ids, vec![0]);
+
+        // the candidates of the vector ranking are limited to its window, the document 1
+        // isn't part of it. The documents first in each ranking are tied.
+        let SearchResult { documents_ids, candidates, candidates_estimated, .. } =
+            search(0.5, 0, 1);
+        assert_eq!(documents_ids, vec![0]);
+        assert_eq!(candidates, [0, 2].iter().copied().collect::<RoaringBitmap>());
+        assert!(candidates_estimated);
+
+        // the threshold is applied to the vector ranking too, the similarity of
+        // the vector of the document 0 to the one of the search is 0.0.
+        let mut search = Search::new(&rtxn, &index);
+        search.query("banana").vector(vec![0.0, 1.0]).semantic_ratio(0.5);
+        search.ranking_score_threshold(0.6);
+        let SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![1, 2]);
+    }
+}
@@ -53,7 +53,8 @@ impl<'a> Iterator for MatchesIter<'a, '_> {
                             char_len,
                         }))
                     } else {
-                        Some(MatchType::Full { char_len, ids })
+                        let synonym_of = matching_words[0].synonym_of.as_deref();
+                        Some(MatchType::Full { char_len, ids, synonym_of })
                     }
                 }
                 None => self.next(),
@@ -72,6 +73,8 @@ pub struct MatchingWord {
     pub word: String,
     pub typo: u8,
     pub prefix: IsPrefix,
+    /// The query words this term has been derived from when it is a synonym of them.
+    pub synonym_of: Option<String>,
 }
 
 impl fmt::Debug for MatchingWord {
@@ -80,13 +83,17 @@ impl fmt::Debug for MatchingWord {
             .field("word", &self.word)
             .field("typo", &self.typo)
             .field("prefix", &self.prefix)
+            .field("synonym_of", &self.synonym_of)
             .finish()
     }
 }
 
 impl PartialEq for MatchingWord {
     fn eq(&self, other: &Self) -> bool {
-        self.prefix == other.prefix && self.typo == other.typo && self.word == other.word
+        self.prefix == other.prefix
+            && self.typo == other.typo
+            && self.word == other.word
+            && self.synonym_of == other.synonym_of
     }
 }
 
@@ -94,7 +101,13 @@ impl MatchingWord {
     pub fn new(word: String, typo: u8, prefix: IsPrefix) -> Self {
         let dfa = build_dfa(&word, typo, prefix);
 
-        Self { dfa, word, typo, prefix }
+        Self { dfa, word, typo, prefix, synonym_of: None }
+    }
+
+    /// Marks this term as a synonym of the given query words.
+    pub fn synonym_of(mut self, words: impl Into<String>) -> Self {
+        self.synonym_of = Some(words.into());
+        self
     }
 
     /// Returns the lenght in chars of the match in case of the token matches the term.
@@ -119,7 +132,7 @@ impl MatchingWord {
 /// In these cases we need to match consecutively several tokens to consider that the match is full.
 #[derive(Debug, PartialEq)]
 pub enum MatchType<'a> {
-    Full { char_len: usize, ids: &'a [PrimitiveWordId] },
+    Full { char_len: usize, ids: &'a [PrimitiveWordId], synonym_of: Option<&'a str> },
     Partial(PartialMatch<'a>),
 }
 
@@ -145,7 +158,8 @@ impl<'a> PartialMatch<'a> {
                     char_len,
                 })
             } else {
-                MatchType::Full { char_len, ids: self.ids }
+                let synonym_of = self.matching_words[0].synonym_of.as_deref();
+                MatchType::Full { char_len, ids: self.ids, synonym_of }
             }
         })
     }
@@ -350,7 +364,7 @@ mod tests {
                     ..Default::default()
                 })
                 .next(),
-            Some(MatchType::Full { char_len: 3, ids: &[2] })
+            Some(MatchType::Full { char_len: 3, ids: &[2], synonym_of: None })
         );
         assert_eq!(
             matching_words
@@ -374,7 +388,7 @@ mod tests {
                     ..Default::default()
                 })
                 .next(),
-            Some(MatchType::Full { char_len: 5, ids: &[2] })
+            Some(MatchType::Full { char_len: 5, ids: &[2], synonym_of: None })
         );
         assert_eq!(
             matching_words
@@ -386,7 +400,7 @@ mod tests {
                     ..Default::default()
                 })
                 .next(),
-            Some(MatchType::Full { char_len: 5, ids: &[0] })
+            Some(MatchType::Full { char_len: 5, ids: &[0], synonym_of: None })
         );
         assert_eq!(
             matching_words
@@ -410,7 +424,7 @@ mod tests {
                     ..Default::default()
                 })
                 .next(),
-            Some(MatchType::Full { char_len: 5, ids: &[2] })
+            Some(MatchType::Full { char_len: 5, ids: &[2], synonym_of: None })
         );
         assert_eq!(
             matching_words
@@ -422,7 +436,7 @@ mod tests {
                     ..Default::default()
                 })
                 .next(),
-            Some(MatchType::Full { char_len: 4, ids: &[2] })
+            Some(MatchType::Full { char_len: 4, ids: &[2], synonym_of: None })
         );
     }
 }
@@ -93,12 +93,17 @@ pub struct Match {
     word_position: usize,
     // position of the token in the whole text.
     token_position: usize,
+    // query words the match is a synonym of.
+    synonym_of: Option<String>,
 }
 
 #[derive(Serialize, Debug, Clone, PartialEq)]
 pub struct MatchBounds {
     pub start: usize,
     pub length: usize,
+    /// The query words this match is a synonym of, if it matched through a synonym.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub synonym_of: Option<String>,
 }
 
 /// Structure used to analize a string, compute words that match,
@@ -139,7 +144,8 @@ impl<'t, A: AsRef<[u8]>> Matcher<'t, '_, A> {
                         partial
                     }
                     // partial match is now full, we keep this matches and we advance positions
-                    Some(MatchType::Full { char_len, ids }) => {
+                    Some(MatchType::Full { char_len, ids, synonym_of }) => {
+                        let synonym_of = synonym_of.map(ToOwned::to_owned);
                         // save previously matched tokens as matches.
                         let iter = potential_matches.into_iter().map(
                             |(token_position, word_position, match_len)| Match {
@@ -147,6 +153,7 @@ impl<'t, A: AsRef<[u8]>> Matcher<'t, '_, A> {
                                 ids: ids.to_vec(),
                                 word_position,
                                 token_position,
+                                synonym_of: synonym_of.clone(),
                             },
                         );
                         matches.extend(iter);
@@ -157,6 +164,7 @@ impl<'t, A: AsRef<[u8]>> Matcher<'t, '_, A> {
                             ids: ids.to_vec(),
                             word_position,
                             token_position,
+                            synonym_of,
                         });
 
                         // the match is complete, we return true.
@@ -193,12 +201,13 @@ impl<'t, A: AsRef<[u8]>> Matcher<'t, '_, A> {
                 match match_type {
                     // we match, we save the current token as a match,
                     // then we continue the rest of the tokens.
-                    MatchType::Full { char_len, ids } => {
+                    MatchType::Full { char_len, ids, synonym_of } => {
                         matches.push(Match {
                             match_len: char_len,
                             ids: ids.to_vec(),
                             word_position,
                             token_position,
+                            synonym_of: synonym_of.map(ToOwned::to_owned),
                         });
                         break;
                     }
@@ -235,6 +244,7 @@ impl<'t, A: AsRef<[u8]>> Matcher<'t, '_, A> {
                 .map(|m| MatchBounds {
                     start: tokens[m.token_position].byte_start,
                     length: m.match_len,
+                    synonym_of: m.synonym_of.clone(),
                 })
                 .collect(),
         }
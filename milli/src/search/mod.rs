@@ -1,42 +1,62 @@
 use std::borrow::Cow;
 use std::collections::hash_map::{Entry, HashMap};
+use std::collections::BTreeMap;
 use std::fmt;
 use std::mem::take;
 use std::result::Result as StdResult;
 use std::str::Utf8Error;
 use std::time::Instant;
 
-use charabia::TokenizerBuilder;
 use distinct::{Distinct, DocIter, FacetDistinct, NoopDistinct};
 use fst::automaton::Str;
 use fst::{Automaton, IntoStreamer, Streamer};
+use heed::types::DecodeIgnore;
+use indexmap::IndexMap;
 use levenshtein_automata::{LevenshteinAutomatonBuilder as LevBuilder, DFA};
 use log::debug;
 use once_cell::sync::Lazy;
 use roaring::bitmap::RoaringBitmap;
 
-pub use self::facet::{FacetDistribution, FacetNumberIter, Filter, DEFAULT_VALUES_PER_FACET};
+pub use self::criteria::CustomRankingRule;
+pub use self::facet::{
+    FacetDistribution, FacetNumberIter, FacetSearch, FacetValueHit, Filter, OrderBy,
+    DEFAULT_VALUES_PER_FACET,
+};
 use self::fst_utils::{Complement, Intersection, StartsWith, Union};
 pub use self::matches::{
     FormatOptions, MatchBounds, Matcher, MatcherBuilder, MatchingWord, MatchingWords,
 };
-use self::query_tree::QueryTreeBuilder;
+pub use self::multi_search::{FederatedHit, FederatedSearchResult, MultiSearch};
+use self::query_tree::{Operation, PrimitiveQuery, QueryRestrictions, QueryTreeBuilder};
+pub use self::score_details::ScoreDetails;
+pub use self::vector::VectorSimilarity;
 use crate::error::UserError;
 use crate::search::criteria::r#final::{Final, FinalResult};
-use crate::{AscDesc, Criterion, DocumentId, Index, Member, Result};
+use crate::{
+    distance_between_two_points, lat_lng_to_xyz, AscDesc, Criterion, DocumentId, FieldId, Index,
+    Member, Result,
+};
 
 // Building these factories is not free.
 static LEVDIST0: Lazy<LevBuilder> = Lazy::new(|| LevBuilder::new(0, true));
 static LEVDIST1: Lazy<LevBuilder> = Lazy::new(|| LevBuilder::new(1, true));
 static LEVDIST2: Lazy<LevBuilder> = Lazy::new(|| LevBuilder::new(2, true));
 
+/// The default weight of the vector ranking in a hybrid search.
+pub const DEFAULT_SEMANTIC_RATIO: f32 = 0.5;
+
 mod criteria;
 mod distinct;
 mod facet;
 mod fst_utils;
+mod hybrid;
 mod matches;
+mod multi_search;
 mod query_tree;
+pub mod score_details;
+mod vector;
 
+#[derive(Clone)]
 pub struct Search<'a> {
     query: Option<String>,
     // this should be linked to the String in the query
@@ -44,10 +64,18 @@ pub struct Search<'a> {
     offset: usize,
     limit: usize,
     sort_criteria: Option<Vec<AscDesc>>,
+    ranking_rules: Option<Vec<Criterion>>,
+    attributes_to_search_on: Option<Vec<String>>,
     terms_matching_strategy: TermsMatchingStrategy,
     authorize_typos: bool,
     words_limit: usize,
     exhaustive_number_hits: bool,
+    ranking_score_threshold: Option<f64>,
+    vector: Option<Vec<f32>>,
+    vector_similarity: VectorSimilarity,
+    semantic_ratio: f32,
+    facets: Option<Vec<String>>,
+    disjunctive_facets: bool,
     rtxn: &'a heed::RoTxn<'a>,
     index: &'a Index,
 }
@@ -60,9 +88,17 @@ impl<'a> Search<'a> {
             offset: 0,
             limit: 20,
             sort_criteria: None,
+            ranking_rules: None,
+            attributes_to_search_on: None,
             terms_matching_strategy: TermsMatchingStrategy::default(),
             authorize_typos: true,
             exhaustive_number_hits: false,
+            ranking_score_threshold: None,
+            vector: None,
+            vector_similarity: VectorSimilarity::default(),
+            semantic_ratio: DEFAULT_SEMANTIC_RATIO,
+            facets: None,
+            disjunctive_facets: false,
             words_limit: 10,
             rtxn,
             index,
@@ -89,6 +125,20 @@ impl<'a> Search<'a> {
         self
     }
 
+    /// Ranks the documents with these ranking rules instead of the ones of the index settings.
+    pub fn ranking_rules(&mut self, ranking_rules: Vec<Criterion>) -> &mut Search<'a> {
+        self.ranking_rules = Some(ranking_rules);
+        self
+    }
+
+    /// Only matches the query words in these searchable attributes, and their nested fields.
+    pub fn attributes_to_search_on(&mut self, attributes: Vec<String>) -> &mut Search<'a> {
+        self.attributes_to_search_on = Some(attributes);
+        self
+    }
+
+    /// The strategy used to make the query words optional, it is ignored when the query
+    /// contains `OR` operators or parentheses, all the words of their operands are required.
     pub fn terms_matching_strategy(&mut self, value: TermsMatchingStrategy) -> &mut Search<'a> {
         self.terms_matching_strategy = value;
         self
@@ -116,6 +166,49 @@ impl<'a> Search<'a> {
         self
     }
 
+    /// Drop the documents whose global ranking score, as computed by
+    /// [`ScoreDetails::global_score`], is lower than the given threshold.
+    pub fn ranking_score_threshold(&mut self, threshold: f64) -> &mut Search<'a> {
+        self.ranking_score_threshold = Some(threshold);
+        self
+    }
+
+    /// Ranks the documents by the similarity of their `_vectors` field to this vector,
+    /// combined with the ranking of the query, if any, according to the semantic ratio.
+    pub fn vector(&mut self, vector: Vec<f32>) -> &mut Search<'a> {
+        self.vector = Some(vector);
+        self
+    }
+
+    pub fn vector_similarity(&mut self, similarity: VectorSimilarity) -> &mut Search<'a> {
+        self.vector_similarity = similarity;
+        self
+    }
+
+    /// The weight of the vector ranking compared to the keyword ranking when searching
+    /// with both a query and a vector, `0.0` only uses the query and `1.0` the vector.
+    pub fn semantic_ratio(&mut self, ratio: f32) -> &mut Search<'a> {
+        self.semantic_ratio = ratio.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Computes the distribution of the values of these facets on the candidates of the search.
+    pub fn facets<I: IntoIterator<Item = A>, A: AsRef<str>>(
+        &mut self,
+        names: I,
+    ) -> &mut Search<'a> {
+        self.facets = Some(names.into_iter().map(|s| s.as_ref().to_string()).collect());
+        self
+    }
+
+    /// Counts the values of each facet as if the filter had no clause on this facet,
+    /// only the top-level `AND` clauses of the filter are considered. The facets are then
+    /// counted on every document matching the query and the filter.
+    pub fn disjunctive_facets(&mut self, value: bool) -> &mut Search<'a> {
+        self.disjunctive_facets = value;
+        self
+    }
+
     fn is_typo_authorized(&self) -> Result<bool> {
         let index_authorizes_typos = self.index.authorize_typos(self.rtxn)?;
         // only authorize typos if both the index and the query allow it.
@@ -123,38 +216,142 @@ impl<'a> Search<'a> {
     }
 
     pub fn execute(&self) -> Result<SearchResult> {
-        // We create the query tree by spliting the query into tokens.
-        let before = Instant::now();
-        let (query_tree, primitive_query, matching_words) = match self.query.as_ref() {
-            Some(query) => {
-                let mut builder = QueryTreeBuilder::new(self.rtxn, self.index)?;
-                builder.terms_matching_strategy(self.terms_matching_strategy);
-
-                builder.authorize_typos(self.is_typo_authorized()?);
-
-                builder.words_limit(self.words_limit);
-                // We make sure that the analyzer is aware of the stop words
-                // this ensures that the query builder is able to properly remove them.
-                let mut tokbuilder = TokenizerBuilder::new();
-                let stop_words = self.index.stop_words(self.rtxn)?;
-                if let Some(ref stop_words) = stop_words {
-                    tokbuilder.stop_words(stop_words);
-                }
+        let mut result = match (&self.vector, &self.query) {
+            (Some(vector), Some(_)) if self.semantic_ratio > 0.0 && self.semantic_ratio < 1.0 => {
+                self.hybrid_search(vector)?
+            }
+            (Some(vector), _) if self.semantic_ratio > 0.0 => self.vector_search(vector)?,
+            _ => self.keyword_search()?,
+        };
+
+        if let Some(facets) = &self.facets {
+            let distribution = self.facet_distribution(facets, &result.candidates)?;
+            result.facet_distribution = Some(distribution);
+        }
+
+        Ok(result)
+    }
+
+    /// Computes the distribution of the facets on the candidates, the values of a disjunctive
+    /// facet are counted on the candidates of the search without the filter on this facet.
+    ///
+    /// When the facets are disjunctive all of them are counted on every document matching the
+    /// query and the filter, not only on the candidates of the search, e.g. those reaching the
+    /// ranking score threshold, so that the counts of all the facets are comparable.
+    fn facet_distribution(
+        &self,
+        facets: &[String],
+        candidates: &RoaringBitmap,
+    ) -> Result<BTreeMap<String, IndexMap<String, u64>>> {
+        let filter = match &self.filter {
+            Some(filter) if self.disjunctive_facets => filter,
+            _ => {
+                let mut distribution = FacetDistribution::new(self.rtxn, self.index);
+                distribution.facets(facets).candidates(candidates.clone());
+                return distribution.execute();
+            }
+        };
+
+        // the documents matching the search without its filter are only resolved once.
+        let unfiltered_candidates = self.unfiltered_candidates()?;
+        let candidates = &unfiltered_candidates & filter.evaluate(self.rtxn, self.index)?;
+        let mut distribution = FacetDistribution::new(self.rtxn, self.index);
+        distribution.facets(facets).candidates(candidates);
+        let mut facet_distribution = distribution.execute()?;
+
+        for facet in facets {
+            let filter_without_facet = filter.without_field(facet);
+            if filter_without_facet.as_ref() == Some(filter) {
+                continue;
+            }
 
-                let tokenizer = tokbuilder.build();
-                let tokens = tokenizer.tokenize(query);
-                builder
-                    .build(tokens)?
-                    .map_or((None, None, None), |(qt, pq, mw)| (Some(qt), Some(pq), Some(mw)))
+            let mut candidates = unfiltered_candidates.clone();
+            if let Some(filter) = filter_without_facet {
+                candidates &= filter.evaluate(self.rtxn, self.index)?;
             }
-            None => (None, None, None),
+
+            let mut distribution = FacetDistribution::new(self.rtxn, self.index);
+            distribution.facets(Some(facet)).candidates(candidates);
+            facet_distribution.extend(distribution.execute()?);
+        }
+
+        Ok(facet_distribution)
+    }
+
+    /// Returns the documents matching the query words and the vector of the search,
+    /// whatever its filter.
+    fn unfiltered_candidates(&self) -> Result<RoaringBitmap> {
+        let vector_candidates = || -> Result<RoaringBitmap> {
+            let vectors = self.index.vectors.remap_data_type::<DecodeIgnore>();
+            vectors.iter(self.rtxn)?.map(|result| Ok(result?.0.get())).collect()
+        };
+
+        let mut candidates = match (&self.vector, &self.query) {
+            (Some(_), Some(_)) if self.semantic_ratio > 0.0 && self.semantic_ratio < 1.0 => {
+                self.keyword_candidates()? | vector_candidates()?
+            }
+            (Some(_), _) if self.semantic_ratio > 0.0 => vector_candidates()?,
+            _ => self.keyword_candidates()?,
+        };
+        candidates -= self.index.soft_deleted_documents_ids(self.rtxn)?;
+
+        Ok(candidates)
+    }
+
+    /// Returns the documents matching the query tree and the restrictions of the query.
+    fn keyword_candidates(&self) -> Result<RoaringBitmap> {
+        let (query, restrictions) = self.build_query_tree()?;
+
+        let mut criteria_builder = criteria::CriteriaBuilder::new(self.rtxn, self.index)?;
+        if let Some(attributes) = &self.attributes_to_search_on {
+            criteria_builder.fields_to_search_on(self.fields_to_search_on(attributes)?);
+        }
+
+        let candidates = match query {
+            Some((query_tree, _, _)) => {
+                let mut wdcache = WordDerivationsCache::new();
+                criteria::resolve_query_tree(&criteria_builder, &query_tree, &mut wdcache)?
+            }
+            None => self.index.documents_ids(self.rtxn)?,
+        };
+
+        if restrictions.is_empty() {
+            Ok(candidates)
+        } else {
+            criteria_builder.restrict_candidates(candidates, &restrictions)
+        }
+    }
+
+    /// Splits the query into tokens and creates its query tree.
+    fn build_query_tree(
+        &self,
+    ) -> Result<(Option<(Operation, PrimitiveQuery, MatchingWords)>, QueryRestrictions)> {
+        let query = match self.query.as_ref() {
+            Some(query) => query,
+            None => return Ok((None, QueryRestrictions::default())),
         };
 
+        let mut builder = QueryTreeBuilder::new(self.rtxn, self.index)?;
+        builder.terms_matching_strategy(self.terms_matching_strategy);
+
+        builder.authorize_typos(self.is_typo_authorized()?);
+
+        builder.words_limit(self.words_limit);
+        builder.build_from_text(query)
+    }
+
+    fn keyword_search(&self) -> Result<SearchResult> {
+        // We create the query tree by spliting the query into tokens.
+        let before = Instant::now();
+        let (query, restrictions) = self.build_query_tree()?;
+        let (query_tree, primitive_query, matching_words) =
+            query.map_or((None, None, None), |(qt, pq, mw)| (Some(qt), Some(pq), Some(mw)));
+
         debug!("query tree: {:?} took {:.02?}", query_tree, before.elapsed());
 
         // We create the original candidates with the facet conditions results.
         let before = Instant::now();
-        let filtered_candidates = match &self.filter {
+        let mut filtered_candidates = match &self.filter {
             Some(condition) => Some(condition.evaluate(self.rtxn, self.index)?),
             None => None,
         };
@@ -184,15 +381,47 @@ impl<'a> Search<'a> {
             }
         }
 
+        let ranking_rules = match &self.ranking_rules {
+            Some(ranking_rules) => {
+                // The fields of the ranking rules of the index are faceted at indexing time,
+                // the ones given at search time must already be faceted.
+                let faceted_fields = self.index.user_defined_faceted_fields(self.rtxn)?;
+                for field in ranking_rules.iter().filter_map(Criterion::field_name) {
+                    if !crate::is_faceted(field, &faceted_fields) {
+                        return Err(UserError::InvalidRankingRuleAttribute {
+                            field: field.to_string(),
+                            valid_fields: faceted_fields.into_iter().collect(),
+                        })?;
+                    }
+                }
+                ranking_rules.clone()
+            }
+            None => self.index.criteria(self.rtxn)?,
+        };
+
         // We check that the sort ranking rule exists and throw an
         // error if we try to use it and that it doesn't.
-        let sort_ranking_rule_missing = !self.index.criteria(self.rtxn)?.contains(&Criterion::Sort);
+        let sort_ranking_rule_missing = !ranking_rules.contains(&Criterion::Sort);
         let empty_sort_criteria = self.sort_criteria.as_ref().map_or(true, |s| s.is_empty());
         if sort_ranking_rule_missing && !empty_sort_criteria {
             return Err(UserError::SortRankingRuleMissing.into());
         }
 
-        let criteria_builder = criteria::CriteriaBuilder::new(self.rtxn, self.index)?;
+        let mut criteria_builder = criteria::CriteriaBuilder::new(self.rtxn, self.index)?;
+        if let Some(attributes) = &self.attributes_to_search_on {
+            criteria_builder.fields_to_search_on(self.fields_to_search_on(attributes)?);
+        }
+
+        // The documents containing the negated words of the query, or not containing
+        // its field-scoped words in these fields, are removed from the candidates.
+        if !restrictions.is_empty() {
+            let candidates = match filtered_candidates {
+                Some(candidates) => candidates,
+                None => self.index.documents_ids(self.rtxn)?,
+            };
+            filtered_candidates =
+                Some(criteria_builder.restrict_candidates(candidates, &restrictions)?);
+        }
 
         match self.index.distinct_field(self.rtxn)? {
             None => {
@@ -201,6 +430,7 @@ impl<'a> Search<'a> {
                     primitive_query,
                     filtered_candidates,
                     self.sort_criteria.clone(),
+                    ranking_rules,
                     self.exhaustive_number_hits,
                     None,
                 )?;
@@ -217,6 +447,7 @@ impl<'a> Search<'a> {
                             primitive_query,
                             filtered_candidates,
                             self.sort_criteria.clone(),
+                            ranking_rules,
                             self.exhaustive_number_hits,
                             Some(distinct.clone()),
                         )?;
@@ -228,6 +459,88 @@ impl<'a> Search<'a> {
         }
     }
 
+    /// Returns the ids of the fields the query words must be found in, we
+    /// check that the attributes are declared in the searchable fields.
+    fn fields_to_search_on(&self, attributes: &[String]) -> Result<Vec<FieldId>> {
+        let fields_ids_map = self.index.fields_ids_map(self.rtxn)?;
+        let searchable_fields = self.index.user_defined_searchable_fields(self.rtxn)?;
+
+        let mut fields_ids = Vec::new();
+        for attribute in attributes {
+            if let Some(searchable_fields) = &searchable_fields {
+                if !crate::is_faceted(attribute, searchable_fields) {
+                    return Err(UserError::InvalidSearchableAttribute {
+                        field: attribute.to_string(),
+                        valid_fields: searchable_fields.iter().map(|s| s.to_string()).collect(),
+                    })?;
+                }
+            }
+
+            fields_ids.extend(
+                fields_ids_map
+                    .iter()
+                    .filter(|(_, name)| crate::is_faceted_by(name, attribute))
+                    .map(|(field_id, _)| field_id),
+            );
+        }
+
+        Ok(fields_ids)
+    }
+
+    /// Returns the filtered documents ordered by the similarity of their vectors to the
+    /// given one, the documents without vectors are ignored and so are the ranking rules.
+    ///
+    /// The documents whose similarity doesn't reach the ranking score threshold are dropped.
+    fn vector_search(&self, vector: &[f32]) -> Result<SearchResult> {
+        let mut candidates = self.index.documents_ids(self.rtxn)?;
+        if let Some(filter) = &self.filter {
+            candidates &= filter.evaluate(self.rtxn, self.index)?;
+        }
+        candidates -= self.index.soft_deleted_documents_ids(self.rtxn)?;
+
+        let mut nearest = vector::nearest_documents(
+            self.rtxn,
+            self.index,
+            vector,
+            self.vector_similarity,
+            &candidates,
+        )?;
+
+        // the documents are ordered by similarity, the first one below the threshold
+        // is followed by the other ones.
+        if let Some(threshold) = self.ranking_score_threshold {
+            let below_threshold = nearest.iter().position(|&(_, similarity)| {
+                let details = ScoreDetails::Vector(score_details::Vector { similarity });
+                ScoreDetails::global_score(Some(&details).into_iter()) < threshold
+            });
+            if let Some(position) = below_threshold {
+                nearest.truncate(position);
+            }
+        }
+
+        let candidates = nearest.iter().map(|(docid, _)| *docid).collect();
+        let (documents_ids, document_scores): (Vec<_>, Vec<_>) = nearest
+            .into_iter()
+            .skip(self.offset)
+            .take(self.limit)
+            .map(|(docid, similarity)| {
+                let details = score_details::Vector { similarity };
+                (docid, vec![ScoreDetails::Vector(details)])
+            })
+            .unzip();
+        let geo_distances = self.geo_distances(&documents_ids, &document_scores)?;
+
+        Ok(SearchResult {
+            matching_words: MatchingWords::default(),
+            candidates,
+            candidates_estimated: false,
+            documents_ids,
+            document_scores,
+            geo_distances,
+            facet_distribution: None,
+        })
+    }
+
     fn perform_sort<D: Distinct>(
         &self,
         mut distinct: D,
@@ -238,12 +551,26 @@ impl<'a> Search<'a> {
         let mut initial_candidates = RoaringBitmap::new();
         let mut excluded_candidates = self.index.soft_deleted_documents_ids(self.rtxn)?;
         let mut documents_ids = Vec::new();
+        let mut document_scores = Vec::new();
+        // the candidates of the buckets that reached the ranking score threshold.
+        let mut relevant_candidates = RoaringBitmap::new();
 
-        while let Some(FinalResult { candidates, bucket_candidates, .. }) =
+        while let Some(FinalResult { candidates, bucket_candidates, score_details, .. }) =
             criteria.next(&excluded_candidates)?
         {
             debug!("Number of candidates found {}", candidates.len());
 
+            if let Some(threshold) = self.ranking_score_threshold {
+                if ScoreDetails::global_score(score_details.iter()) < threshold {
+                    if ScoreDetails::following_buckets_are_worse(&score_details) {
+                        break;
+                    } else {
+                        continue;
+                    }
+                }
+                relevant_candidates |= &candidates;
+            }
+
             let excluded = take(&mut excluded_candidates);
             let mut candidates = distinct.distinct(candidates, excluded);
 
@@ -256,6 +583,7 @@ impl<'a> Search<'a> {
 
             for candidate in candidates.by_ref().take(self.limit - documents_ids.len()) {
                 documents_ids.push(candidate?);
+                document_scores.push(score_details.clone());
             }
 
             excluded_candidates |= candidates.into_excluded();
@@ -265,12 +593,73 @@ impl<'a> Search<'a> {
             }
         }
 
+        // With a threshold only the documents of the buckets that reached it are candidates,
+        // when we stopped because of the limit the following buckets were not ranked and the
+        // candidates are only the relevant documents found so far.
+        let (candidates, candidates_estimated) = match self.ranking_score_threshold {
+            Some(_) => (relevant_candidates, documents_ids.len() == self.limit),
+            None => (initial_candidates, false),
+        };
+
+        let geo_distances = self.geo_distances(&documents_ids, &document_scores)?;
+
         Ok(SearchResult {
             matching_words,
-            candidates: initial_candidates - excluded_candidates,
+            candidates: candidates - excluded_candidates,
+            candidates_estimated,
             documents_ids,
+            document_scores,
+            geo_distances,
+            facet_distribution: None,
         })
     }
+
+    /// Returns the distance in meters between each document and the target point of the
+    /// `_geoPoint` sort or, when there is no such sort, the center of the `_geoRadius` filter.
+    fn geo_distances(
+        &self,
+        documents_ids: &[DocumentId],
+        document_scores: &[Vec<ScoreDetails>],
+    ) -> Result<Vec<Option<f64>>> {
+        let geo_sort = |details: &[ScoreDetails]| {
+            details.iter().find_map(|detail| match detail {
+                ScoreDetails::GeoSort(geo_sort) => Some(*geo_sort),
+                _ => None,
+            })
+        };
+
+        if document_scores.iter().any(|details| geo_sort(details).is_some()) {
+            return Ok(document_scores
+                .iter()
+                .map(|details| geo_sort(details).and_then(|geo_sort| geo_sort.distance()))
+                .collect());
+        }
+
+        let (center, radius) = match self.filter.as_ref().and_then(Filter::geo_radius) {
+            Some(geo_radius) => geo_radius,
+            None => return Ok(vec![None; documents_ids.len()]),
+        };
+        let rtree = match self.index.geo_rtree(self.rtxn)? {
+            Some(rtree) => rtree,
+            None => return Ok(vec![None; documents_ids.len()]),
+        };
+
+        // the documents are all in the radius, we stop once we found all of them.
+        let documents: RoaringBitmap = documents_ids.iter().copied().collect();
+        let mut distances = HashMap::new();
+        for point in rtree.nearest_neighbor_iter(&lat_lng_to_xyz(&center)) {
+            let distance = distance_between_two_points(&center, &point.data.1);
+            if distances.len() as u64 == documents.len() || distance >= radius {
+                break;
+            }
+            // a document can have many points, the first one is the nearest.
+            if documents.contains(point.data.0) {
+                distances.entry(point.data.0).or_insert(distance);
+            }
+        }
+
+        Ok(documents_ids.iter().map(|docid| distances.get(docid).copied()).collect())
+    }
 }
 
 impl fmt::Debug for Search<'_> {
@@ -281,10 +670,18 @@ impl fmt::Debug for Search<'_> {
             offset,
             limit,
             sort_criteria,
+            ranking_rules,
+            attributes_to_search_on,
             terms_matching_strategy,
             authorize_typos,
             words_limit,
             exhaustive_number_hits,
+            ranking_score_threshold,
+            vector,
+            vector_similarity,
+            semantic_ratio,
+            facets,
+            disjunctive_facets,
             rtxn: _,
             index: _,
         } = self;
@@ -294,9 +691,17 @@ impl fmt::Debug for Search<'_> {
             .field("offset", offset)
             .field("limit", limit)
             .field("sort_criteria", sort_criteria)
+            .field("ranking_rules", ranking_rules)
+            .field("attributes_to_search_on", attributes_to_search_on)
             .field("terms_matching_strategy", terms_matching_strategy)
             .field("authorize_typos", authorize_typos)
             .field("exhaustive_number_hits", exhaustive_number_hits)
+            .field("ranking_score_threshold", ranking_score_threshold)
+            .field("vector", vector)
+            .field("vector_similarity", vector_similarity)
+            .field("semantic_ratio", semantic_ratio)
+            .field("facets", facets)
+            .field("disjunctive_facets", disjunctive_facets)
             .field("words_limit", words_limit)
             .finish()
     }
@@ -306,8 +711,20 @@ impl fmt::Debug for Search<'_> {
 pub struct SearchResult {
     pub matching_words: MatchingWords,
     pub candidates: RoaringBitmap,
-    // TODO those documents ids should be associated with their criteria scores.
+    /// `true` when `candidates` is a lower bound of the documents matching the search, it is
+    /// the case when the ranking score threshold is set and the search stopped at the limit
+    /// before ranking all the documents.
+    pub candidates_estimated: bool,
     pub documents_ids: Vec<DocumentId>,
+    /// The score details of each document of `documents_ids`, one by ranking rule,
+    /// use [`ScoreDetails::global_score`] to compute the normalized score of a document.
+    pub document_scores: Vec<Vec<ScoreDetails>>,
+    /// The distance in meters between each document of `documents_ids` and the point
+    /// of the `_geoPoint` sort, or the center of the `_geoRadius` filter when there is
+    /// no such sort, `None` when there is neither or the document has no geo point.
+    pub geo_distances: Vec<Option<f64>>,
+    /// The distribution of the values of the facets requested with [`Search::facets`].
+    pub facet_distribution: Option<BTreeMap<String, IndexMap<String, u64>>>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -418,8 +835,13 @@ pub fn build_dfa(word: &str, typos: u8, is_prefix: bool) -> DFA {
 
 #[cfg(test)]
 mod test {
+    use big_s::S;
+    use charabia::TokenizerBuilder;
+    use maplit::hashset;
+
     use super::*;
     use crate::index::tests::TempIndex;
+    use crate::SynonymGroup;
 
     #[test]
     fn test_is_authorized_typos() {
@@ -508,4 +930,441 @@ mod test {
 
         assert_eq!(found, &[("zealand".to_string(), 1)]);
     }
+
+    #[test]
+    fn test_geo_distances() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_primary_key(S("id"));
+                settings.set_filterable_fields(hashset!(S("_geo")));
+                settings.set_sortable_fields(hashset!(S("_geo")));
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "city": "Lille",     "_geo": { "lat": 50.6299, "lng": 3.0569 } },
+                { "id": 1, "city": "Roubaix",   "_geo": { "lat": 50.6924, "lng": 3.1763 } },
+                { "id": 2, "city": "Tourcoing", "_geo": { "lat": 50.7263, "lng": 3.1541 } },
+                { "id": 3, "city": "Paris",     "_geo": { "lat": 48.9021, "lng": 2.3708 } },
+                { "id": 4, "city": "Nowhere" }
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let lille = [50.6299, 3.0569];
+        let cities = [lille, [50.6924, 3.1763], [50.7263, 3.1541], [48.9021, 2.3708]];
+
+        // the distances are those of the `_geoPoint` sort, even with a `_geoRadius` filter.
+        let mut search = Search::new(&rtxn, &index);
+        search.sort_criteria(vec![AscDesc::Asc(Member::Geo(lille))]);
+        search.filter(Filter::from_str("_geoRadius(48.9021, 2.3708, 1000000)").unwrap().unwrap());
+        let SearchResult { documents_ids, geo_distances, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![0, 1, 2, 3]);
+        let expected: Vec<_> =
+            cities.iter().map(|city| Some(distance_between_two_points(&lille, city))).collect();
+        assert_eq!(geo_distances, expected);
+
+        // the documents without a geo point have no distance.
+        let mut search = Search::new(&rtxn, &index);
+        search.sort_criteria(vec![AscDesc::Desc(Member::Geo(lille))]);
+        let SearchResult { documents_ids, geo_distances, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![3, 2, 1, 0, 4]);
+        assert_eq!(geo_distances[4], None);
+
+        // without sort the distances are those to the center of the `_geoRadius` filter.
+        let mut search = Search::new(&rtxn, &index);
+        search.filter(Filter::from_str("_geoRadius(50.6299, 3.0569, 15000)").unwrap().unwrap());
+        let SearchResult { documents_ids, geo_distances, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![0, 1, 2]);
+        assert_eq!(geo_distances, &expected[..3]);
+
+        // without sort nor geo filter there is no distance.
+        let search = Search::new(&rtxn, &index);
+        let SearchResult { geo_distances, .. } = search.execute().unwrap();
+        assert_eq!(geo_distances, vec![None; 5]);
+    }
+
+    #[test]
+    fn test_facet_distribution() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset!(S("color"), S("size")));
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "name": "shoes", "color": "red",  "size": 42 },
+                { "id": 1, "name": "shoes", "color": "blue", "size": 42 },
+                { "id": 2, "name": "shoes", "color": "red",  "size": 43 },
+                { "id": 3, "name": "socks", "color": "red",  "size": 42 },
+                { "id": 4, "name": "shoes", "color": "green" }
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let counts = |distribution: &BTreeMap<String, IndexMap<String, u64>>, facet: &str| {
+            distribution[facet].iter().map(|(k, v)| (k.clone(), *v)).collect::<Vec<_>>()
+        };
+
+        // the values are counted on the candidates of the search.
+        let mut search = Search::new(&rtxn, &index);
+        search.query("shoes").facets(["color", "size"]);
+        search.filter(Filter::from_str("color = red AND size = 42").unwrap().unwrap());
+        let SearchResult { documents_ids, facet_distribution, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![0]);
+        let distribution = facet_distribution.unwrap();
+        assert_eq!(counts(&distribution, "color"), vec![(S("red"), 1)]);
+        assert_eq!(counts(&distribution, "size"), vec![(S("42"), 1)]);
+
+        // the values of a disjunctive facet ignore the filter on this facet.
+        search.disjunctive_facets(true);
+        let SearchResult { documents_ids, facet_distribution, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![0]);
+        let distribution = facet_distribution.unwrap();
+        assert_eq!(counts(&distribution, "color"), vec![(S("blue"), 1), (S("red"), 1)]);
+        assert_eq!(counts(&distribution, "size"), vec![(S("42"), 1), (S("43"), 1)]);
+
+        // the disjunctive values are counted on the documents of every bucket of the query.
+        let mut search = Search::new(&rtxn, &index);
+        search.query("shoes red").facets(["color", "size"]).disjunctive_facets(true);
+        search.filter(Filter::from_str("size = 42").unwrap().unwrap());
+        let SearchResult { documents_ids, facet_distribution, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![0, 1]);
+        let distribution = facet_distribution.unwrap();
+        assert_eq!(counts(&distribution, "color"), vec![(S("blue"), 1), (S("red"), 1)]);
+        assert_eq!(counts(&distribution, "size"), vec![(S("42"), 2), (S("43"), 1)]);
+
+        // the facets are all counted on the documents matching the query, whatever the
+        // documents that reach the ranking score threshold.
+        search.ranking_score_threshold(1.1);
+        let SearchResult { documents_ids, facet_distribution, .. } = search.execute().unwrap();
+        assert!(documents_ids.is_empty());
+        let distribution = facet_distribution.unwrap();
+        assert_eq!(counts(&distribution, "color"), vec![(S("blue"), 1), (S("red"), 1)]);
+        assert_eq!(counts(&distribution, "size"), vec![(S("42"), 2), (S("43"), 1)]);
+
+        // without facets there is no distribution.
+        let search = Search::new(&rtxn, &index);
+        let SearchResult { facet_distribution, .. } = search.execute().unwrap();
+        assert_eq!(facet_distribution, None);
+    }
+
+    #[test]
+    fn test_ranking_rules() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_sortable_fields(hashset!(S("price")));
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "name": "red shoes", "price": 80 },
+                { "id": 1, "name": "shoes", "price": 50 },
+                { "id": 2, "name": "red socks", "price": 10 },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        // the ranking rules of the index are used by default.
+        let mut search = Search::new(&rtxn, &index);
+        search.query("red shoes");
+        let SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![0, 2]);
+
+        // the ranking rules of the search replace the ones of the index.
+        search.ranking_rules(vec![Criterion::Asc(S("price")), Criterion::Words]);
+        let SearchResult { documents_ids, document_scores, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![2, 0]);
+        assert!(matches!(document_scores[0][0], ScoreDetails::Sort(_)));
+
+        // the sort parameter requires the sort ranking rule.
+        search.sort_criteria(vec![AscDesc::Desc(Member::Field(S("price")))]);
+        let error = search.execute().unwrap_err();
+        assert!(matches!(error, crate::Error::UserError(UserError::SortRankingRuleMissing)));
+
+        search.ranking_rules(vec![Criterion::Sort]);
+        let SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![0, 2]);
+
+        // the fields of the ranking rules must be faceted.
+        search.ranking_rules(vec![Criterion::Desc(S("name"))]);
+        let error = search.execute().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Attribute `name` can't be used by a ranking rule at search time as it is not faceted. Available faceted attributes are: `price`."
+        );
+    }
+
+    #[test]
+    fn test_attributes_to_search_on() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_searchable_fields(vec![S("title"), S("author"), S("description")]);
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "title": "rust book", "author": { "name": "steve" }, "description": "learn rust" },
+                { "id": 1, "title": "the go book", "author": { "name": "rob" }, "description": "learn go, not rust" },
+                { "id": 2, "title": "rusty knives", "author": { "name": "rust" }, "description": "sharpening" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+
+        let mut search = Search::new(&rtxn, &index);
+        search.query("rust").authorize_typos(false);
+        let SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids.len(), 3);
+
+        // only the words of the given attributes are matched.
+        search.attributes_to_search_on(vec![S("title")]);
+        let SearchResult { documents_ids, candidates, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![0, 2]);
+        assert_eq!(candidates.len(), 2);
+
+        search.query("rust learn");
+        let SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![0, 2]);
+
+        // the nested fields of an attribute are searched too.
+        search.query("rust").attributes_to_search_on(vec![S("author")]);
+        let SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![2]);
+
+        search.attributes_to_search_on(vec![S("id")]);
+        let error = search.execute().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Attribute `id` is not searchable. Available searchable attributes are: `author, description, title`."
+        );
+    }
+
+    #[test]
+    fn test_attributes_to_search_on_words_pairs() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_searchable_fields(vec![S("title"), S("description"), S("tags")]);
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "title": "red", "description": "shoes", "tags": "red shoes" },
+                { "id": 1, "title": "red shoes", "description": "for running", "tags": "sport" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let mut search = Search::new(&rtxn, &index);
+        search.query("red shoes").ranking_rules(vec![Criterion::Words, Criterion::Proximity]);
+
+        // both documents contain the words next to each other.
+        let SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![0, 1]);
+
+        // the words of the document 0 are only next to each other in a field that isn't searched.
+        search.attributes_to_search_on(vec![S("title"), S("description")]);
+        let SearchResult { documents_ids, document_scores, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![1, 0]);
+        assert_ne!(document_scores[0], document_scores[1]);
+    }
+
+    #[test]
+    fn test_negative_query() {
+        let index = TempIndex::new();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "name": "refurbished laptop" },
+                { "id": 1, "name": "laptop with a hard drive" },
+                { "id": 2, "name": "new laptop" },
+                { "id": 3, "name": "laptop drive hard" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let mut search = Search::new(&rtxn, &index);
+
+        search.query("laptop -refurbished");
+        let SearchResult { documents_ids, matching_words, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![1, 3, 2]);
+        // the negated words are not highlighted.
+        let builder = MatcherBuilder::new(matching_words, TokenizerBuilder::default().build());
+        let format_options = FormatOptions { highlight: true, crop: None };
+        let mut matcher = builder.build("refurbished laptop");
+        assert_eq!(&matcher.format(format_options), "refurbished <em>laptop</em>");
+
+        // the words of a negated phrase must be consecutive to exclude a document.
+        search.query("laptop -\"hard drive\"");
+        let SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![3, 0, 2]);
+
+        // a query made of negated words only returns all the other documents.
+        search.query("-refurbished -new");
+        let SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![1, 3]);
+    }
+
+    #[test]
+    fn test_scoped_query() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_searchable_fields(vec![S("title"), S("author"), S("description")])
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "title": "rust in action", "author": "tim mcnamara", "description": "systems programming" },
+                { "id": 1, "title": "programming rust", "author": { "name": "jim blandy" }, "description": "fast and safe" },
+                { "id": 2, "title": "the book", "author": "steve klabnik", "description": "rust programming language" },
+                { "id": 3, "title": "zero to production", "author": "luca palmieri", "description": "backend in rust" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let mut search = Search::new(&rtxn, &index);
+
+        search.query("title:rust");
+        let SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![0, 1]);
+
+        // combined with free-text words and phrases.
+        search.query("description:\"rust programming\" book");
+        let SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![2]);
+
+        search.query("programming -title:rust");
+        let SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![2]);
+
+        // the nested fields are searched too.
+        search.query("author:blandy");
+        let SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![1]);
+
+        // the scoped words have the same typos and prefixes as the other words.
+        search.query("title:programing");
+        let SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![1]);
+
+        search.query("title:prod");
+        let SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![3]);
+
+        // a word that is not a searchable field is a plain word.
+        search.query("steve:klabnik");
+        let SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![2]);
+    }
+
+    #[test]
+    fn test_or_operators() {
+        let index = TempIndex::new();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "name": "phone case" },
+                { "id": 1, "name": "mobile case" },
+                { "id": 2, "name": "laptop case" },
+                { "id": 3, "name": "phone charger" },
+                { "id": 4, "name": "red phone case" },
+                { "id": 5, "name": "red mobile charger" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let mut search = Search::new(&rtxn, &index);
+
+        search.query("(phone OR mobile) case");
+        let SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![0, 1, 4]);
+
+        // the words between the operators are all required, whatever the matching strategy.
+        search.query("(phone OR mobile) red case");
+        let SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![4]);
+
+        search.query("phone OR laptop");
+        let SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![0, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_synonym_groups() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_synonym_groups(vec![
+                    SynonymGroup::Bidirectional { synonyms: vec![S("NYC"), S("New York")] },
+                    SynonymGroup::OneWay { input: S("big apple"), synonyms: vec![S("new york")] },
+                ]);
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "name": "I moved to New York last year" },
+                { "id": 1, "name": "NYC is expensive" },
+                { "id": 2, "name": "The big apple never sleeps" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let synonyms = index.synonyms(&rtxn).unwrap();
+        assert_eq!(synonyms.len(), 3);
+        assert_eq!(synonyms[&vec![S("nyc")]], vec![vec![S("new"), S("york")]]);
+        assert_eq!(synonyms[&vec![S("new"), S("york")]], vec![vec![S("nyc")]]);
+        assert_eq!(synonyms[&vec![S("big"), S("apple")]], vec![vec![S("new"), S("york")]]);
+
+        let mut search = Search::new(&rtxn, &index);
+
+        // a bidirectional group expands each of its phrases into the others.
+        search.query("nyc");
+        let SearchResult { mut documents_ids, matching_words, .. } = search.execute().unwrap();
+        documents_ids.sort_unstable();
+        assert_eq!(documents_ids, vec![0, 1]);
+        // the matcher knows which query words have been matched through a synonym.
+        let builder = MatcherBuilder::new(matching_words, TokenizerBuilder::default().build());
+        let mut matcher = builder.build("I moved to New York last year");
+        assert_eq!(
+            matcher.matches(),
+            vec![
+                MatchBounds { start: 11, length: 3, synonym_of: Some(S("nyc")) },
+                MatchBounds { start: 15, length: 4, synonym_of: Some(S("nyc")) },
+            ]
+        );
+        let mut matcher = builder.build("NYC is expensive");
+        assert_eq!(matcher.matches(), vec![MatchBounds { start: 0, length: 3, synonym_of: None }]);
+
+        search.query("new york");
+        let SearchResult { mut documents_ids, .. } = search.execute().unwrap();
+        documents_ids.sort_unstable();
+        assert_eq!(documents_ids, vec![0, 1]);
+
+        // a one-way group only expands its input.
+        search.query("big apple");
+        let SearchResult { mut documents_ids, .. } = search.execute().unwrap();
+        documents_ids.sort_unstable();
+        assert_eq!(documents_ids, vec![0, 2]);
+    }
 }
@@ -0,0 +1,221 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+use roaring::RoaringBitmap;
+
+use super::{MatchingWords, ScoreDetails, Search, SearchResult};
+use crate::{DocumentId, Result};
+
+/// Executes many searches at once, each one can target a different index.
+///
+/// Every index is a distinct environment, there is no read transaction spanning many of them:
+/// each `Search` keeps the read transaction it was created with. The searches on the same
+/// index can share the same transaction to see the same version of the index.
+///
+/// The results are either returned for each search or federated
+/// into a single list ranked by the global score of the documents.
+pub struct MultiSearch<'a> {
+    searches: Vec<Search<'a>>,
+    offset: usize,
+    limit: usize,
+}
+
+/// A document returned by a federated multi-search.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FederatedHit {
+    /// The position of the search that returned this document in the multi-search,
+    /// the document id is an internal id of the index of this search.
+    pub query_index: usize,
+    pub document_id: DocumentId,
+    /// The global score of the document, see [`ScoreDetails::global_score`], `None` when
+    /// none of the ranking rules that ranked the document can be expressed as a bounded rank,
+    /// e.g. for a placeholder search or a search only ranked by a sort.
+    pub ranking_score: Option<f64>,
+    pub score_details: Vec<ScoreDetails>,
+}
+
+#[derive(Default)]
+pub struct FederatedSearchResult {
+    pub hits: Vec<FederatedHit>,
+    /// The candidates of each search, in the order of the searches.
+    pub candidates: Vec<RoaringBitmap>,
+    /// The matching words of each search, in the order of the searches.
+    pub matching_words: Vec<MatchingWords>,
+}
+
+impl<'a> MultiSearch<'a> {
+    pub fn new() -> MultiSearch<'a> {
+        MultiSearch { searches: Vec::new(), offset: 0, limit: 20 }
+    }
+
+    pub fn search(&mut self, search: Search<'a>) -> &mut MultiSearch<'a> {
+        self.searches.push(search);
+        self
+    }
+
+    /// The number of federated hits to skip, the offset of each search is ignored.
+    pub fn offset(&mut self, offset: usize) -> &mut MultiSearch<'a> {
+        self.offset = offset;
+        self
+    }
+
+    /// The number of federated hits to return, the limit of each search is ignored.
+    pub fn limit(&mut self, limit: usize) -> &mut MultiSearch<'a> {
+        self.limit = limit;
+        self
+    }
+
+    /// Returns the results of each search, in the order of the searches.
+    pub fn execute(&self) -> Result<Vec<SearchResult>> {
+        self.searches.iter().map(Search::execute).collect()
+    }
+
+    /// Merges the results of the searches into a single list of documents ranked by their
+    /// global score, the documents with the same score are ranked in the order of the searches.
+    ///
+    /// The documents without a ranking score can't be compared to the other ones, they are
+    /// ranked after all the documents with a score, in the order of the searches.
+    pub fn execute_federated(&self) -> Result<FederatedSearchResult> {
+        // every search must return enough documents to fill the requested page.
+        let window = self.offset + self.limit;
+
+        let mut hits = Vec::new();
+        let mut candidates = Vec::with_capacity(self.searches.len());
+        let mut matching_words = Vec::with_capacity(self.searches.len());
+        for (query_index, search) in self.searches.iter().enumerate() {
+            let mut search = search.clone();
+            search.offset(0).limit(window);
+            let result = search.execute()?;
+
+            let documents = result.documents_ids.into_iter().zip(result.document_scores);
+            hits.extend(documents.map(|(document_id, score_details)| FederatedHit {
+                query_index,
+                document_id,
+                ranking_score: match score_details.iter().any(|d| d.rank().is_some()) {
+                    true => Some(ScoreDetails::global_score(score_details.iter())),
+                    false => None,
+                },
+                score_details,
+            }));
+            candidates.push(result.candidates);
+            matching_words.push(result.matching_words);
+        }
+
+        // the sort is stable, the documents of a search keep their relative order.
+        hits.sort_by(|a, b| {
+            let ordering = match (a.ranking_score, b.ranking_score) {
+                (Some(a), Some(b)) => b.partial_cmp(&a).unwrap_or(Ordering::Equal),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            };
+            ordering.then_with(|| a.query_index.cmp(&b.query_index))
+        });
+        let hits = hits.into_iter().skip(self.offset).take(self.limit).collect();
+
+        Ok(FederatedSearchResult { hits, candidates, matching_words })
+    }
+}
+
+impl Default for MultiSearch<'_> {
+    fn default() -> Self {
+        MultiSearch::new()
+    }
+}
+
+impl fmt::Debug for MultiSearch<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let MultiSearch { searches, offset, limit } = self;
+        f.debug_struct("MultiSearch")
+            .field("searches", searches)
+            .field("offset", offset)
+            .field("limit", limit)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::tests::TempIndex;
+
+    #[test]
+    fn multi_search() {
+        let products = TempIndex::new();
+        products
+            .add_documents(documents!([
+                { "id": 0, "name": "red shoes" },
+                { "id": 1, "name": "blue socks" },
+                { "id": 2, "name": "red shirt" },
+            ]))
+            .unwrap();
+
+        let articles = TempIndex::new();
+        articles
+            .add_documents(documents!([
+                { "id": 0, "title": "how to clean your red shoes" },
+                { "id": 1, "title": "red shoes and blue socks" },
+            ]))
+            .unwrap();
+
+        let products_rtxn = products.read_txn().unwrap();
+        let articles_rtxn = articles.read_txn().unwrap();
+
+        let mut products_search = Search::new(&products_rtxn, &products);
+        products_search.query("red shoes").limit(1);
+        let mut articles_search = Search::new(&articles_rtxn, &articles);
+        articles_search.query("red shoes");
+
+        let mut multi_search = MultiSearch::new();
+        multi_search.search(products_search).search(articles_search);
+
+        // the results of each search respect their own parameters.
+        let results = multi_search.execute().unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].documents_ids, vec![0]);
+        assert_eq!(results[1].documents_ids, vec![1, 0]);
+
+        // the federated hits are ranked by their global score.
+        let result = multi_search.execute_federated().unwrap();
+        let hits: Vec<_> =
+            result.hits.iter().map(|hit| (hit.query_index, hit.document_id)).collect();
+        assert_eq!(hits, vec![(0, 0), (1, 1), (1, 0), (0, 2)]);
+        assert!(result.hits.windows(2).all(|w| w[0].ranking_score >= w[1].ranking_score));
+        assert!(result.hits.iter().all(|hit| hit.ranking_score.is_some()));
+        assert_eq!(result.candidates.len(), 2);
+
+        multi_search.offset(1).limit(2);
+        let result = multi_search.execute_federated().unwrap();
+        let hits: Vec<_> =
+            result.hits.iter().map(|hit| (hit.query_index, hit.document_id)).collect();
+        assert_eq!(hits, vec![(1, 1), (1, 0)]);
+    }
+
+    #[test]
+    fn federated_placeholder_search() {
+        let index = TempIndex::new();
+        index
+            .add_documents(documents!([
+                { "id": 0, "name": "blue socks" },
+                { "id": 1, "name": "red shoes" },
+            ]))
+            .unwrap();
+        let rtxn = index.read_txn().unwrap();
+
+        let placeholder_search = Search::new(&rtxn, &index);
+        let mut keyword_search = Search::new(&rtxn, &index);
+        keyword_search.query("shoes");
+
+        let mut multi_search = MultiSearch::new();
+        multi_search.search(placeholder_search).search(keyword_search);
+
+        // the documents of the placeholder search are not perfect matches.
+        let result = multi_search.execute_federated().unwrap();
+        let hits: Vec<_> = result
+            .hits
+            .iter()
+            .map(|hit| (hit.query_index, hit.document_id, hit.ranking_score.is_some()))
+            .collect();
+        assert_eq!(hits, vec![(1, 1, true), (0, 0, false), (0, 1, false)]);
+    }
+}
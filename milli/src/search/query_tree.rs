@@ -1,16 +1,18 @@
 use std::borrow::Cow;
 use std::cmp::max;
+use std::iter::Peekable;
+use std::ops::Range;
 use std::{fmt, mem};
 
 use charabia::classifier::ClassifiedTokenIter;
-use charabia::{SeparatorKind, TokenKind};
+use charabia::{SeparatorKind, TokenKind, TokenizerBuilder};
 use fst::Set;
 use roaring::RoaringBitmap;
 use slice_group_by::GroupBy;
 
 use crate::search::matches::matching_words::{MatchingWord, PrimitiveWordId};
 use crate::search::TermsMatchingStrategy;
-use crate::{CboRoaringBitmapLenCodec, Index, MatchingWords, Result};
+use crate::{CboRoaringBitmapLenCodec, FieldId, Index, MatchingWords, Result};
 
 type IsOptionalWord = bool;
 type IsPrefix = bool;
@@ -260,26 +262,148 @@ impl<'a> QueryTreeBuilder<'a> {
     /// - if `authorize_typos` is set to `false` the query tree will be generated
     ///   forcing all query words to match documents without any typo
     ///   (the criterion `typo` will be ignored)
+    ///
+    /// The words and phrases prefixed by a `-` are not part of the query tree nor of the
+    /// matching words, they are returned apart, with the words and phrases scoped to a field,
+    /// so that the candidates can be restricted accordingly.
+    ///
+    /// The parenthesized groups of the query produce the corresponding `And` operations, the
+    /// `OR` operators are only recognized by [`Self::build_from_text`] as the tokens are
+    /// lowercased and the original text is needed to find them.
+    #[allow(dead_code)]
     pub fn build<A: AsRef<[u8]>>(
         &self,
         query: ClassifiedTokenIter<A>,
-    ) -> Result<Option<(Operation, PrimitiveQuery, MatchingWords)>> {
+    ) -> Result<(Option<(Operation, PrimitiveQuery, MatchingWords)>, QueryRestrictions)> {
+        self.build_query(None, query)
+    }
+
+    /// Tokenizes the text, taking the stop words of the index into account, and builds its
+    /// query tree like [`Self::build`] does, the uppercased `OR` words of the text are
+    /// explicit operators producing the corresponding `Or` operations.
+    pub fn build_from_text(
+        &self,
+        text: &str,
+    ) -> Result<(Option<(Operation, PrimitiveQuery, MatchingWords)>, QueryRestrictions)> {
+        // We make sure that the analyzer is aware of the stop words
+        // this ensures that the query builder is able to properly remove them.
+        let mut tokbuilder = TokenizerBuilder::new();
+        let stop_words = self.index.stop_words(self.rtxn)?;
+        if let Some(ref stop_words) = stop_words {
+            tokbuilder.stop_words(stop_words);
+        }
+
+        let tokenizer = tokbuilder.build();
+        let tokens = tokenizer.tokenize(text);
+        self.build_query(Some(text), tokens)
+    }
+
+    fn build_query<A: AsRef<[u8]>>(
+        &self,
+        text: Option<&str>,
+        query: ClassifiedTokenIter<A>,
+    ) -> Result<(Option<(Operation, PrimitiveQuery, MatchingWords)>, QueryRestrictions)> {
         let stop_words = self.index.stop_words(self.rtxn)?;
-        let primitive_query = create_primitive_query(query, stop_words, self.words_limit);
+        let searchable_fields = self.searchable_fields_names()?;
+        let ParsedQuery { primitive: primitive_query, operators, negative, scoped } =
+            create_primitive_query(text, query, stop_words, self.words_limit, |field| {
+                !scoped_fields_ids(&searchable_fields, field).is_empty()
+            });
+
+        let (word_len_one_typo, word_len_two_typo) = self.min_word_len_for_typo()?;
+        let exact_words = self.exact_words();
+        let config = TypoConfig { max_typos: 2, word_len_one_typo, word_len_two_typo, exact_words };
+
+        let mut restrictions = QueryRestrictions { negative, scoped: Vec::new() };
+        for ScopedQueryPart { field, words, prefix, negative } in scoped {
+            // a scoped word is derived like the words of the query tree, unless it is negated
+            // like the words of the negative query, the words of a phrase are always exact.
+            let queries = match words.as_slice() {
+                [word] if !negative => {
+                    let kind = typos(word.clone(), self.authorize_typos, config.clone());
+                    vec![Query { prefix, kind }]
+                }
+                words => words
+                    .iter()
+                    .map(|word| Query { prefix: false, kind: QueryKind::exact(word.clone()) })
+                    .collect(),
+            };
+            let fields_ids = scoped_fields_ids(&searchable_fields, &field);
+            restrictions.scoped.push(ScopedRestriction { fields_ids, queries, negative });
+        }
+
         if !primitive_query.is_empty() {
-            let qt = create_query_tree(
+            let expression = QueryExpression::parse(primitive_query.len(), &operators);
+            let qt = create_expression_tree(
                 self,
                 self.terms_matching_strategy,
                 self.authorize_typos,
                 &primitive_query,
+                &expression,
+            )?;
+            let matching_words = create_matching_words(
+                self,
+                self.authorize_typos,
+                &primitive_query,
+                &expression.leaves(),
             )?;
-            let matching_words =
-                create_matching_words(self, self.authorize_typos, &primitive_query)?;
-            Ok(Some((qt, primitive_query, matching_words)))
+            Ok((Some((qt, primitive_query, matching_words)), restrictions))
         } else {
-            Ok(None)
+            Ok((None, restrictions))
         }
     }
+
+    /// Returns the ids and the lowercased names of the searchable fields,
+    /// the field names are lowercased as the query words are normalized.
+    fn searchable_fields_names(&self) -> Result<Vec<(FieldId, String)>> {
+        let fields_ids_map = self.index.fields_ids_map(self.rtxn)?;
+        let searchable_fields = self.index.searchable_fields(self.rtxn)?;
+        let is_searchable =
+            |name: &str| searchable_fields.as_ref().map_or(true, |fields| fields.contains(&name));
+
+        Ok(fields_ids_map
+            .iter()
+            .filter(|(_, name)| is_searchable(name))
+            .map(|(field_id, name)| (field_id, name.to_lowercase()))
+            .collect())
+    }
+}
+
+/// Returns the ids of the searchable field a query part is scoped to and of its nested fields.
+fn scoped_fields_ids(searchable_fields: &[(FieldId, String)], field: &str) -> Vec<FieldId> {
+    searchable_fields
+        .iter()
+        .filter(|(_, name)| crate::is_faceted_by(name, field))
+        .map(|(field_id, _)| *field_id)
+        .collect()
+}
+
+/// The parts of the query that are not part of the query tree
+/// but restrict the candidates of the search.
+#[derive(Debug, Default, Clone)]
+pub struct QueryRestrictions {
+    /// The words and phrases prefixed by a `-`, the documents containing any of them are excluded.
+    pub negative: NegativeQuery,
+    /// The words and phrases prefixed by a searchable field name, the documents must contain
+    /// them in this field or one of its nested fields, or must not if negated.
+    pub scoped: Vec<ScopedRestriction>,
+}
+
+/// A word, or the consecutive words of a phrase, that must only be matched in some fields.
+#[derive(Debug, Clone)]
+pub struct ScopedRestriction {
+    /// The ids of the field the part is scoped to and of its nested fields.
+    pub fields_ids: Vec<FieldId>,
+    /// The consecutive words of the part, with their typo and prefix derivations.
+    pub queries: Vec<Query>,
+    /// Whether the documents containing the words in the fields are excluded instead.
+    pub negative: bool,
+}
+
+impl QueryRestrictions {
+    pub fn is_empty(&self) -> bool {
+        self.negative.is_empty() && self.scoped.is_empty()
+    }
 }
 
 /// Split the word depending on the frequency of pairs near together in the database documents.
@@ -534,11 +658,49 @@ fn create_query_tree(
     Ok(Operation::or(true, operation_children))
 }
 
+/// Creates the query tree of a query with explicit operators.
+///
+/// The matching strategy only applies to a query without operators, the words criterion
+/// only creates its buckets from the root of the query tree and would resolve the words
+/// optional in a nested operation as a plain union. The words of the ranges of the
+/// primitive query between the operators are therefore all required.
+fn create_expression_tree(
+    ctx: &impl Context,
+    terms_matching_strategy: TermsMatchingStrategy,
+    authorize_typos: bool,
+    query: &[PrimitiveQueryPart],
+    expression: &QueryExpression,
+) -> Result<Operation> {
+    let children = |children: &[QueryExpression]| -> Result<Vec<Operation>> {
+        children
+            .iter()
+            .map(|child| {
+                create_expression_tree(
+                    ctx,
+                    TermsMatchingStrategy::All,
+                    authorize_typos,
+                    query,
+                    child,
+                )
+            })
+            .collect()
+    };
+
+    match expression {
+        QueryExpression::Query(range) => {
+            create_query_tree(ctx, terms_matching_strategy, authorize_typos, &query[range.clone()])
+        }
+        QueryExpression::And(expressions) => Ok(Operation::and(children(expressions)?)),
+        QueryExpression::Or(expressions) => Ok(Operation::or(false, children(expressions)?)),
+    }
+}
+
 /// Main function that matchings words used for crop and highlight.
 fn create_matching_words(
     ctx: &impl Context,
     authorize_typos: bool,
     query: &[PrimitiveQueryPart],
+    leaves: &[Range<usize>],
 ) -> Result<MatchingWords> {
     /// Matches on the `PrimitiveQueryPart` and create matchings words from it.
     fn resolve_primitive_part(
@@ -556,7 +718,7 @@ fn create_matching_words(
                     for synonym in synonyms {
                         let synonym = synonym
                             .into_iter()
-                            .map(|syn| MatchingWord::new(syn.to_string(), 0, false))
+                            .map(|syn| MatchingWord::new(syn, 0, false).synonym_of(word.as_str()))
                             .collect();
                         matching_words.push((synonym, vec![id]));
                     }
@@ -636,10 +798,14 @@ fn create_matching_words(
                                 .collect();
 
                             if let Some(synonyms) = ctx.synonyms(&words)? {
+                                let synonym_of = words.join(" ");
                                 for synonym in synonyms {
                                     let synonym = synonym
                                         .into_iter()
-                                        .map(|syn| MatchingWord::new(syn.to_string(), 0, false))
+                                        .map(|syn| {
+                                            MatchingWord::new(syn, 0, false)
+                                                .synonym_of(synonym_of.as_str())
+                                        })
                                         .collect();
                                     matching_words.push((synonym, ids.clone()));
                                 }
@@ -677,13 +843,174 @@ fn create_matching_words(
         Ok(())
     }
 
+    // the ngrams are not created across the operators of the query,
+    // the ids of the words are the ones they have in the whole query.
     let mut matching_words = Vec::new();
-    ngrams(ctx, authorize_typos, query, &mut matching_words, 0)?;
+    for range in leaves {
+        let id = query[..range.start].iter().map(|x| x.len() as PrimitiveWordId).sum();
+        ngrams(ctx, authorize_typos, &query[range.clone()], &mut matching_words, id)?;
+    }
     Ok(MatchingWords::new(matching_words))
 }
 
 pub type PrimitiveQuery = Vec<PrimitiveQueryPart>;
 
+/// The words and phrases of the query prefixed by a `-`,
+/// the documents containing any of them must not be returned.
+pub type NegativeQuery = Vec<PrimitiveQueryPart>;
+
+/// The words and phrases of the query prefixed by a field name, e.g. `title:rust`.
+pub type ScopedQuery = Vec<ScopedQueryPart>;
+
+/// A word, or the consecutive words of a phrase, that must only be matched in a given field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScopedQueryPart {
+    pub field: String,
+    pub words: Vec<String>,
+    /// Whether the word is the last word of the query, the words of a phrase are never prefixes.
+    pub prefix: IsPrefix,
+    /// Whether the documents containing the words in the field are excluded instead.
+    pub negative: bool,
+}
+
+/// An explicit operator of the query, found between the parts of the primitive query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum QueryOperator {
+    Or,
+    OpenGroup,
+    CloseGroup,
+}
+
+/// The parts and operators of a query once its tokens are parsed.
+#[derive(Debug, Default)]
+struct ParsedQuery {
+    primitive: PrimitiveQuery,
+    /// The operators with the index of the primitive query part that follows them.
+    operators: Vec<(usize, QueryOperator)>,
+    negative: NegativeQuery,
+    scoped: ScopedQuery,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum QueryItem {
+    Part(usize),
+    Operator(QueryOperator),
+}
+
+/// The structure of a query defined by its explicit operators, an `OR` operator binds
+/// the parts or groups it is between and all the other parts and groups are required.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum QueryExpression {
+    And(Vec<QueryExpression>),
+    Or(Vec<QueryExpression>),
+    /// A range of consecutive parts of the primitive query.
+    Query(Range<usize>),
+}
+
+impl QueryExpression {
+    /// Parses the operators found between the `len` parts of a primitive query,
+    /// the unbalanced parentheses and the dangling `OR` operators are ignored.
+    fn parse(len: usize, operators: &[(usize, QueryOperator)]) -> Self {
+        let mut items = Vec::with_capacity(len + operators.len());
+        let mut operators = operators.iter().peekable();
+        for index in 0..=len {
+            while let Some((_, operator)) = operators.next_if(|(i, _)| *i == index) {
+                items.push(QueryItem::Operator(*operator));
+            }
+            if index < len {
+                items.push(QueryItem::Part(index));
+            }
+        }
+
+        Self::parse_and(&mut items.into_iter().peekable(), false)
+    }
+
+    fn parse_and(items: &mut Peekable<std::vec::IntoIter<QueryItem>>, nested: bool) -> Self {
+        let mut children: Vec<Self> = Vec::new();
+        while let Some(&item) = items.peek() {
+            match item {
+                QueryItem::Operator(QueryOperator::CloseGroup) => {
+                    items.next();
+                    if nested {
+                        break;
+                    }
+                }
+                QueryItem::Operator(QueryOperator::Or) => {
+                    items.next();
+                }
+                QueryItem::Part(_) | QueryItem::Operator(QueryOperator::OpenGroup) => {
+                    // the consecutive required parts are kept together to create ngrams.
+                    match (children.last_mut(), Self::parse_or(items)) {
+                        (Some(Self::Query(last)), Self::Query(range))
+                            if last.end == range.start =>
+                        {
+                            last.end = range.end
+                        }
+                        (_, operand) => children.push(operand),
+                    }
+                }
+            }
+        }
+
+        Self::and(children)
+    }
+
+    fn parse_or(items: &mut Peekable<std::vec::IntoIter<QueryItem>>) -> Self {
+        let mut operands = vec![Self::parse_operand(items)];
+        while items.peek() == Some(&QueryItem::Operator(QueryOperator::Or)) {
+            items.next();
+            match items.peek() {
+                Some(QueryItem::Part(_)) | Some(QueryItem::Operator(QueryOperator::OpenGroup)) => {
+                    operands.push(Self::parse_operand(items))
+                }
+                _ => break,
+            }
+        }
+
+        Self::or(operands)
+    }
+
+    fn parse_operand(items: &mut Peekable<std::vec::IntoIter<QueryItem>>) -> Self {
+        match items.next() {
+            Some(QueryItem::Part(index)) => Self::Query(index..index + 1),
+            Some(QueryItem::Operator(QueryOperator::OpenGroup)) => Self::parse_and(items, true),
+            _ => Self::And(Vec::new()),
+        }
+    }
+
+    fn and(children: Vec<Self>) -> Self {
+        let mut children: Vec<_> = children.into_iter().filter(|c| !c.is_empty()).collect();
+        if children.len() == 1 {
+            children.pop().unwrap()
+        } else {
+            Self::And(children)
+        }
+    }
+
+    fn or(children: Vec<Self>) -> Self {
+        let mut children: Vec<_> = children.into_iter().filter(|c| !c.is_empty()).collect();
+        if children.len() == 1 {
+            children.pop().unwrap()
+        } else {
+            Self::Or(children)
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        matches!(self, Self::And(children) | Self::Or(children) if children.is_empty())
+    }
+
+    /// Returns the ranges of the primitive query between the operators.
+    fn leaves(&self) -> Vec<Range<usize>> {
+        match self {
+            Self::Query(range) => vec![range.clone()],
+            Self::And(children) | Self::Or(children) => {
+                children.iter().flat_map(Self::leaves).collect()
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum PrimitiveQueryPart {
     Phrase(Vec<String>),
@@ -709,54 +1036,172 @@ impl PrimitiveQueryPart {
 
 /// Create primitive query from tokenized query string,
 /// the primitive query is an intermediate state to build the query tree.
-fn create_primitive_query<A>(
+///
+/// The words and phrases directly preceded by a `-`, itself preceded by a whitespace
+/// or at the start of the query, are returned apart in the negative query.
+///
+/// The words and phrases directly preceded by a field name and a `:` are returned apart
+/// in the scoped query, they are also part of the primitive query if not negated. A word
+/// followed by a `:` is only a field name if `is_field` accepts it, e.g. not in `meeting 10:30`.
+///
+/// The `OR` words, uppercased in the original `text` if any, and the parentheses outside of
+/// the phrases are returned as operators.
+fn create_primitive_query<A, F>(
+    text: Option<&str>,
     query: ClassifiedTokenIter<A>,
     stop_words: Option<Set<&[u8]>>,
     words_limit: Option<usize>,
-) -> PrimitiveQuery
+    is_field: F,
+) -> ParsedQuery
 where
     A: AsRef<[u8]>,
+    F: Fn(&str) -> bool,
 {
-    let mut primitive_query = Vec::new();
+    /// Pushes a word or phrase in the parts of the query it belongs to,
+    /// a single scoped word is a word of the primitive query, not a phrase.
+    fn push_part(
+        words: Vec<String>,
+        prefix: IsPrefix,
+        field: Option<String>,
+        negative: bool,
+        parsed: &mut ParsedQuery,
+    ) {
+        match field {
+            Some(field) => {
+                if !negative {
+                    let part = match words.as_slice() {
+                        [word] => PrimitiveQueryPart::Word(word.clone(), prefix),
+                        words => PrimitiveQueryPart::Phrase(words.to_vec()),
+                    };
+                    parsed.primitive.push(part);
+                }
+                parsed.scoped.push(ScopedQueryPart { field, words, prefix, negative });
+            }
+            None if negative => parsed.negative.push(PrimitiveQueryPart::Phrase(words)),
+            None => parsed.primitive.push(PrimitiveQueryPart::Phrase(words)),
+        }
+    }
+
+    let mut parsed = ParsedQuery::default();
     let mut phrase = Vec::new();
     let mut quoted = false;
+    // whether the next word or phrase is negated and whether the current phrase is.
+    let mut negative = false;
+    let mut negative_phrase = false;
+    // the field the next word or phrase is scoped to and the one of the current phrase.
+    let mut field = None;
+    let mut phrase_field = None;
+    let mut previous_char = None;
 
     let parts_limit = words_limit.unwrap_or(usize::MAX);
 
     let mut peekable = query.peekable();
     while let Some(token) = peekable.next() {
         // early return if word limit is exceeded
-        if primitive_query.len() >= parts_limit {
-            return primitive_query;
+        if parsed.primitive.len() >= parts_limit {
+            return parsed;
         }
 
         match token.kind {
             TokenKind::Word | TokenKind::StopWord => {
                 // 1. if the word is quoted we push it in a phrase-buffer waiting for the ending quote,
-                // 2. if the word is not the last token of the query and is not a stop_word we push it as a non-prefix word,
-                // 3. if the word is the last token of the query we push it as a prefix word.
+                // 2. if the word is an uppercase `OR` we push it as an operator,
+                // 3. if the word is a field name directly followed by a `:` it is the field the next word is scoped to,
+                // 4. if the word is scoped to a field we push it as a phrase in the scoped query,
+                // 5. if the word is negated we push it as a non-prefix word in the negative query,
+                // 6. if the word is not the last token of the query and is not a stop_word we push it as a non-prefix word,
+                // 7. if the word is the last token of the query we push it as a prefix word.
+                let is_field = peekable.peek().map_or(false, |next| {
+                    next.is_separator() && matches!(next.lemma(), ":" | ":\"")
+                }) && is_field(token.lemma());
                 if quoted {
                     phrase.push(token.lemma().to_string());
+                } else if field.is_none()
+                    && text.and_then(|text| text.get(token.byte_start..token.byte_end))
+                        == Some("OR")
+                {
+                    parsed.operators.push((parsed.primitive.len(), QueryOperator::Or));
+                } else if is_field && field.is_none() {
+                    field = Some(token.lemma().to_string());
+                    previous_char = token.lemma().chars().last();
+                    continue;
+                } else if field.is_some() {
+                    let words = vec![token.lemma().to_string()];
+                    let prefix = peekable.peek().is_none();
+                    push_part(words, prefix, field.take(), negative, &mut parsed);
+                } else if negative {
+                    parsed
+                        .negative
+                        .push(PrimitiveQueryPart::Word(token.lemma().to_string(), false));
                 } else if peekable.peek().is_some() {
                     if !stop_words.as_ref().map_or(false, |swords| swords.contains(token.lemma())) {
-                        primitive_query
+                        parsed
+                            .primitive
                             .push(PrimitiveQueryPart::Word(token.lemma().to_string(), false));
                     }
                 } else {
-                    primitive_query.push(PrimitiveQueryPart::Word(token.lemma().to_string(), true));
+                    parsed
+                        .primitive
+                        .push(PrimitiveQueryPart::Word(token.lemma().to_string(), true));
                 }
+                negative = false;
+                previous_char = token.lemma().chars().last();
             }
             TokenKind::Separator(separator_kind) => {
+                // a field name must be directly followed by the word or phrase it scopes,
+                // otherwise it is a plain word, e.g. `http://`.
+                if !quoted && token.lemma().chars().any(|c| c != ':' && c != '"') {
+                    if let Some(word) = field.take() {
+                        let part = PrimitiveQueryPart::Word(word, false);
+                        if negative {
+                            parsed.negative.push(part);
+                        } else {
+                            parsed.primitive.push(part);
+                        }
+                    }
+                }
+
+                // a `-` only negates the following word or phrase when it starts a term,
+                // e.g. `laptop -refurbished` but not `well-known`.
+                // the parentheses are only operators outside of the phrases.
+                let mut groups = Vec::new();
+                let mut quoted_char = quoted;
+                for c in token.lemma().chars() {
+                    match c {
+                        '-' => negative = previous_char.map_or(true, char::is_whitespace),
+                        '"' => quoted_char = !quoted_char,
+                        ':' if field.is_some() => (),
+                        '(' if !quoted_char => {
+                            groups.push(QueryOperator::OpenGroup);
+                            negative = false;
+                        }
+                        ')' if !quoted_char => {
+                            groups.push(QueryOperator::CloseGroup);
+                            negative = false;
+                        }
+                        _ => negative = false,
+                    }
+                    previous_char = Some(c);
+                }
+
                 let quote_count = token.lemma().chars().filter(|&s| s == '"').count();
                 // swap quoted state if we encounter a double quote
                 if quote_count % 2 != 0 {
                     quoted = !quoted;
+                    if quoted {
+                        negative_phrase = negative;
+                        phrase_field = field.take();
+                    }
                 }
                 // if there is a quote or a hard separator we close the phrase.
                 if !phrase.is_empty() && (quote_count > 0 || separator_kind == SeparatorKind::Hard)
                 {
-                    primitive_query.push(PrimitiveQueryPart::Phrase(mem::take(&mut phrase)));
+                    let words = mem::take(&mut phrase);
+                    push_part(words, false, phrase_field.clone(), negative_phrase, &mut parsed);
                 }
+
+                let index = parsed.primitive.len();
+                parsed.operators.extend(groups.into_iter().map(|group| (index, group)));
             }
             _ => (),
         }
@@ -764,10 +1209,20 @@ where
 
     // If a quote is never closed, we consider all of the end of the query as a phrase.
     if !phrase.is_empty() {
-        primitive_query.push(PrimitiveQueryPart::Phrase(mem::take(&mut phrase)));
+        let words = mem::take(&mut phrase);
+        push_part(words, false, phrase_field, negative_phrase, &mut parsed);
+    }
+
+    // A field name that ends the query is the last word being typed.
+    if let Some(word) = field {
+        if negative {
+            parsed.negative.push(PrimitiveQueryPart::Word(word, false));
+        } else {
+            parsed.primitive.push(PrimitiveQueryPart::Word(word, true));
+        }
     }
 
-    primitive_query
+    parsed
 }
 
 /// Returns the maximum number of typos that this Operation allows.
@@ -814,20 +1269,24 @@ mod test {
     }
 
     impl TestContext {
-        fn build<A: AsRef<[u8]>>(
+        fn build(
             &self,
             terms_matching_strategy: TermsMatchingStrategy,
             authorize_typos: bool,
             words_limit: Option<usize>,
-            query: ClassifiedTokenIter<A>,
+            query: &str,
         ) -> Result<Option<(Operation, PrimitiveQuery)>> {
-            let primitive_query = create_primitive_query(query, None, words_limit);
+            let tokens = query.tokenize();
+            let ParsedQuery { primitive: primitive_query, operators, .. } =
+                create_primitive_query(Some(query), tokens, None, words_limit, |_| true);
             if !primitive_query.is_empty() {
-                let qt = create_query_tree(
+                let expression = QueryExpression::parse(primitive_query.len(), &operators);
+                let qt = create_expression_tree(
                     self,
                     terms_matching_strategy,
                     authorize_typos,
                     &primitive_query,
+                    &expression,
                 )?;
                 Ok(Some((qt, primitive_query)))
             } else {
@@ -935,10 +1394,8 @@ mod test {
     #[test]
     fn prefix() {
         let query = "hey friends";
-        let tokens = query.tokenize();
-
         let (query_tree, _) = TestContext::default()
-            .build(TermsMatchingStrategy::All, true, None, tokens)
+            .build(TermsMatchingStrategy::All, true, None, query)
             .unwrap()
             .unwrap();
 
@@ -954,10 +1411,8 @@ mod test {
     #[test]
     fn no_prefix() {
         let query = "hey friends ";
-        let tokens = query.tokenize();
-
         let (query_tree, _) = TestContext::default()
-            .build(TermsMatchingStrategy::All, true, None, tokens)
+            .build(TermsMatchingStrategy::All, true, None, query)
             .unwrap()
             .unwrap();
 
@@ -973,10 +1428,8 @@ mod test {
     #[test]
     fn synonyms() {
         let query = "hello world ";
-        let tokens = query.tokenize();
-
         let (query_tree, _) = TestContext::default()
-            .build(TermsMatchingStrategy::All, true, None, tokens)
+            .build(TermsMatchingStrategy::All, true, None, query)
             .unwrap()
             .unwrap();
 
@@ -1000,10 +1453,8 @@ mod test {
     #[test]
     fn complex_synonyms() {
         let query = "new york city ";
-        let tokens = query.tokenize();
-
         let (query_tree, _) = TestContext::default()
-            .build(TermsMatchingStrategy::All, true, None, tokens)
+            .build(TermsMatchingStrategy::All, true, None, query)
             .unwrap()
             .unwrap();
 
@@ -1036,10 +1487,8 @@ mod test {
     #[test]
     fn ngrams() {
         let query = "n grams ";
-        let tokens = query.tokenize();
-
         let (query_tree, _) = TestContext::default()
-            .build(TermsMatchingStrategy::All, true, None, tokens)
+            .build(TermsMatchingStrategy::All, true, None, query)
             .unwrap()
             .unwrap();
 
@@ -1055,10 +1504,8 @@ mod test {
     #[test]
     fn word_split() {
         let query = "wordsplit fish ";
-        let tokens = query.tokenize();
-
         let (query_tree, _) = TestContext::default()
-            .build(TermsMatchingStrategy::All, true, None, tokens)
+            .build(TermsMatchingStrategy::All, true, None, query)
             .unwrap()
             .unwrap();
 
@@ -1076,10 +1523,8 @@ mod test {
     #[test]
     fn word_split_choose_pair_with_max_freq() {
         let query = "quickbrownfox";
-        let tokens = query.tokenize();
-
         let (query_tree, _) = TestContext::default()
-            .build(TermsMatchingStrategy::All, true, None, tokens)
+            .build(TermsMatchingStrategy::All, true, None, query)
             .unwrap()
             .unwrap();
 
@@ -1093,10 +1538,8 @@ mod test {
     #[test]
     fn phrase() {
         let query = "\"hey friends\" \" \" \"wooop";
-        let tokens = query.tokenize();
-
         let (query_tree, _) = TestContext::default()
-            .build(TermsMatchingStrategy::All, true, None, tokens)
+            .build(TermsMatchingStrategy::All, true, None, query)
             .unwrap()
             .unwrap();
 
@@ -1111,10 +1554,8 @@ mod test {
     fn phrase_2() {
         // https://github.com/meilisearch/meilisearch/issues/2722
         let query = "coco \"harry\"";
-        let tokens = query.tokenize();
-
         let (query_tree, _) = TestContext::default()
-            .build(TermsMatchingStrategy::default(), true, None, tokens)
+            .build(TermsMatchingStrategy::default(), true, None, query)
             .unwrap()
             .unwrap();
 
@@ -1130,10 +1571,8 @@ mod test {
     #[test]
     fn phrase_with_hard_separator() {
         let query = "\"hey friends. wooop wooop\"";
-        let tokens = query.tokenize();
-
         let (query_tree, _) = TestContext::default()
-            .build(TermsMatchingStrategy::All, true, None, tokens)
+            .build(TermsMatchingStrategy::All, true, None, query)
             .unwrap()
             .unwrap();
 
@@ -1147,10 +1586,8 @@ mod test {
     #[test]
     fn optional_word() {
         let query = "hey my friend ";
-        let tokens = query.tokenize();
-
         let (query_tree, _) = TestContext::default()
-            .build(TermsMatchingStrategy::default(), true, None, tokens)
+            .build(TermsMatchingStrategy::default(), true, None, query)
             .unwrap()
             .unwrap();
 
@@ -1180,10 +1617,8 @@ mod test {
     #[test]
     fn optional_word_phrase() {
         let query = "\"hey my\"";
-        let tokens = query.tokenize();
-
         let (query_tree, _) = TestContext::default()
-            .build(TermsMatchingStrategy::default(), true, None, tokens)
+            .build(TermsMatchingStrategy::default(), true, None, query)
             .unwrap()
             .unwrap();
 
@@ -1195,10 +1630,8 @@ mod test {
     #[test]
     fn optional_word_multiple_phrases() {
         let query = r#""hey" my good "friend""#;
-        let tokens = query.tokenize();
-
         let (query_tree, _) = TestContext::default()
-            .build(TermsMatchingStrategy::default(), true, None, tokens)
+            .build(TermsMatchingStrategy::default(), true, None, query)
             .unwrap()
             .unwrap();
 
@@ -1225,10 +1658,8 @@ mod test {
     #[test]
     fn no_typo() {
         let query = "hey friends ";
-        let tokens = query.tokenize();
-
         let (query_tree, _) = TestContext::default()
-            .build(TermsMatchingStrategy::All, false, None, tokens)
+            .build(TermsMatchingStrategy::All, false, None, query)
             .unwrap()
             .unwrap();
 
@@ -1244,10 +1675,8 @@ mod test {
     #[test]
     fn words_limit() {
         let query = "\"hey my\" good friend";
-        let tokens = query.tokenize();
-
         let (query_tree, _) = TestContext::default()
-            .build(TermsMatchingStrategy::All, false, Some(2), tokens)
+            .build(TermsMatchingStrategy::All, false, Some(2), query)
             .unwrap()
             .unwrap();
 
@@ -1258,6 +1687,189 @@ mod test {
         "###);
     }
 
+    #[test]
+    fn negative_words() {
+        let query = "laptop -refurbished well-known -\"hard drive\" pro";
+        let tokens = query.tokenize();
+
+        let ParsedQuery { primitive: primitive_query, negative: negative_query, .. } =
+            create_primitive_query(Some(query), tokens, None, None, |_| true);
+
+        insta::assert_debug_snapshot!(primitive_query, @r###"
+        [
+            Word(
+                "laptop",
+                false,
+            ),
+            Word(
+                "well",
+                false,
+            ),
+            Word(
+                "known",
+                false,
+            ),
+            Word(
+                "pro",
+                true,
+            ),
+        ]
+        "###);
+        insta::assert_debug_snapshot!(negative_query, @r###"
+        [
+            Word(
+                "refurbished",
+                false,
+            ),
+            Phrase(
+                [
+                    "hard",
+                    "drive",
+                ],
+            ),
+        ]
+        "###);
+    }
+
+    #[test]
+    fn scoped_words() {
+        let query = "title:rust -author:\"steve klabnik\" book at:home http://rust title:cargo";
+        let tokens = query.tokenize();
+
+        // `at` is not a field, it is a plain word.
+        let is_field = |field: &str| field == "title" || field == "author";
+        let ParsedQuery { primitive: primitive_query, scoped: scoped_query, .. } =
+            create_primitive_query(Some(query), tokens, None, None, is_field);
+
+        insta::assert_debug_snapshot!(primitive_query, @r###"
+        [
+            Word(
+                "rust",
+                false,
+            ),
+            Word(
+                "book",
+                false,
+            ),
+            Word(
+                "at",
+                false,
+            ),
+            Word(
+                "home",
+                false,
+            ),
+            Word(
+                "http",
+                false,
+            ),
+            Word(
+                "rust",
+                false,
+            ),
+            Word(
+                "cargo",
+                true,
+            ),
+        ]
+        "###);
+        insta::assert_debug_snapshot!(scoped_query, @r###"
+        [
+            ScopedQueryPart {
+                field: "title",
+                words: [
+                    "rust",
+                ],
+                prefix: false,
+                negative: false,
+            },
+            ScopedQueryPart {
+                field: "author",
+                words: [
+                    "steve",
+                    "klabnik",
+                ],
+                prefix: false,
+                negative: true,
+            },
+            ScopedQueryPart {
+                field: "title",
+                words: [
+                    "cargo",
+                ],
+                prefix: true,
+                negative: false,
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn or_operators() {
+        let query = "(phone OR mobile) case";
+
+        let (query_tree, _) = TestContext::default()
+            .build(TermsMatchingStrategy::All, true, None, query)
+            .unwrap()
+            .unwrap();
+
+        insta::assert_debug_snapshot!(query_tree, @r###"
+        AND
+          OR
+            Tolerant { word: "phone", max typo: 1 }
+            Tolerant { word: "mobile", max typo: 1 }
+          PrefixExact { word: "case" }
+        "###);
+
+        // only the uppercase `OR` is an operator.
+        let query = "phone or mobile";
+        let tokens = query.tokenize();
+
+        let ParsedQuery { primitive, operators, .. } =
+            create_primitive_query(Some(query), tokens, None, None, |_| true);
+        assert_eq!(primitive.len(), 3);
+        assert!(operators.is_empty());
+
+        // without the original text the `OR` words can't be told apart.
+        let query = "phone OR mobile";
+        let tokens = query.tokenize();
+
+        let ParsedQuery { primitive, operators, .. } =
+            create_primitive_query(None, tokens, None, None, |_| true);
+        assert_eq!(primitive.len(), 3);
+        assert!(operators.is_empty());
+    }
+
+    #[test]
+    fn query_expression() {
+        use QueryExpression::{And, Or, Query};
+        use QueryOperator::{CloseGroup, OpenGroup, Or as OrOperator};
+
+        // `a b OR c d`
+        let expression = QueryExpression::parse(4, &[(2, OrOperator)]);
+        assert_eq!(
+            expression,
+            And(vec![Query(0..1), Or(vec![Query(1..2), Query(2..3)]), Query(3..4)])
+        );
+
+        // `a (b c) d`, the groups without operators are kept with their neighbours.
+        let expression = QueryExpression::parse(4, &[(1, OpenGroup), (3, CloseGroup)]);
+        assert_eq!(expression, Query(0..4));
+
+        // `(a OR (b c)) d OR`, the dangling operators are ignored.
+        let operators = [
+            (0, OpenGroup),
+            (1, OrOperator),
+            (1, OpenGroup),
+            (3, CloseGroup),
+            (3, CloseGroup),
+            (4, OrOperator),
+        ];
+        let expression = QueryExpression::parse(4, &operators);
+        assert_eq!(expression, And(vec![Or(vec![Query(0..1), Query(1..3)]), Query(3..4)]));
+        assert_eq!(expression.leaves(), vec![0..1, 1..3, 3..4]);
+    }
+
     #[test]
     fn test_min_word_len_typo() {
         let exact_words = fst::Set::from_iter([b""]).unwrap().map_data(Cow::Owned).unwrap();
@@ -1287,13 +1899,12 @@ mod test {
     #[test]
     fn disable_typo_on_word() {
         let query = "goodbye";
-        let tokens = query.tokenize();
 
         let exact_words = fst::Set::from_iter(Some("goodbye")).unwrap().into_fst().into_inner();
         let exact_words = Some(fst::Set::new(exact_words).unwrap().map_data(Cow::Owned).unwrap());
         let context = TestContext { exact_words, ..Default::default() };
         let (query_tree, _) =
-            context.build(TermsMatchingStrategy::All, true, Some(2), tokens).unwrap().unwrap();
+            context.build(TermsMatchingStrategy::All, true, Some(2), query).unwrap().unwrap();
 
         assert!(matches!(
             query_tree,
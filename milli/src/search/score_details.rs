@@ -0,0 +1,301 @@
+use serde::Serialize;
+
+use crate::distance_between_two_points;
+
+/// The details of why a document was ranked in a given bucket by a ranking rule.
+///
+/// Every ranking rule that splits its candidates into buckets attaches one of these
+/// to the buckets it returns, a returned document therefore carries one `ScoreDetails`
+/// by ranking rule, in the order of the ranking rules.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ScoreDetails {
+    Words(Words),
+    Typo(Typo),
+    Proximity(Proximity),
+    Attribute(Attribute),
+    Exactness(Exactness),
+    Sort(Sort),
+    GeoSort(GeoSort),
+    Vector(Vector),
+    Custom(Custom),
+}
+
+impl ScoreDetails {
+    /// Returns the rank of this bucket, `None` if the ranking rule
+    /// can't be expressed as a bounded rank (e.g. sorting by a field).
+    pub fn rank(&self) -> Option<Rank> {
+        match self {
+            ScoreDetails::Words(details) => Some(details.rank()),
+            ScoreDetails::Typo(details) => Some(details.rank()),
+            ScoreDetails::Proximity(details) => Some(details.rank()),
+            ScoreDetails::Attribute(details) => Some(details.rank()),
+            ScoreDetails::Exactness(details) => Some(details.rank()),
+            ScoreDetails::Custom(details) => Some(details.rank()),
+            ScoreDetails::Vector(details) => Some(details.rank()),
+            ScoreDetails::Sort(_) | ScoreDetails::GeoSort(_) => None,
+        }
+    }
+
+    /// Computes the normalized score of a document, between `0.0` and `1.0`, from the
+    /// details returned by the ranking rules, in the order of the ranking rules.
+    ///
+    /// Ranking rules that can't be expressed as a bounded rank are ignored.
+    pub fn global_score<'a>(details: impl Iterator<Item = &'a ScoreDetails>) -> f64 {
+        Rank::global_score(details.filter_map(ScoreDetails::rank))
+    }
+
+    /// Returns `true` if the buckets returned after the one with these details can't have
+    /// a better global score, it is the case when no ranking rule that doesn't participate
+    /// in the score is followed by one that does.
+    pub fn following_buckets_are_worse(details: &[ScoreDetails]) -> bool {
+        details.iter().skip_while(|details| details.rank().is_some()).all(|d| d.rank().is_none())
+    }
+}
+
+/// The rank of a bucket, `max_rank` is the best possible rank, `1` the worst one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rank {
+    pub rank: u32,
+    pub max_rank: u32,
+}
+
+impl Rank {
+    /// Returns the score of this rank alone, between `0.0` and `1.0`.
+    pub fn local_score(self) -> f64 {
+        self.rank as f64 / self.max_rank.max(1) as f64
+    }
+
+    /// Merges the ranks of the successive ranking rules as if they were the digits of a
+    /// single number, the first rank being the most significant one, and normalizes it.
+    pub fn global_score(ranks: impl Iterator<Item = Self>) -> f64 {
+        let mut score = 0.0;
+        let mut weight = 1.0;
+        for Rank { rank, max_rank } in ranks {
+            let max_rank = max_rank.max(1);
+            let rank = rank.clamp(1, max_rank);
+            weight /= max_rank as f64;
+            score += (rank - 1) as f64 * weight;
+        }
+        score + weight
+    }
+}
+
+/// The number of query words matched by the documents of the bucket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Words {
+    pub matching_words: u32,
+    pub max_matching_words: u32,
+}
+
+impl Words {
+    pub fn rank(&self) -> Rank {
+        Rank { rank: self.matching_words, max_rank: self.max_matching_words }
+    }
+}
+
+/// The number of typos the documents of the bucket contain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Typo {
+    pub typo_count: u32,
+    pub max_typo_count: u32,
+}
+
+impl Typo {
+    pub fn rank(&self) -> Rank {
+        Rank {
+            rank: self.max_typo_count.saturating_sub(self.typo_count) + 1,
+            max_rank: self.max_typo_count + 1,
+        }
+    }
+}
+
+/// The sum of the proximities between the query words in the documents of the bucket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Proximity {
+    pub proximity: u32,
+    pub max_proximity: u32,
+}
+
+impl Proximity {
+    pub fn rank(&self) -> Rank {
+        Rank {
+            rank: self.max_proximity.saturating_sub(self.proximity) + 1,
+            max_rank: self.max_proximity + 1,
+        }
+    }
+}
+
+/// The average absolute position of the query words in the documents of the bucket,
+/// the lower the better. Absolute positions are ordered by searchable attribute first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Attribute {
+    pub average_position: u32,
+    pub max_position: u32,
+}
+
+impl Attribute {
+    pub fn rank(&self) -> Rank {
+        Rank {
+            rank: self.max_position.saturating_sub(self.average_position) + 1,
+            max_rank: self.max_position + 1,
+        }
+    }
+}
+
+/// How exactly the documents of the bucket match the query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Exactness {
+    /// An attribute contains exactly the query and nothing else.
+    ExactAttribute { max_matching_words: u32 },
+    /// An attribute starts with exactly the query.
+    AttributeStartsWith { max_matching_words: u32 },
+    /// The documents contain `matching_words` of the query words without any derivation.
+    ExactWords { matching_words: u32, max_matching_words: u32 },
+}
+
+impl Exactness {
+    pub fn rank(&self) -> Rank {
+        match *self {
+            Exactness::ExactAttribute { max_matching_words } => {
+                Rank { rank: max_matching_words + 3, max_rank: max_matching_words + 3 }
+            }
+            Exactness::AttributeStartsWith { max_matching_words } => {
+                Rank { rank: max_matching_words + 2, max_rank: max_matching_words + 3 }
+            }
+            Exactness::ExactWords { matching_words, max_matching_words } => {
+                Rank { rank: matching_words + 1, max_rank: max_matching_words + 3 }
+            }
+        }
+    }
+}
+
+/// The value of the field the documents of the bucket are sorted by,
+/// `null` for the documents that don't have this field.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sort {
+    pub field_name: String,
+    pub ascending: bool,
+    pub value: serde_json::Value,
+}
+
+/// The geo point of the document of the bucket that was compared to the target point.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeoSort {
+    pub target_point: [f64; 2],
+    pub ascending: bool,
+    pub value: Option<[f64; 2]>,
+}
+
+impl GeoSort {
+    /// The distance in meters between the target point and the geo point of the document.
+    pub fn distance(&self) -> Option<f64> {
+        self.value.map(|value| distance_between_two_points(&self.target_point, &value))
+    }
+}
+
+/// The similarity between the vector of the search and the most similar vector of the document.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Vector {
+    pub similarity: f32,
+}
+
+impl Vector {
+    /// The number of ranks the similarities are spread over.
+    const MAX_RANK: u32 = 1000;
+
+    /// The similarity is bounded to the one of the cosine, between `-1.0` and `1.0`,
+    /// a dot product greater than `1.0` therefore has the best rank.
+    pub fn rank(&self) -> Rank {
+        let similarity = (self.similarity.clamp(-1.0, 1.0) as f64 + 1.0) / 2.0;
+        let rank = 1 + (similarity * (Self::MAX_RANK - 1) as f64).round() as u32;
+        Rank { rank, max_rank: Self::MAX_RANK }
+    }
+}
+
+/// The bucket in which a custom ranking rule placed the documents.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Custom {
+    pub name: String,
+    pub rank: u32,
+    pub max_rank: u32,
+}
+
+impl Custom {
+    pub fn rank(&self) -> Rank {
+        Rank { rank: self.rank, max_rank: self.max_rank }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn global_score_is_lexicographic() {
+        let best = [Rank { rank: 3, max_rank: 3 }, Rank { rank: 1, max_rank: 5 }];
+        let worse = [Rank { rank: 2, max_rank: 3 }, Rank { rank: 5, max_rank: 5 }];
+
+        let best = Rank::global_score(best.iter().copied());
+        let worse = Rank::global_score(worse.iter().copied());
+        assert!(best > worse, "{} should be greater than {}", best, worse);
+    }
+
+    #[test]
+    fn global_score_bounds() {
+        let max = [Rank { rank: 3, max_rank: 3 }, Rank { rank: 5, max_rank: 5 }];
+        assert!((Rank::global_score(max.iter().copied()) - 1.0).abs() < 1e-9);
+
+        let min = [Rank { rank: 1, max_rank: 3 }, Rank { rank: 1, max_rank: 5 }];
+        assert!((Rank::global_score(min.iter().copied()) - 1.0 / 15.0).abs() < 1e-9);
+
+        assert_eq!(Rank::global_score(std::iter::empty()), 1.0);
+    }
+
+    #[test]
+    fn sort_details_are_ignored() {
+        let details = [
+            ScoreDetails::Words(Words { matching_words: 2, max_matching_words: 2 }),
+            ScoreDetails::Sort(Sort {
+                field_name: "price".to_string(),
+                ascending: true,
+                value: serde_json::json!(12),
+            }),
+        ];
+        assert!((ScoreDetails::global_score(details.iter()) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn vector_rank() {
+        let rank = |similarity| Vector { similarity }.rank();
+        assert_eq!(rank(1.0), Rank { rank: 1000, max_rank: 1000 });
+        assert_eq!(rank(12.5), Rank { rank: 1000, max_rank: 1000 });
+        assert_eq!(rank(-1.0), Rank { rank: 1, max_rank: 1000 });
+        assert!(rank(0.8).local_score() > rank(0.2).local_score());
+        assert!(rank(0.99).local_score() < 1.0);
+    }
+
+    #[test]
+    fn following_buckets_are_worse() {
+        let words = ScoreDetails::Words(Words { matching_words: 1, max_matching_words: 2 });
+        let typo = ScoreDetails::Typo(Typo { typo_count: 1, max_typo_count: 2 });
+        let sort = ScoreDetails::Sort(Sort {
+            field_name: "price".to_string(),
+            ascending: true,
+            value: serde_json::json!(12),
+        });
+
+        assert!(ScoreDetails::following_buckets_are_worse(&[words.clone(), typo.clone()]));
+        assert!(ScoreDetails::following_buckets_are_worse(&[words.clone(), sort.clone()]));
+        assert!(!ScoreDetails::following_buckets_are_worse(&[words, sort, typo]));
+    }
+}
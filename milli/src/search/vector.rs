@@ -0,0 +1,236 @@
+use std::cmp::Ordering;
+
+use roaring::RoaringBitmap;
+use serde::{Deserialize, Serialize};
+
+use crate::error::UserError;
+use crate::{DocumentId, Index, Result, BEU32};
+
+/// The similarity used to compare the vector of a search with the vectors of the documents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum VectorSimilarity {
+    /// The cosine of the angle between the vectors, between `-1.0` and `1.0`.
+    Cosine,
+    /// The dot product of the vectors, the cosine for normalized vectors.
+    Dot,
+}
+
+impl Default for VectorSimilarity {
+    fn default() -> Self {
+        VectorSimilarity::Cosine
+    }
+}
+
+impl VectorSimilarity {
+    /// Returns the similarity between the two vectors, the greater the more similar.
+    pub fn similarity(self, a: &[f32], b: &[f32]) -> f32 {
+        let dot: f32 = a.iter().zip(b).map(|(a, b)| a * b).sum();
+        match self {
+            VectorSimilarity::Dot => dot,
+            VectorSimilarity::Cosine => {
+                let norms = norm(a) * norm(b);
+                if norms == 0.0 {
+                    0.0
+                } else {
+                    dot / norms
+                }
+            }
+        }
+    }
+}
+
+fn norm(vector: &[f32]) -> f32 {
+    vector.iter().map(|x| x * x).sum::<f32>().sqrt()
+}
+
+/// Returns the candidates that have vectors along with the similarity of their most similar
+/// vector to the target, ordered from the most similar to the least similar document.
+pub(crate) fn nearest_documents(
+    rtxn: &heed::RoTxn,
+    index: &Index,
+    target: &[f32],
+    similarity: VectorSimilarity,
+    candidates: &RoaringBitmap,
+) -> Result<Vec<(DocumentId, f32)>> {
+    let mut documents = Vec::new();
+    for docid in candidates {
+        let vectors = match index.vectors.get(rtxn, &BEU32::new(docid))? {
+            Some(vectors) => vectors,
+            None => continue,
+        };
+
+        // the vectors of the documents all have the same dimensions, checked at indexing
+        // time, we only need to check the first one against the target.
+        if let Some(vector) = vectors.first().filter(|_| documents.is_empty()) {
+            if vector.len() != target.len() {
+                let (expected, found) = (vector.len(), target.len());
+                let error =
+                    UserError::InvalidVectorDimensions { document_id: None, expected, found };
+                return Err(error.into());
+            }
+        }
+
+        let mut best = None;
+        for vector in vectors {
+            let score = similarity.similarity(target, &vector);
+            best = Some(best.map_or(score, |best: f32| best.max(score)));
+        }
+
+        if let Some(score) = best {
+            documents.push((docid, score));
+        }
+    }
+
+    // the most similar documents first, the smallest ids first in case of equality.
+    documents.sort_by(|(aid, a), (bid, b)| {
+        b.partial_cmp(a).unwrap_or(Ordering::Equal).then_with(|| aid.cmp(bid))
+    });
+
+    Ok(documents)
+}
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+    use maplit::hashset;
+
+    use super::*;
+    use crate::index::tests::TempIndex;
+    use crate::{Filter, Search, SearchResult};
+
+    #[test]
+    fn similarities() {
+        let a = [1.0, 0.0];
+        let b = [2.0, 2.0];
+
+        assert_eq!(VectorSimilarity::Dot.similarity(&a, &b), 2.0);
+        let cosine = VectorSimilarity::Cosine.similarity(&a, &b);
+        assert!((cosine - std::f32::consts::FRAC_1_SQRT_2).abs() < f32::EPSILON);
+        assert_eq!(VectorSimilarity::Cosine.similarity(&a, &[0.0, 0.0]), 0.0);
+    }
+
+    #[test]
+    fn vector_search() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("kind") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "kind": "fruit", "_vectors": [1.0, 0.0, 0.0] },
+                { "id": 1, "kind": "vegetable", "_vectors": [0.9, 0.1, 0.0] },
+                { "id": 2, "kind": "fruit", "_vectors": [[0.0, 0.0, 1.0], [0.7, 0.7, 0.0]] },
+                { "id": 3, "kind": "fruit", "_vectors": [0.0, -1.0, 0.0] },
+                { "id": 4, "kind": "fruit" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.vectors.len(&rtxn).unwrap(), 4);
+
+        // the documents are ordered by their most similar vector.
+        let mut search = Search::new(&rtxn, &index);
+        search.vector(vec![1.0, 0.0, 0.0]);
+        let SearchResult { documents_ids, candidates, document_scores, .. } =
+            search.execute().unwrap();
+        assert_eq!(documents_ids, vec![0, 1, 2, 3]);
+        assert_eq!(candidates, (0..4).collect::<RoaringBitmap>());
+        assert_eq!(document_scores.len(), 4);
+
+        // the number of documents is limited and the filter is respected.
+        let mut search = Search::new(&rtxn, &index);
+        search.vector(vec![1.0, 0.0, 0.0]);
+        search.filter(Filter::from_str("kind = fruit").unwrap().unwrap());
+        search.limit(2);
+        let SearchResult { documents_ids, candidates, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![0, 2]);
+        assert_eq!(candidates, [0, 2, 3].iter().copied().collect::<RoaringBitmap>());
+
+        // the documents whose similarity doesn't reach the threshold are not candidates.
+        let mut search = Search::new(&rtxn, &index);
+        search.vector(vec![1.0, 0.0, 0.0]);
+        search.ranking_score_threshold(0.9);
+        let SearchResult { documents_ids, candidates, candidates_estimated, .. } =
+            search.execute().unwrap();
+        assert_eq!(documents_ids, vec![0, 1]);
+        assert_eq!(candidates, (0..2).collect::<RoaringBitmap>());
+        assert!(!candidates_estimated);
+
+        // the dot product takes the norm of the vectors into account.
+        let mut search = Search::new(&rtxn, &index);
+        search.vector(vec![0.0, -2.0, 0.0]);
+        search.vector_similarity(VectorSimilarity::Dot);
+        search.offset(1);
+        let SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![0, 2, 1]);
+
+        let mut search = Search::new(&rtxn, &index);
+        search.vector(vec![1.0, 0.0]);
+        let error = search.execute().unwrap_err();
+        assert_eq!(error.to_string(), "Invalid vector dimensions: expected `3` but found `2`.");
+        drop(rtxn);
+
+        // clearing the documents also clears their vectors.
+        let mut wtxn = index.write_txn().unwrap();
+        crate::update::ClearDocuments::new(&mut wtxn, &index).execute().unwrap();
+        assert!(index.vectors.is_empty(&wtxn).unwrap());
+        wtxn.commit().unwrap();
+    }
+
+    #[test]
+    fn invalid_vectors() {
+        let index = TempIndex::new();
+
+        // all the vectors must have the same dimensions.
+        let error = index
+            .add_documents(documents!([
+                { "id": 0, "_vectors": [1.0, 0.0] },
+                { "id": 1, "_vectors": [[1.0, 0.0], [1.0, 0.0, 0.0]] },
+            ]))
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid vector dimensions in the `_vectors` field of the document with the id: `1`: expected `2` but found `3`."
+        );
+
+        index.add_documents(documents!([{ "id": 0, "_vectors": [1.0, 0.0] }])).unwrap();
+        let error = index.add_documents(documents!([{ "id": 1, "_vectors": [1.0] }])).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid vector dimensions in the `_vectors` field of the document with the id: `1`: expected `2` but found `1`."
+        );
+
+        let error =
+            index.add_documents(documents!([{ "id": 0, "_vectors": [1.0, "two"] }])).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            r#"The `_vectors` field in the document with the id: `0` is not an array of numbers nor an array of arrays of numbers. Found `[1.0,"two"]`."#
+        );
+
+        // the vectors of all the documents can be replaced by vectors of other dimensions.
+        index
+            .add_documents(documents!([
+                { "id": 0, "_vectors": [1.0, 0.0, 0.0] },
+                { "id": 1, "_vectors": [0.0, 1.0, 0.0] },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let mut search = Search::new(&rtxn, &index);
+        search.vector(vec![0.0, 1.0, 0.0]);
+        let SearchResult { documents_ids, .. } = search.execute().unwrap();
+        let external_ids = index.external_documents_ids(&rtxn).unwrap();
+        let expected: Vec<_> = ["1", "0"].iter().map(|id| external_ids.get(id).unwrap()).collect();
+        assert_eq!(documents_ids, expected);
+
+        let mut search = Search::new(&rtxn, &index);
+        search.vector(vec![1.0, 0.0]);
+        let error = search.execute().unwrap_err();
+        assert_eq!(error.to_string(), "Invalid vector dimensions: expected `3` but found `2`.");
+    }
+}
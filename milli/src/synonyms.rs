@@ -0,0 +1,91 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+/// A group of synonyms as it is defined in the settings of an index.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub enum SynonymGroup {
+    /// The `input` is expanded into each of the `synonyms` at search time,
+    /// but the `synonyms` are not expanded into the `input`.
+    OneWay { input: String, synonyms: Vec<String> },
+    /// Each of the `synonyms` is expanded into all the other ones at search time.
+    Bidirectional { synonyms: Vec<String> },
+}
+
+/// A group of synonyms where every phrase has been normalized into its words,
+/// the `inputs` are the phrases that are expanded into the `synonyms`.
+pub(crate) struct NormalizedSynonymGroup {
+    pub inputs: Vec<Vec<String>>,
+    pub synonyms: Vec<Vec<String>>,
+}
+
+/// Builds the FST mapping every input phrase, its words joined by a space, to the id of the
+/// synonym set it expands into, along with the synonym sets themselves.
+///
+/// Every phrase that is an input of the same groups shares the same set, which means that
+/// a bidirectional group is only stored once and that a set can contain the phrase that
+/// is being expanded, it must be ignored when reading the set.
+pub(crate) fn build_synonyms_fst(
+    groups: &[NormalizedSynonymGroup],
+) -> fst::Result<(fst::Map<Vec<u8>>, Vec<Vec<Vec<String>>>)> {
+    let mut inputs_groups: BTreeMap<String, BTreeSet<usize>> = BTreeMap::new();
+    for (group_id, group) in groups.iter().enumerate() {
+        for input in group.inputs.iter().filter(|input| !input.is_empty()) {
+            inputs_groups.entry(input.join(" ")).or_default().insert(group_id);
+        }
+    }
+
+    let mut sets_ids = HashMap::new();
+    let mut sets = Vec::new();
+    let mut entries = Vec::with_capacity(inputs_groups.len());
+    for (input, groups_ids) in inputs_groups {
+        let set_id = *sets_ids.entry(groups_ids).or_insert_with_key(|groups_ids| {
+            let set: BTreeSet<_> = groups_ids
+                .iter()
+                .flat_map(|&group_id| groups[group_id].synonyms.iter())
+                .filter(|synonym| !synonym.is_empty())
+                .cloned()
+                .collect();
+            sets.push(set.into_iter().collect());
+            sets.len() as u64 - 1
+        });
+        entries.push((input, set_id));
+    }
+
+    let fst = fst::Map::from_iter(entries)?;
+    Ok((fst, sets))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn phrase(text: &str) -> Vec<String> {
+        text.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn shared_synonym_sets() {
+        let groups = vec![
+            NormalizedSynonymGroup {
+                inputs: vec![phrase("nyc"), phrase("new york")],
+                synonyms: vec![phrase("nyc"), phrase("new york")],
+            },
+            NormalizedSynonymGroup {
+                inputs: vec![phrase("nyc")],
+                synonyms: vec![phrase("big apple")],
+            },
+        ];
+
+        let (fst, sets) = build_synonyms_fst(&groups).unwrap();
+        assert_eq!(fst.len(), 2);
+
+        let new_york = fst.get("new york").unwrap() as usize;
+        assert_eq!(sets[new_york], vec![phrase("new york"), phrase("nyc")]);
+
+        let nyc = fst.get("nyc").unwrap() as usize;
+        assert_eq!(sets[nyc], vec![phrase("big apple"), phrase("new york"), phrase("nyc")]);
+
+        assert_eq!(fst.get("big apple"), None);
+    }
+}
@@ -35,6 +35,9 @@ impl<'t, 'u, 'i> ClearDocuments<'t, 'u, 'i> {
             field_id_docid_facet_f64s,
             field_id_docid_facet_strings,
             documents,
+            vectors,
+            synonym_sets: _,
+            custom_ranking_rules: _,
         } = self.index;
 
         let empty_roaring = RoaringBitmap::default();
@@ -57,6 +60,7 @@ impl<'t, 'u, 'i> ClearDocuments<'t, 'u, 'i> {
         for field_id in faceted_fields {
             self.index.put_number_faceted_documents_ids(self.wtxn, field_id, &empty_roaring)?;
             self.index.put_string_faceted_documents_ids(self.wtxn, field_id, &empty_roaring)?;
+            self.index.put_string_faceted_values_fst(self.wtxn, field_id, &fst::Set::default())?;
         }
 
         // Clear the other databases.
@@ -77,6 +81,7 @@ impl<'t, 'u, 'i> ClearDocuments<'t, 'u, 'i> {
         field_id_docid_facet_f64s.clear(self.wtxn)?;
         field_id_docid_facet_strings.clear(self.wtxn)?;
         documents.clear(self.wtxn)?;
+        vectors.clear(self.wtxn)?;
 
         Ok(number_of_documents)
     }
@@ -1,4 +1,5 @@
 use std::collections::btree_map::Entry;
+use std::collections::HashSet;
 
 use fst::IntoStreamer;
 use heed::types::{ByteSlice, Str};
@@ -8,15 +9,16 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use time::OffsetDateTime;
 
-use super::ClearDocuments;
+use super::{compute_facet_strings_fst, ClearDocuments};
 use crate::error::{InternalError, SerializationError, UserError};
 use crate::heed_codec::facet::{
-    FacetLevelValueU32Codec, FacetStringLevelZeroValueCodec, FacetStringZeroBoundsValueCodec,
+    FacetLevelValueU32Codec, FacetStringLevelZeroCodec, FacetStringLevelZeroValueCodec,
+    FacetStringZeroBoundsValueCodec,
 };
 use crate::heed_codec::CboRoaringBitmapCodec;
 use crate::index::{db_name, main_key};
 use crate::{
-    DocumentId, ExternalDocumentsIds, FieldId, FieldIdMapMissingEntry, Index, Result,
+    DocumentId, ExternalDocumentsIds, FieldId, FieldIdMapMissingEntry, Filter, Index, Result,
     RoaringBitmapCodec, SmallString32, BEU32,
 };
 
@@ -62,6 +64,14 @@ impl<'t, 'u, 'i> DeleteDocuments<'t, 'u, 'i> {
         Some(docid)
     }
 
+    /// Deletes all the documents matching the given filter, it is evaluated in the
+    /// write transaction. Returns the number of documents matching the filter.
+    pub fn delete_documents_by_filter(&mut self, filter: &Filter) -> Result<u64> {
+        let docids = filter.evaluate(self.wtxn, self.index)?;
+        self.delete_documents(&docids);
+        Ok(docids.len())
+    }
+
     pub fn execute(mut self) -> Result<DocumentDeletionResult> {
         self.index.set_updated_at(self.wtxn, &OffsetDateTime::now_utc())?;
         // We retrieve the current documents ids that are in the database.
@@ -192,6 +202,9 @@ impl<'t, 'u, 'i> DeleteDocuments<'t, 'u, 'i> {
             field_id_docid_facet_f64s,
             field_id_docid_facet_strings,
             documents,
+            vectors,
+            synonym_sets: _,
+            custom_ranking_rules: _,
         } = self.index;
 
         // Retrieve the words and the external documents ids contained in the documents.
@@ -219,6 +232,9 @@ impl<'t, 'u, 'i> DeleteDocuments<'t, 'u, 'i> {
             }
             drop(iter);
 
+            // We delete the vectors of the document, if any.
+            vectors.delete(self.wtxn, &key)?;
+
             // We iterate through the words positions of the document id,
             // retrieve the word and delete the positions.
             let mut iter = docid_word_positions.prefix_iter_mut(self.wtxn, &(docid, ""))?;
@@ -454,7 +470,7 @@ impl<'t, 'u, 'i> DeleteDocuments<'t, 'u, 'i> {
             &self.to_delete_docids,
         )?;
 
-        remove_docids_from_facet_field_id_string_docids(
+        let fields_with_removed_strings = remove_docids_from_facet_field_id_string_docids(
             self.wtxn,
             facet_id_string_docids,
             &self.to_delete_docids,
@@ -480,6 +496,12 @@ impl<'t, 'u, 'i> DeleteDocuments<'t, 'u, 'i> {
             docids -= &self.to_delete_docids;
             self.index.put_string_faceted_documents_ids(self.wtxn, field_id, &docids)?;
 
+            // Rebuild the FST of the string values when some of them lost their last document.
+            if fields_with_removed_strings.contains(&field_id) {
+                let fst = compute_facet_strings_fst(self.wtxn, *facet_id_string_docids, field_id)?;
+                self.index.put_string_faceted_values_fst(self.wtxn, field_id, &fst)?;
+            }
+
             remove_docids_from_field_id_docid_facet_value(
                 self.wtxn,
                 field_id_docid_facet_strings,
@@ -582,12 +604,14 @@ where
     Ok(())
 }
 
+/// Returns the ids of the fields of which some level zero string values were removed.
 fn remove_docids_from_facet_field_id_string_docids<'a, C, D>(
     wtxn: &'a mut heed::RwTxn,
     db: &heed::Database<C, D>,
     to_remove: &RoaringBitmap,
-) -> crate::Result<()> {
+) -> crate::Result<HashSet<FieldId>> {
     let db_name = Some(crate::index::db_name::FACET_ID_STRING_DOCIDS);
+    let mut fields_ids = HashSet::new();
     let mut iter = db.remap_types::<ByteSlice, ByteSlice>().iter_mut(wtxn)?;
     while let Some(result) = iter.next() {
         let (key, val) = result?;
@@ -624,6 +648,9 @@ fn remove_docids_from_facet_field_id_string_docids<'a, C, D>(
                 let previous_len = docids.len();
                 docids -= to_remove;
                 if docids.is_empty() {
+                    let (field_id, _) = FacetStringLevelZeroCodec::bytes_decode(key)
+                        .ok_or_else(|| SerializationError::Decoding { db_name })?;
+                    fields_ids.insert(field_id);
                     // safety: we don't keep references from inside the LMDB database.
                     unsafe { iter.del_current()? };
                 } else if docids.len() != previous_len {
@@ -639,7 +666,7 @@ fn remove_docids_from_facet_field_id_string_docids<'a, C, D>(
         }
     }
 
-    Ok(())
+    Ok(fields_ids)
 }
 
 fn remove_docids_from_facet_field_id_docids<'a, C>(
@@ -676,7 +703,6 @@ mod tests {
 
     use super::*;
     use crate::index::tests::TempIndex;
-    use crate::Filter;
 
     fn delete_documents<'t>(
         wtxn: &mut RwTxn<'t, '_>,
@@ -803,6 +829,49 @@ mod tests {
         wtxn.commit().unwrap();
     }
 
+    #[test]
+    fn delete_documents_by_filter() {
+        let index = TempIndex::new();
+
+        let mut wtxn = index.write_txn().unwrap();
+        index
+            .update_settings_using_wtxn(&mut wtxn, |settings| {
+                settings.set_primary_key(S("docid"));
+                settings.set_filterable_fields(hashset! { S("expires_at") });
+            })
+            .unwrap();
+
+        index
+            .add_documents_using_wtxn(
+                &mut wtxn,
+                documents!([
+                    { "docid": "1_4", "expires_at": 1 },
+                    { "docid": "1_5", "expires_at": 12 },
+                    { "docid": "1_7", "expires_at": 5 },
+                    { "docid": "1_36" },
+                ]),
+            )
+            .unwrap();
+
+        let filter = Filter::from_str("expires_at < 10").unwrap().unwrap();
+        let mut builder = DeleteDocuments::new(&mut wtxn, &index).unwrap();
+        assert_eq!(builder.delete_documents_by_filter(&filter).unwrap(), 2);
+        let result = builder.execute().unwrap();
+        assert_eq!(result.deleted_documents, 2);
+        assert_eq!(result.remaining_documents, 2);
+
+        // The filter no longer matches anything once the documents are deleted.
+        let mut builder = DeleteDocuments::new(&mut wtxn, &index).unwrap();
+        assert_eq!(builder.delete_documents_by_filter(&filter).unwrap(), 0);
+
+        let external_documents_ids = index.external_documents_ids(&wtxn).unwrap();
+        assert!(external_documents_ids.get("1_4").is_none());
+        assert!(external_documents_ids.get("1_7").is_none());
+        assert!(external_documents_ids.get("1_5").is_some());
+
+        wtxn.commit().unwrap();
+    }
+
     #[test]
     fn placeholder_search_should_not_return_deleted_documents() {
         let index = TempIndex::new();
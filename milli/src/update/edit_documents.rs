@@ -0,0 +1,201 @@
+use std::io::{Seek, SeekFrom};
+
+use roaring::RoaringBitmap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::documents::{DocumentsBatchBuilder, DocumentsBatchReader};
+use crate::error::{InternalError, UserError};
+use crate::index::db_name;
+use crate::update::index_documents::fetch_matching_values_in_object;
+use crate::update::{
+    DeleteDocuments, IndexDocuments, IndexDocumentsConfig, IndexDocumentsMethod, IndexerConfig,
+};
+use crate::{obkv_to_json, Filter, Index, Object, Result, BEU32};
+
+/// Selects the documents matching a filter and passes each of them to a closure
+/// that can modify or delete it, only the modified documents are reindexed.
+pub struct EditDocuments<'t, 'u, 'i, 'a> {
+    wtxn: &'t mut heed::RwTxn<'i, 'u>,
+    index: &'i Index,
+    indexer_config: &'a IndexerConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DocumentEditionResult {
+    pub edited_documents: u64,
+    pub deleted_documents: u64,
+    pub number_of_documents: u64,
+}
+
+impl<'t, 'u, 'i, 'a> EditDocuments<'t, 'u, 'i, 'a> {
+    pub fn new(
+        wtxn: &'t mut heed::RwTxn<'i, 'u>,
+        index: &'i Index,
+        indexer_config: &'a IndexerConfig,
+    ) -> EditDocuments<'t, 'u, 'i, 'a> {
+        EditDocuments { wtxn, index, indexer_config }
+    }
+
+    /// Calls the closure with every document matching the filter, the closure returns
+    /// the new version of the document or `None` if the document must be deleted.
+    ///
+    /// The primary key of a document can't be modified.
+    pub fn execute<E>(self, filter: &Filter, mut edit: E) -> Result<DocumentEditionResult>
+    where
+        E: FnMut(Object) -> Option<Object>,
+    {
+        let docids = filter.evaluate(self.wtxn, self.index)?;
+        let primary_key = self.index.primary_key(self.wtxn)?.map(String::from);
+        let fields_ids_map = self.index.fields_ids_map(self.wtxn)?;
+        let all_fields: Vec<_> = fields_ids_map.ids().collect();
+
+        // The edited documents are written in a temporary file to be reindexed.
+        let mut builder = DocumentsBatchBuilder::new(tempfile::tempfile()?);
+        let mut to_delete = RoaringBitmap::new();
+
+        for docid in docids {
+            let obkv = self.index.documents.get(self.wtxn, &BEU32::new(docid))?.ok_or(
+                InternalError::DatabaseMissingEntry { db_name: db_name::DOCUMENTS, key: None },
+            )?;
+            let document = obkv_to_json(&all_fields, &fields_ids_map, obkv)?;
+
+            match edit(document.clone()) {
+                Some(edited) if edited == document => (),
+                Some(edited) => {
+                    if let Some(primary_key) = &primary_key {
+                        let original_id = primary_key_values(document, primary_key);
+                        if original_id != primary_key_values(edited.clone(), primary_key) {
+                            let document_id = original_id.into_iter().next().unwrap_or_default();
+                            return Err(UserError::PrimaryKeyCannotBeEdited { document_id }.into());
+                        }
+                    }
+                    builder.append_json_object(&edited)?;
+                }
+                None => {
+                    to_delete.insert(docid);
+                }
+            }
+        }
+
+        let deleted_documents = if to_delete.is_empty() {
+            0
+        } else {
+            let mut deletion = DeleteDocuments::new(self.wtxn, self.index)?;
+            deletion.delete_documents(&to_delete);
+            deletion.execute()?.deleted_documents
+        };
+
+        let edited_documents = builder.documents_count() as u64;
+        if edited_documents > 0 {
+            let mut file = builder.into_inner()?;
+            file.seek(SeekFrom::Start(0))?;
+            let reader = DocumentsBatchReader::from_reader(file)?;
+
+            // The edited documents replace the previous versions through the usual pipeline.
+            let config = IndexDocumentsConfig {
+                update_method: IndexDocumentsMethod::ReplaceDocuments,
+                ..Default::default()
+            };
+            let indexing =
+                IndexDocuments::new(self.wtxn, self.index, self.indexer_config, config, |_| ())?;
+            let (indexing, user_result) = indexing.add_documents(reader)?;
+            user_result?;
+            indexing.execute()?;
+        }
+
+        let number_of_documents = self.index.number_of_documents(self.wtxn)?;
+        Ok(DocumentEditionResult { edited_documents, deleted_documents, number_of_documents })
+    }
+}
+
+/// Returns the values of the primary key in the document, it can be nested.
+fn primary_key_values(document: Object, primary_key: &str) -> Vec<Value> {
+    let mut values = Vec::new();
+    fetch_matching_values_in_object(document, primary_key, "", &mut values);
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use big_s::S;
+    use maplit::hashset;
+    use serde_json::json;
+
+    use super::*;
+    use crate::index::tests::TempIndex;
+
+    #[test]
+    fn edit_documents_matching_a_filter() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_primary_key(S("id"));
+                settings.set_filterable_fields(hashset! { S("kind") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 0, "kind": "book", "price": "12.5" },
+                { "id": 1, "kind": "book", "price": "8" },
+                { "id": 2, "kind": "book", "price": 3.5 },
+                { "id": 3, "kind": "draft", "price": "1" },
+                { "id": 4, "kind": "film", "price": "20" },
+            ]))
+            .unwrap();
+
+        // Normalize the price of the books into numbers and delete the drafts.
+        let mut wtxn = index.write_txn().unwrap();
+        let filter = Filter::from_str("kind = book OR kind = draft").unwrap().unwrap();
+        let result = EditDocuments::new(&mut wtxn, &index, &index.indexer_config)
+            .execute(&filter, |mut document| {
+                if document["kind"] == "draft" {
+                    return None;
+                }
+                if let Some(price) = document["price"].as_str() {
+                    document.insert(S("price"), json!(price.parse::<f64>().unwrap()));
+                }
+                Some(document)
+            })
+            .unwrap();
+        wtxn.commit().unwrap();
+
+        // The document 2 was not modified and is not reindexed.
+        assert_eq!(
+            result,
+            DocumentEditionResult {
+                edited_documents: 2,
+                deleted_documents: 1,
+                number_of_documents: 4
+            }
+        );
+
+        let rtxn = index.read_txn().unwrap();
+        let external_documents_ids = index.external_documents_ids(&rtxn).unwrap();
+        assert!(external_documents_ids.get("3").is_none());
+
+        let fields_ids_map = index.fields_ids_map(&rtxn).unwrap();
+        let all_fields: Vec<_> = fields_ids_map.ids().collect();
+        let docid = external_documents_ids.get("0").unwrap();
+        let (_, obkv) = index.documents(&rtxn, Some(docid)).unwrap()[0];
+        let document = obkv_to_json(&all_fields, &fields_ids_map, obkv).unwrap();
+        assert_eq!(document["price"], json!(12.5));
+        drop(rtxn);
+
+        // The primary key can't be modified.
+        let mut wtxn = index.write_txn().unwrap();
+        let filter = Filter::from_str("kind = film").unwrap().unwrap();
+        let error = EditDocuments::new(&mut wtxn, &index, &index.indexer_config)
+            .execute(&filter, |mut document| {
+                document.insert(S("id"), json!(5));
+                Some(document)
+            })
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "The primary key of the document with the id: `4` can't be modified by an edition."
+        );
+    }
+}
@@ -210,6 +210,9 @@ impl<'t, 'u, 'i> Facets<'t, 'u, 'i> {
                 field_id,
                 &string_documents_ids,
             )?;
+            let string_values_fst =
+                compute_facet_strings_fst(self.wtxn, self.index.facet_id_string_docids, field_id)?;
+            self.index.put_string_faceted_values_fst(self.wtxn, field_id, &string_values_fst)?;
             for facet_strings_level in facet_string_levels {
                 write_into_lmdb_database(
                     self.wtxn,
@@ -256,6 +259,26 @@ impl<'t, 'u, 'i> Facets<'t, 'u, 'i> {
     }
 }
 
+/// Builds the FST of the normalized string values of the given field id,
+/// the keys of the level 0 of the database are already ordered.
+pub(crate) fn compute_facet_strings_fst(
+    rtxn: &heed::RoTxn,
+    db: heed::Database<FacetStringLevelZeroCodec, FacetStringLevelZeroValueCodec>,
+    field_id: FieldId,
+) -> Result<fst::Set<Vec<u8>>> {
+    let mut prefix = field_id.to_be_bytes().to_vec();
+    prefix.push(0); // the level zero
+
+    let iter = db.remap_types::<ByteSlice, DecodeIgnore>().prefix_iter(rtxn, &prefix)?;
+    let mut builder = fst::SetBuilder::memory();
+    for result in iter {
+        let (key, _) = result?;
+        builder.insert(&key[prefix.len()..])?;
+    }
+
+    Ok(builder.into_set())
+}
+
 /// Compute the content of the database levels from its level 0 for the given field id.
 ///
 /// ## Returns:
@@ -710,4 +733,34 @@ mod tests {
         test("default", None, None);
         test("tiny_groups_tiny_levels", NonZeroUsize::new(1), NonZeroUsize::new(1));
     }
+
+    #[test]
+    fn test_facets_string_values_fst() {
+        let index = TempIndex::new();
+        index
+            .update_settings(|settings| {
+                settings
+                    .set_filterable_fields(IntoIterator::into_iter(["facet".to_owned()]).collect());
+            })
+            .unwrap();
+        index
+            .add_documents(documents!([
+                { "id": 0, "facet": "Blue" },
+                { "id": 1, "facet": "red" },
+                { "id": 2, "facet": "blue" },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let field_id = index.fields_ids_map(&rtxn).unwrap().id("facet").unwrap();
+        let fst = index.string_faceted_values_fst(&rtxn, field_id).unwrap().unwrap();
+        assert_eq!(fst.stream().into_strs().unwrap(), vec!["blue", "red"]);
+        drop(rtxn);
+
+        index.add_documents(documents!([{ "id": 3, "facet": "green" }])).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let fst = index.string_faceted_values_fst(&rtxn, field_id).unwrap().unwrap();
+        assert_eq!(fst.stream().into_strs().unwrap(), vec!["blue", "green", "red"]);
+    }
 }
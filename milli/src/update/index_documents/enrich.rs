@@ -7,7 +7,7 @@ use serde_json::Value;
 
 use crate::documents::{DocumentsBatchIndex, DocumentsBatchReader, EnrichedDocumentsBatchReader};
 use crate::error::{GeoError, InternalError, UserError};
-use crate::update::index_documents::{obkv_to_object, writer_into_reader};
+use crate::update::index_documents::{obkv_to_object, writer_into_reader, IndexDocumentsMethod};
 use crate::{FieldId, Index, Object, Result};
 
 /// The symbol used to define levels in a nested primary key.
@@ -20,11 +20,13 @@ const DEFAULT_PRIMARY_KEY: &str = "id";
 ///  - we can infer a primary key,
 ///  - all the documents id exist and are extracted,
 ///  - the validity of them but also,
-///  - the validity of the `_geo` field depending on the settings.
+///  - the validity of the `_geo` field depending on the settings, unless the documents
+///    are patches which are only valid once applied on the stored documents.
 pub fn enrich_documents_batch<R: Read + Seek>(
     rtxn: &heed::RoTxn,
     index: &Index,
     autogenerate_docids: bool,
+    update_method: IndexDocumentsMethod,
     reader: DocumentsBatchReader<R>,
 ) -> Result<StdResult<EnrichedDocumentsBatchReader<R>, UserError>> {
     let (mut cursor, mut documents_batch_index) = reader.into_cursor_and_fields_index();
@@ -72,6 +74,7 @@ pub fn enrich_documents_batch<R: Read + Seek>(
     // If the settings specifies that a _geo field must be used therefore we must check the
     // validity of it in all the documents of this batch and this is when we return `Some`.
     let geo_field_id = match documents_batch_index.id("_geo") {
+        Some(_) if update_method == IndexDocumentsMethod::PatchDocuments => None,
         Some(geo_field_id) if index.sortable_fields(rtxn)?.contains("_geo") => Some(geo_field_id),
         _otherwise => None,
     };
@@ -340,24 +343,40 @@ pub fn extract_finite_float_from_value(value: Value) -> StdResult<f64, Value> {
 }
 
 pub fn validate_geo_from_json(id: &DocumentId, bytes: &[u8]) -> Result<StdResult<(), GeoError>> {
-    use GeoError::*;
+    let value = serde_json::from_slice(bytes).map_err(InternalError::SerdeJson)?;
+    Ok(validate_geo_from_value(id, value))
+}
+
+/// Validates the `_geo` field of a document, a single point or an array of points.
+pub fn validate_geo_from_value(id: &DocumentId, value: Value) -> StdResult<(), GeoError> {
     let debug_id = || Value::from(id.debug());
-    match serde_json::from_slice(bytes).map_err(InternalError::SerdeJson)? {
+    match value {
+        Value::Array(points) => {
+            points.into_iter().try_for_each(|point| validate_geo_point(debug_id, point))
+        }
+        point => validate_geo_point(debug_id, point),
+    }
+}
+
+/// Validates a single `{ "lat": ..., "lng": ... }` point of the `_geo` field.
+fn validate_geo_point(debug_id: impl Fn() -> Value, value: Value) -> StdResult<(), GeoError> {
+    use GeoError::*;
+    match value {
         Value::Object(mut object) => match (object.remove("lat"), object.remove("lng")) {
             (Some(lat), Some(lng)) => {
                 match (extract_finite_float_from_value(lat), extract_finite_float_from_value(lng)) {
-                    (Ok(_), Ok(_)) => Ok(Ok(())),
-                    (Err(value), Ok(_)) => Ok(Err(BadLatitude { document_id: debug_id(), value })),
-                    (Ok(_), Err(value)) => Ok(Err(BadLongitude { document_id: debug_id(), value })),
+                    (Ok(_), Ok(_)) => Ok(()),
+                    (Err(value), Ok(_)) => Err(BadLatitude { document_id: debug_id(), value }),
+                    (Ok(_), Err(value)) => Err(BadLongitude { document_id: debug_id(), value }),
                     (Err(lat), Err(lng)) => {
-                        Ok(Err(BadLatitudeAndLongitude { document_id: debug_id(), lat, lng }))
+                        Err(BadLatitudeAndLongitude { document_id: debug_id(), lat, lng })
                     }
                 }
             }
-            (None, Some(_)) => Ok(Err(MissingLatitude { document_id: debug_id() })),
-            (Some(_), None) => Ok(Err(MissingLongitude { document_id: debug_id() })),
-            (None, None) => Ok(Err(MissingLatitudeAndLongitude { document_id: debug_id() })),
+            (None, Some(_)) => Err(MissingLatitude { document_id: debug_id() }),
+            (Some(_), None) => Err(MissingLongitude { document_id: debug_id() }),
+            (None, None) => Err(MissingLatitudeAndLongitude { document_id: debug_id() }),
         },
-        value => Ok(Err(NotAnObject { document_id: debug_id(), value })),
+        value => Err(NotAnObject { document_id: debug_id(), value }),
     }
 }
@@ -1,7 +1,6 @@
 use std::fs::File;
 use std::io;
 
-use concat_arrays::concat_arrays;
 use serde_json::Value;
 
 use super::helpers::{create_writer, writer_into_reader, GrenadParameters};
@@ -11,7 +10,8 @@ use crate::{FieldId, InternalError, Result};
 
 /// Extracts the geographical coordinates contained in each document under the `_geo` field.
 ///
-/// Returns the generated grenad reader containing the docid as key associated to the (latitude, longitude)
+/// Returns the generated grenad reader containing the docid as key associated to the
+/// (latitude, longitude) of each of its points, a document can have an array of points.
 #[logging_timer::time]
 pub fn extract_geo_points<R: io::Read + io::Seek>(
     obkv_documents: grenad::Reader<R>,
@@ -40,19 +40,39 @@ pub fn extract_geo_points<R: io::Read + io::Seek>(
         let lng = obkv.get(lng_fid);
 
         if let Some((lat, lng)) = lat.zip(lng) {
+            let lat = serde_json::from_slice(lat).map_err(InternalError::SerdeJson)?;
+            let lng = serde_json::from_slice(lng).map_err(InternalError::SerdeJson)?;
+
+            // when `_geo` is an array of points, the flattened latitudes and longitudes are
+            // arrays where the n-th latitude and the n-th longitude are those of the n-th point.
+            let (lats, lngs) = match (lat, lng) {
+                (Value::Array(lats), Value::Array(lngs)) => (lats, lngs),
+                (lat, lng) => (vec![lat], vec![lng]),
+            };
+            if lats.len() < lngs.len() {
+                return Err(GeoError::MissingLatitude { document_id: document_id() })?;
+            } else if lats.len() > lngs.len() {
+                return Err(GeoError::MissingLongitude { document_id: document_id() })?;
+            }
+
             // then we extract the values
-            let lat = extract_finite_float_from_value(
-                serde_json::from_slice(lat).map_err(InternalError::SerdeJson)?,
-            )
-            .map_err(|lat| GeoError::BadLatitude { document_id: document_id(), value: lat })?;
+            let mut bytes = Vec::with_capacity(lats.len() * 16);
+            for (lat, lng) in lats.into_iter().zip(lngs) {
+                let lat = extract_finite_float_from_value(lat).map_err(|lat| {
+                    GeoError::BadLatitude { document_id: document_id(), value: lat }
+                })?;
+
+                let lng = extract_finite_float_from_value(lng).map_err(|lng| {
+                    GeoError::BadLongitude { document_id: document_id(), value: lng }
+                })?;
 
-            let lng = extract_finite_float_from_value(
-                serde_json::from_slice(lng).map_err(InternalError::SerdeJson)?,
-            )
-            .map_err(|lng| GeoError::BadLongitude { document_id: document_id(), value: lng })?;
+                bytes.extend_from_slice(&lat.to_ne_bytes());
+                bytes.extend_from_slice(&lng.to_ne_bytes());
+            }
 
-            let bytes: [u8; 16] = concat_arrays![lat.to_ne_bytes(), lng.to_ne_bytes()];
-            writer.insert(docid_bytes, bytes)?;
+            if !bytes.is_empty() {
+                writer.insert(docid_bytes, bytes)?;
+            }
         } else if lat.is_none() && lng.is_some() {
             return Err(GeoError::MissingLatitude { document_id: document_id() })?;
         } else if lat.is_some() && lng.is_none() {
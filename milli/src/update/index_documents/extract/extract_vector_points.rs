@@ -0,0 +1,90 @@
+use std::fs::File;
+use std::io;
+
+use serde_json::Value;
+
+use super::helpers::{create_writer, writer_into_reader, GrenadParameters};
+use crate::error::UserError;
+use crate::{FieldId, InternalError, Result};
+
+/// Extracts the embedding vectors contained in each document under the `_vectors` field.
+///
+/// Returns the generated grenad reader containing the docid as key associated to the
+/// JSON serialized vectors, a document can have one vector or an array of vectors.
+///
+/// All the vectors must have the dimensions of the vectors of the documents that are kept
+/// in the index or, if there is none, the ones of the first vector of the chunk.
+#[logging_timer::time]
+pub fn extract_vector_points<R: io::Read + io::Seek>(
+    obkv_documents: grenad::Reader<R>,
+    indexer: GrenadParameters,
+    primary_key_id: FieldId,
+    vectors_fid: FieldId,
+    mut vector_dimensions: Option<usize>,
+) -> Result<grenad::Reader<File>> {
+    let mut writer = create_writer(
+        indexer.chunk_compression_type,
+        indexer.chunk_compression_level,
+        tempfile::tempfile()?,
+    );
+
+    let mut cursor = obkv_documents.into_cursor()?;
+    while let Some((docid_bytes, value)) = cursor.move_on_next()? {
+        let obkv = obkv::KvReader::new(value);
+
+        // we only need the primary key when we throw an error
+        // so we create this getter to lazily get it when needed
+        let document_id = || -> Value {
+            let document_id = obkv.get(primary_key_id).unwrap();
+            serde_json::from_slice(document_id).unwrap()
+        };
+
+        if let Some(value) = obkv.get(vectors_fid) {
+            let value = serde_json::from_slice(value).map_err(InternalError::SerdeJson)?;
+            let vectors = parse_vectors(&value).ok_or_else(|| UserError::InvalidVectorsType {
+                document_id: document_id(),
+                value: value.clone(),
+            })?;
+
+            for vector in &vectors {
+                match vector_dimensions {
+                    Some(expected) if expected != vector.len() => {
+                        return Err(UserError::InvalidVectorDimensions {
+                            document_id: Some(document_id()),
+                            expected,
+                            found: vector.len(),
+                        }
+                        .into())
+                    }
+                    Some(_) => (),
+                    None => vector_dimensions = Some(vector.len()),
+                }
+            }
+
+            if !vectors.is_empty() {
+                let bytes = serde_json::to_vec(&vectors).map_err(InternalError::SerdeJson)?;
+                writer.insert(docid_bytes, bytes)?;
+            }
+        }
+    }
+
+    writer_into_reader(writer)
+}
+
+/// Parses a single vector, an array of numbers, or many vectors, an array of arrays
+/// of numbers. A `null` value or an empty array means that there is no vector.
+pub fn parse_vectors(value: &Value) -> Option<Vec<Vec<f32>>> {
+    fn parse_vector(values: &[Value]) -> Option<Vec<f32>> {
+        values.iter().map(|value| value.as_f64().map(|number| number as f32)).collect()
+    }
+
+    match value {
+        Value::Null => Some(Vec::new()),
+        Value::Array(values) if values.iter().all(Value::is_array) => values
+            .iter()
+            .map(|value| value.as_array().and_then(|values| parse_vector(values)))
+            .collect(),
+        Value::Array(values) => parse_vector(values).map(|vector| vec![vector]),
+        _ => None,
+    }
+}
@@ -1,6 +1,7 @@
 mod enrich;
 mod extract;
 mod helpers;
+mod patch;
 mod transform;
 mod typed_chunk;
 
@@ -21,8 +22,8 @@ use typed_chunk::{write_typed_chunk_into_index, TypedChunk};
 
 use self::enrich::enrich_documents_batch;
 pub use self::enrich::{
-    extract_finite_float_from_value, validate_document_id, validate_document_id_value,
-    validate_geo_from_json, DocumentId,
+    extract_finite_float_from_value, fetch_matching_values_in_object, validate_document_id,
+    validate_document_id_value, validate_geo_from_json, validate_geo_from_value, DocumentId,
 };
 pub use self::helpers::{
     as_cloneable_grenad, create_sorter, create_writer, fst_stream_into_hashset,
@@ -63,6 +64,10 @@ pub enum IndexDocumentsMethod {
     /// Merge the previous version of the document with the new version,
     /// replacing old attributes values with the new ones and add the new attributes.
     UpdateDocuments,
+
+    /// Apply the attributes of the new version of the document as field-level operations
+    /// on the previous version, e.g. incrementing a number or appending to an array.
+    PatchDocuments,
 }
 
 impl Default for IndexDocumentsMethod {
@@ -145,18 +150,22 @@ where
             self.wtxn,
             self.index,
             self.config.autogenerate_docids,
+            self.config.update_method,
             reader,
         )? {
             Ok(reader) => reader,
             Err(user_error) => return Ok((self, Err(user_error))),
         };
 
-        let indexed_documents = self
+        let indexed_documents = match self
             .transform
             .as_mut()
             .expect("Invalid document addition state")
             .read_documents(enriched_documents_reader, self.wtxn, &self.progress)?
-            as u64;
+        {
+            Ok(indexed_documents) => indexed_documents as u64,
+            Err(user_error) => return Ok((self, Err(user_error))),
+        };
 
         self.added_documents += indexed_documents;
 
@@ -249,9 +258,22 @@ where
         // get the primary key field id
         let primary_key_id = fields_ids_map.id(&primary_key).unwrap();
 
-        // get searchable fields for word databases
+        // get the fid of the `_vectors` field and the dimensions of the vectors of the
+        // documents that are kept, the replaced documents are only deleted later on.
+        let vectors_field_id = fields_ids_map.id("_vectors");
+        let vector_dimensions = self.index.vector_dimensions(self.wtxn, &replaced_documents_ids)?;
+        // get searchable fields for word databases, the `_vectors` field is never
+        // searchable as its numbers would only fill the word databases with noise.
         let searchable_fields =
-            self.index.searchable_fields_ids(self.wtxn)?.map(HashSet::from_iter);
+            match (self.index.searchable_fields_ids(self.wtxn)?, vectors_field_id) {
+                (Some(fields), vectors) => {
+                    Some(fields.into_iter().filter(|&fid| Some(fid) != vectors).collect())
+                }
+                (None, Some(vectors)) => {
+                    Some(fields_ids_map.ids().filter(|&fid| fid != vectors).collect())
+                }
+                (None, None) => None,
+            };
         // get filterable fields for facet databases
         let faceted_fields = self.index.faceted_fields_ids(self.wtxn)?;
         // get the fid of the `_geo.lat` and `_geo.lng` fields.
@@ -310,6 +332,8 @@ where
                     faceted_fields,
                     primary_key_id,
                     geo_fields_ids,
+                    vectors_field_id,
+                    vector_dimensions,
                     stop_words,
                     max_positions_per_attributes,
                     exact_attributes,
@@ -653,6 +677,128 @@ mod tests {
         drop(rtxn);
     }
 
+    #[test]
+    fn simple_document_patch() {
+        let mut index = TempIndex::new();
+        index.index_documents_config.update_method = IndexDocumentsMethod::PatchDocuments;
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset! { S("stock") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 1, "name": "kevin", "stock": 2, "tags": ["a"], "meta": { "color": "red", "size": 3 } }
+            ]))
+            .unwrap();
+
+        // The operations are applied in order, on top of the previous patches of the same update.
+        index
+            .add_documents(documents!([
+                { "id": 1, "stock": { "$increment": 1 }, "tags": { "$append": "b" }, "meta": { "color": { "$delete": null } } },
+                { "id": 1, "stock": { "$increment": 2 } }
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.number_of_documents(&rtxn).unwrap(), 1);
+        let docid = index.external_documents_ids(&rtxn).unwrap().get("1").unwrap();
+        let (_, obkv) = index.documents(&rtxn, Some(docid)).unwrap()[0];
+        let fields_ids_map = index.fields_ids_map(&rtxn).unwrap();
+        let displayed: Vec<_> = fields_ids_map.ids().collect();
+        let document = crate::obkv_to_json(&displayed, &fields_ids_map, obkv).unwrap();
+        assert_eq!(
+            serde_json::Value::Object(document),
+            serde_json::json!({ "id": 1, "name": "kevin", "stock": 5, "tags": ["a", "b"], "meta": { "size": 3 } })
+        );
+
+        // The patched documents are reindexed.
+        let filter = crate::Filter::from_str("stock = 5").unwrap().unwrap();
+        let result = index.search(&rtxn).filter(filter).execute().unwrap();
+        assert_eq!(result.documents_ids, vec![docid]);
+        drop(rtxn);
+
+        // An operation that can't be applied on the stored value is rejected with the batch.
+        let mut wtxn = index.write_txn().unwrap();
+        let builder = IndexDocuments::new(
+            &mut wtxn,
+            &index,
+            &index.indexer_config,
+            index.index_documents_config.clone(),
+            |_| (),
+        )
+        .unwrap();
+        let (builder, user_result) =
+            builder.add_documents(documents!([{ "id": 1, "name": { "$increment": 1 } }])).unwrap();
+        assert_eq!(
+            user_result.unwrap_err().to_string(),
+            "Could not apply the `$increment` operation on the `name` attribute of the document with the id: `1`. The previous value `\"kevin\"` is not a number."
+        );
+
+        // The patches of a document already patched by the update are applied on top of
+        // the previous ones when the update is executed.
+        let (builder, user_result) =
+            builder.add_documents(documents!([{ "id": 1, "name": "kevina" }])).unwrap();
+        user_result.unwrap();
+        let (builder, user_result) =
+            builder.add_documents(documents!([{ "id": 1, "name": { "$increment": 1 } }])).unwrap();
+        user_result.unwrap();
+        let error = builder.execute().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Could not apply the `$increment` operation on the `name` attribute of the document with the id: `1`. The previous value `\"kevina\"` is not a number."
+        );
+    }
+
+    #[test]
+    fn document_patch_geo_and_vectors() {
+        let mut index = TempIndex::new();
+        index.index_documents_config.update_method = IndexDocumentsMethod::PatchDocuments;
+
+        index
+            .update_settings(|settings| {
+                settings.set_sortable_fields(hashset! { S("_geo") });
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+                { "id": 1, "_geo": { "lat": 12, "lng": 42 }, "_vectors": [0.5, 1.0] }
+            ]))
+            .unwrap();
+
+        // A patch of the `_geo` field is valid once merged into the stored point.
+        index.add_documents(documents!([{ "id": 1, "_geo": { "lat": 13 } }])).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let docid = index.external_documents_ids(&rtxn).unwrap().get("1").unwrap();
+        let (_, obkv) = index.documents(&rtxn, Some(docid)).unwrap()[0];
+        let fields_ids_map = index.fields_ids_map(&rtxn).unwrap();
+        let geo = fields_ids_map.id("_geo").unwrap();
+        let document = crate::obkv_to_json(&[geo], &fields_ids_map, obkv).unwrap();
+        assert_eq!(
+            serde_json::Value::Object(document),
+            serde_json::json!({ "_geo": { "lat": 13, "lng": 42 } })
+        );
+        drop(rtxn);
+
+        // The patched documents must have a valid `_geo` and `_vectors` fields.
+        let error = index
+            .add_documents(documents!([{ "id": 1, "_geo": { "lng": { "$delete": null } } }]))
+            .unwrap_err();
+        assert!(error.to_string().starts_with("Could not find longitude in the document"));
+
+        let error = index
+            .add_documents(documents!([{ "id": 1, "_vectors": { "$append": "red" } }]))
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "The `_vectors` field in the document with the id: `\"1\"` is not an array of numbers nor an array of arrays of numbers. Found `[0.5,1.0,\"red\"]`."
+        );
+    }
+
     #[test]
     fn simple_document_merge() {
         let mut index = TempIndex::new();
@@ -958,6 +1104,66 @@ mod tests {
         assert_eq!(documents_ids, vec![0, 1, 2, 3]);
     }
 
+    #[test]
+    fn index_geo_arrays() {
+        let index = TempIndex::new();
+
+        index
+            .update_settings(|settings| {
+                settings.set_filterable_fields(hashset!(S("_geo")));
+                settings.set_sortable_fields(hashset!(S("_geo")));
+            })
+            .unwrap();
+
+        index
+            .add_documents(documents!([
+              { "id": 0, "_geo": [{ "lat": 10, "lng": 10 }, { "lat": 31, "lng": 42 }] },
+              { "id": 1, "_geo": { "lat": 20, "lng": 20 } },
+              { "id": 2, "_geo": [{ "lat": "-10", "lng": -10 }, { "lat": 0, "lng": 0 }] },
+              { "id": 3, "_geo": [] },
+            ]))
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let rtree = index.geo_rtree(&rtxn).unwrap().unwrap();
+        assert_eq!(rtree.size(), 5);
+        assert_eq!(index.geo_faceted_documents_ids(&rtxn).unwrap(), RoaringBitmap::from_iter(0..3));
+
+        // any point of a document can match the filter.
+        let mut search = crate::Search::new(&rtxn, &index);
+        search.filter(crate::Filter::from_str("_geoRadius(31, 42, 1000)").unwrap().unwrap());
+        let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![0]);
+
+        // the documents are sorted by their nearest point.
+        let mut search = crate::Search::new(&rtxn, &index);
+        search.sort_criteria(vec![crate::AscDesc::Asc(crate::Member::Geo([31., 40.]))]);
+        let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert_eq!(documents_ids, vec![0, 1, 2, 3]);
+        drop(rtxn);
+
+        // the previous points of a replaced document don't match anymore.
+        index.add_documents(documents!([{ "id": 0, "_geo": { "lat": 10, "lng": 10 } }])).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let mut search = crate::Search::new(&rtxn, &index);
+        search.filter(crate::Filter::from_str("_geoRadius(31, 42, 1000)").unwrap().unwrap());
+        let crate::SearchResult { documents_ids, .. } = search.execute().unwrap();
+        assert!(documents_ids.is_empty());
+        drop(rtxn);
+
+        // every point of the array must be valid.
+        let error = index
+            .add_documents(documents!([
+              { "id": 0, "_geo": [{ "lat": 12, "lng": 42 }, [13, 43]] }
+            ]))
+            .unwrap_err();
+        assert_eq!(
+            &error.to_string(),
+            r#"The `_geo` field in the document with the id: `0` is not an object nor an array of objects. Was expecting objects with the `_geo.lat` and `_geo.lng` fields but instead got `[13,43]`."#
+        );
+    }
+
     #[test]
     fn geo_error() {
         let mut index = TempIndex::new();
@@ -1018,6 +1224,16 @@ mod tests {
             &error.to_string(),
             r#"Could not parse longitude in the document with the id: `0`. Was expecting a finite number but instead got `"hello"`."#
         );
+
+        let error = index
+            .add_documents(documents!([
+              { "id": 0, "_geo": [{ "lat": 12, "lng": 42 }, { "lat": 13, "lng": 43 }, { "lat": 14 }] }
+            ]))
+            .unwrap_err();
+        assert_eq!(
+            &error.to_string(),
+            r#"Could not find longitude in the document with the id: `0`. Was expecting a `_geo.lng` field."#
+        );
     }
 
     #[test]
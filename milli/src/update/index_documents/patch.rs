@@ -0,0 +1,354 @@
+use std::borrow::Cow;
+use std::convert::TryInto;
+use std::result::Result as StdResult;
+
+use obkv::{KvReaderU16, KvWriter};
+use serde_json::{Map, Number, Value};
+
+use crate::error::{InternalError, UserError};
+use crate::{DocumentId, FieldIdMapMissingEntry, FieldsIdsMap, Result};
+
+/// Adds the given number to the number of the previous version, or sets it.
+const INCREMENT_OPERATION: &str = "$increment";
+/// Pushes the given value, or values of an array, at the end of the previous array.
+const APPEND_OPERATION: &str = "$append";
+/// Removes the given value, or values of an array, from the previous array.
+const REMOVE_OPERATION: &str = "$remove";
+/// Removes the attribute from the document whatever the given value is.
+const DELETE_OPERATION: &str = "$delete";
+
+/// The reason why an operation can't be applied on the previous version of a value.
+#[derive(Debug)]
+pub struct PatchOperationError {
+    pub operation: &'static str,
+    pub reason: String,
+}
+
+/// The patches of a document, as stored in the sorter of the patches until they
+/// are applied on the version of the document in the database.
+///
+/// They are encoded as the original document id (`u32::MAX` for a new document), the
+/// length of the external id (`u16`) followed by the external id, then every patch
+/// prefixed by its length (`u32`), all the integers being big-endian.
+pub struct DocumentPatches<'a> {
+    pub original_docid: Option<DocumentId>,
+    pub external_id: &'a str,
+    patches: &'a [u8],
+}
+
+impl<'a> DocumentPatches<'a> {
+    /// Writes a single patch of a document into the output buffer.
+    pub fn encode(
+        original_docid: Option<DocumentId>,
+        external_id: &str,
+        patch: &[u8],
+        output: &mut Vec<u8>,
+    ) {
+        output.clear();
+        output.extend_from_slice(&original_docid.unwrap_or(u32::MAX).to_be_bytes());
+        output.extend_from_slice(&(external_id.len() as u16).to_be_bytes());
+        output.extend_from_slice(external_id.as_bytes());
+        output.extend_from_slice(&(patch.len() as u32).to_be_bytes());
+        output.extend_from_slice(patch);
+    }
+
+    pub fn decode(bytes: &'a [u8]) -> Result<DocumentPatches<'a>> {
+        let header_len = Self::header_len(bytes);
+        let original_docid = u32::from_be_bytes(bytes[..4].try_into().unwrap());
+        let external_id = std::str::from_utf8(&bytes[6..header_len])?;
+        Ok(DocumentPatches {
+            original_docid: Some(original_docid).filter(|docid| *docid != u32::MAX),
+            external_id,
+            patches: &bytes[header_len..],
+        })
+    }
+
+    /// Returns the patches in the order they must be applied.
+    pub fn patches(&self) -> impl Iterator<Item = &'a [u8]> {
+        let mut bytes = self.patches;
+        std::iter::from_fn(move || {
+            if bytes.len() < 4 {
+                return None;
+            }
+            let (len, rest) = bytes.split_at(4);
+            let len = u32::from_be_bytes(len.try_into().unwrap()) as usize;
+            let (patch, rest) = rest.split_at(len);
+            bytes = rest;
+            Some(patch)
+        })
+    }
+
+    fn header_len(bytes: &[u8]) -> usize {
+        6 + u16::from_be_bytes([bytes[4], bytes[5]]) as usize
+    }
+}
+
+/// Composes the successive patches of a document by concatenating them in order, the
+/// original document id and the external id are the ones of the first patch.
+pub fn merge_document_patches<'a>(_key: &[u8], values: &[Cow<'a, [u8]>]) -> Result<Cow<'a, [u8]>> {
+    if values.len() == 1 {
+        return Ok(values[0].clone());
+    }
+
+    let capacity = values.iter().map(|v| v.len()).sum::<usize>();
+    let mut output = Vec::with_capacity(capacity);
+    output.extend_from_slice(&values[0]);
+    for value in &values[1..] {
+        output.extend_from_slice(&value[DocumentPatches::header_len(value)..]);
+    }
+    Ok(Cow::Owned(output))
+}
+
+/// Applies the patch on the previous version of the document, both as obkvs, and writes
+/// the new version of the document into the output buffer.
+///
+/// Every attribute of the patch is applied on the same attribute of the previous version,
+/// see [`apply_value_patch`] for the different operations that can be applied.
+pub fn apply_document_patch(
+    document_id: &str,
+    base: Option<KvReaderU16>,
+    patch: KvReaderU16,
+    fields_ids_map: &FieldsIdsMap,
+    output: &mut Vec<u8>,
+) -> Result<()> {
+    use itertools::merge_join_by;
+    use itertools::EitherOrBoth::{Both, Left, Right};
+
+    output.clear();
+    let mut writer = KvWriter::new(output);
+
+    let base_iter = base.iter().flat_map(|base| base.iter());
+    for eob in merge_join_by(base_iter, patch.iter(), |(b, _), (p, _)| b.cmp(p)) {
+        let (field_id, base, patch) = match eob {
+            Left((field_id, base)) => {
+                writer.insert(field_id, base)?;
+                continue;
+            }
+            Right((field_id, patch)) => (field_id, None, patch),
+            Both((field_id, base), (_, patch)) => (field_id, Some(base), patch),
+        };
+
+        let base =
+            base.map(serde_json::from_slice).transpose().map_err(InternalError::SerdeJson)?;
+        let patch = serde_json::from_slice(patch).map_err(InternalError::SerdeJson)?;
+
+        match apply_value_patch(base, patch) {
+            Ok(Some(value)) => {
+                let value = serde_json::to_vec(&value).map_err(InternalError::SerdeJson)?;
+                writer.insert(field_id, value)?;
+            }
+            Ok(None) => (),
+            Err(PatchOperationError { operation, reason }) => {
+                let field =
+                    fields_ids_map.name(field_id).ok_or(FieldIdMapMissingEntry::FieldId {
+                        field_id,
+                        process: "apply document patch",
+                    })?;
+                return Err(UserError::InvalidDocumentPatch {
+                    document_id: document_id.to_string(),
+                    field: field.to_string(),
+                    operation,
+                    reason,
+                }
+                .into());
+            }
+        }
+    }
+
+    writer.finish()?;
+    Ok(())
+}
+
+/// Applies the patch on the previous version of a value, returns `None` if the value
+/// must be removed from the document.
+///
+/// The patch can be an object with a single operation key:
+///  - `{ "$increment": 2 }` adds a number to the previous number,
+///  - `{ "$append": ["red", "blue"] }` pushes values at the end of the previous array,
+///  - `{ "$remove": "red" }` removes values from the previous array,
+///  - `{ "$delete": null }` removes the value from the document.
+///
+/// Any other object is merged into the previous object, applying its values as patches
+/// on the values of the previous object, which means that a nested key can be deleted.
+/// The other values replace the previous value.
+pub fn apply_value_patch(
+    base: Option<Value>,
+    patch: Value,
+) -> StdResult<Option<Value>, PatchOperationError> {
+    let object = match patch {
+        Value::Object(object) => object,
+        patch => return Ok(Some(patch)),
+    };
+
+    let operation = match object.iter().next() {
+        Some((key, _)) if object.len() == 1 => match key.as_str() {
+            INCREMENT_OPERATION => Some(INCREMENT_OPERATION),
+            APPEND_OPERATION => Some(APPEND_OPERATION),
+            REMOVE_OPERATION => Some(REMOVE_OPERATION),
+            DELETE_OPERATION => Some(DELETE_OPERATION),
+            _otherwise => None,
+        },
+        _otherwise => None,
+    };
+
+    match operation {
+        Some(operation) => {
+            let argument = object.into_iter().next().map(|(_, value)| value).unwrap();
+            apply_operation(operation, base, argument)
+        }
+        None => {
+            let mut base = match base {
+                Some(Value::Object(base)) => base,
+                _otherwise => Map::new(),
+            };
+            for (key, patch) in object {
+                let previous = base.remove(&key);
+                if let Some(value) = apply_value_patch(previous, patch)? {
+                    base.insert(key, value);
+                }
+            }
+            Ok(Some(Value::Object(base)))
+        }
+    }
+}
+
+fn apply_operation(
+    operation: &'static str,
+    base: Option<Value>,
+    argument: Value,
+) -> StdResult<Option<Value>, PatchOperationError> {
+    let error = |reason: String| PatchOperationError { operation, reason };
+
+    match operation {
+        INCREMENT_OPERATION => {
+            let increment = match argument {
+                Value::Number(number) => number,
+                value => return Err(error(format!("Expected a number but found `{}`.", value))),
+            };
+            match base {
+                None | Some(Value::Null) => Ok(Some(Value::Number(increment))),
+                Some(Value::Number(number)) => {
+                    let sum = match (number.as_i64(), increment.as_i64()) {
+                        (Some(left), Some(right)) => left.checked_add(right).map(Number::from),
+                        _otherwise => None,
+                    };
+                    let sum = match sum {
+                        Some(sum) => sum,
+                        None => {
+                            let sum = number.as_f64().unwrap_or_default()
+                                + increment.as_f64().unwrap_or_default();
+                            Number::from_f64(sum).ok_or_else(|| {
+                                error(format!("The result `{}` is not a finite number.", sum))
+                            })?
+                        }
+                    };
+                    Ok(Some(Value::Number(sum)))
+                }
+                Some(value) => {
+                    Err(error(format!("The previous value `{}` is not a number.", value)))
+                }
+            }
+        }
+        APPEND_OPERATION => {
+            let values = match argument {
+                Value::Array(values) => values,
+                value => vec![value],
+            };
+            match base {
+                None | Some(Value::Null) => Ok(Some(Value::Array(values))),
+                Some(Value::Array(mut array)) => {
+                    array.extend(values);
+                    Ok(Some(Value::Array(array)))
+                }
+                Some(value) => {
+                    Err(error(format!("The previous value `{}` is not an array.", value)))
+                }
+            }
+        }
+        REMOVE_OPERATION => {
+            let values = match argument {
+                Value::Array(values) => values,
+                value => vec![value],
+            };
+            match base {
+                None => Ok(None),
+                Some(Value::Null) => Ok(Some(Value::Null)),
+                Some(Value::Array(mut array)) => {
+                    array.retain(|value| !values.contains(value));
+                    Ok(Some(Value::Array(array)))
+                }
+                Some(value) => {
+                    Err(error(format!("The previous value `{}` is not an array.", value)))
+                }
+            }
+        }
+        DELETE_OPERATION => Ok(None),
+        _otherwise => unreachable!("unknown patch operation `{}`", operation),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn patch(base: Value, patch: Value) -> Option<Value> {
+        apply_value_patch(Some(base), patch).unwrap()
+    }
+
+    #[test]
+    fn value_operations() {
+        assert_eq!(patch(json!(2), json!({ "$increment": 3 })), Some(json!(5)));
+        assert_eq!(patch(json!(2), json!({ "$increment": -0.5 })), Some(json!(1.5)));
+        assert_eq!(apply_value_patch(None, json!({ "$increment": 3 })).unwrap(), Some(json!(3)));
+
+        assert_eq!(patch(json!(["a"]), json!({ "$append": "b" })), Some(json!(["a", "b"])));
+        assert_eq!(
+            patch(json!(["a"]), json!({ "$append": ["b", "c"] })),
+            Some(json!(["a", "b", "c"]))
+        );
+        assert_eq!(patch(json!(["a", "b", "a"]), json!({ "$remove": "a" })), Some(json!(["b"])));
+        assert_eq!(patch(json!("a"), json!({ "$delete": true })), None);
+
+        // values that are not operations replace the previous value.
+        assert_eq!(patch(json!(["a"]), json!("b")), Some(json!("b")));
+        assert_eq!(patch(json!(2), json!({ "$other": 3 })), Some(json!({ "$other": 3 })));
+    }
+
+    #[test]
+    fn nested_operations() {
+        let base = json!({ "color": "red", "stock": 2, "size": { "width": 2, "height": 3 } });
+        let patched = patch(
+            base,
+            json!({ "color": { "$delete": null }, "stock": { "$increment": 1 }, "size": { "height": 4 } }),
+        );
+        assert_eq!(patched, Some(json!({ "stock": 3, "size": { "width": 2, "height": 4 } })));
+    }
+
+    #[test]
+    fn merge_encoded_patches() {
+        let mut first = Vec::new();
+        DocumentPatches::encode(Some(4), "kevin", b"first", &mut first);
+        let mut second = Vec::new();
+        DocumentPatches::encode(None, "kevin", b"second", &mut second);
+
+        let values = [Cow::Borrowed(&first[..]), Cow::Borrowed(&second[..])];
+        let merged = merge_document_patches(b"", &values).unwrap();
+        let patches = DocumentPatches::decode(&merged).unwrap();
+        assert_eq!(patches.original_docid, Some(4));
+        assert_eq!(patches.external_id, "kevin");
+        assert_eq!(patches.patches().collect::<Vec<_>>(), vec![&b"first"[..], &b"second"[..]]);
+    }
+
+    #[test]
+    fn invalid_operations() {
+        let error = apply_value_patch(Some(json!("a")), json!({ "$increment": 1 })).unwrap_err();
+        assert_eq!(error.operation, "$increment");
+        assert_eq!(error.reason, "The previous value `\"a\"` is not a number.");
+
+        let error = apply_value_patch(Some(json!(1)), json!({ "$append": 1 })).unwrap_err();
+        assert_eq!(error.operation, "$append");
+        assert_eq!(error.reason, "The previous value `1` is not an array.");
+    }
+}
@@ -1,8 +1,10 @@
 use std::borrow::Cow;
 use std::collections::hash_map::Entry;
 use std::collections::{HashMap, HashSet};
+use std::convert::TryInto;
 use std::fs::File;
 use std::io::{Read, Seek, SeekFrom};
+use std::result::Result as StdResult;
 
 use fxhash::FxHashMap;
 use heed::RoTxn;
@@ -12,15 +14,24 @@ use roaring::RoaringBitmap;
 use serde_json::Value;
 use smartstring::SmartString;
 
+use super::enrich::validate_geo_from_value;
+use super::extract::parse_vectors;
 use super::helpers::{create_sorter, create_writer, keep_latest_obkv, merge_obkvs, MergeFn};
+use super::patch::{
+    apply_document_patch, apply_value_patch, merge_document_patches, DocumentPatches,
+    PatchOperationError,
+};
 use super::{IndexDocumentsMethod, IndexerConfig};
-use crate::documents::{DocumentsBatchIndex, EnrichedDocument, EnrichedDocumentsBatchReader};
+use crate::documents::{
+    obkv_to_object, DocumentsBatchIndex, EnrichedDocument, EnrichedDocumentsBatchCursor,
+    EnrichedDocumentsBatchReader,
+};
 use crate::error::{Error, InternalError, UserError};
 use crate::index::db_name;
 use crate::update::{AvailableDocumentsIds, UpdateIndexingStep};
 use crate::{
-    ExternalDocumentsIds, FieldDistribution, FieldId, FieldIdMapMissingEntry, FieldsIdsMap, Index,
-    Result, BEU32,
+    obkv_to_json, DocumentId, ExternalDocumentsIds, FieldDistribution, FieldId,
+    FieldIdMapMissingEntry, FieldsIdsMap, Index, Object, Result, BEU32,
 };
 
 pub struct TransformOutput {
@@ -56,6 +67,9 @@ pub struct Transform<'a, 'i> {
     new_documents_ids: RoaringBitmap,
     // To increase the cache locality and decrease the heap usage we use compact smartstring.
     new_external_documents_ids_builder: FxHashMap<SmartString<smartstring::Compact>, u64>,
+    // The patches of the documents of this update, they are applied on the
+    // documents of the database once all the documents have been read.
+    patches_sorter: Option<grenad::Sorter<MergeFn>>,
     documents_count: usize,
 }
 
@@ -92,11 +106,20 @@ impl<'a, 'i> Transform<'a, 'i> {
     ) -> Result<Self> {
         // We must choose the appropriate merge function for when two or more documents
         // with the same user id must be merged or fully replaced in the same batch.
+        // The patched documents are complete documents, we only need to keep the latest one.
         let merge_function = match index_documents_method {
-            IndexDocumentsMethod::ReplaceDocuments => keep_latest_obkv,
+            IndexDocumentsMethod::ReplaceDocuments | IndexDocumentsMethod::PatchDocuments => {
+                keep_latest_obkv
+            }
             IndexDocumentsMethod::UpdateDocuments => merge_obkvs,
         };
 
+        // The memory is shared with the sorter of the patches when there is one.
+        let sorters_count = match index_documents_method {
+            IndexDocumentsMethod::PatchDocuments => 3,
+            _ => 2,
+        };
+
         // We initialize the sorter with the user indexing settings.
         let original_sorter = create_sorter(
             grenad::SortAlgorithm::Stable,
@@ -104,7 +127,7 @@ impl<'a, 'i> Transform<'a, 'i> {
             indexer_settings.chunk_compression_type,
             indexer_settings.chunk_compression_level,
             indexer_settings.max_nb_chunks,
-            indexer_settings.max_memory.map(|mem| mem / 2),
+            indexer_settings.max_memory.map(|mem| mem / sorters_count),
         );
 
         // We initialize the sorter with the user indexing settings.
@@ -114,8 +137,22 @@ impl<'a, 'i> Transform<'a, 'i> {
             indexer_settings.chunk_compression_type,
             indexer_settings.chunk_compression_level,
             indexer_settings.max_nb_chunks,
-            indexer_settings.max_memory.map(|mem| mem / 2),
+            indexer_settings.max_memory.map(|mem| mem / sorters_count),
         );
+
+        // The successive patches of a document are stored in order, they must
+        // be applied in this order on the document of the database.
+        let patches_sorter = match index_documents_method {
+            IndexDocumentsMethod::PatchDocuments => Some(create_sorter(
+                grenad::SortAlgorithm::Stable,
+                merge_document_patches,
+                indexer_settings.chunk_compression_type,
+                indexer_settings.chunk_compression_level,
+                indexer_settings.max_nb_chunks,
+                indexer_settings.max_memory.map(|mem| mem / sorters_count),
+            )),
+            _ => None,
+        };
         let documents_ids = index.documents_ids(wtxn)?;
         let soft_deleted_documents_ids = index.soft_deleted_documents_ids(wtxn)?;
 
@@ -134,6 +171,7 @@ impl<'a, 'i> Transform<'a, 'i> {
             replaced_documents_ids: RoaringBitmap::new(),
             new_documents_ids: RoaringBitmap::new(),
             new_external_documents_ids_builder: FxHashMap::default(),
+            patches_sorter,
             documents_count: 0,
         })
     }
@@ -143,7 +181,7 @@ impl<'a, 'i> Transform<'a, 'i> {
         reader: EnrichedDocumentsBatchReader<R>,
         wtxn: &mut heed::RwTxn,
         progress_callback: F,
-    ) -> Result<usize>
+    ) -> Result<StdResult<usize, UserError>>
     where
         R: Read + Seek,
         F: Fn(UpdateIndexingStep) + Sync,
@@ -152,6 +190,17 @@ impl<'a, 'i> Transform<'a, 'i> {
 
         let external_documents_ids = self.index.external_documents_ids(wtxn)?;
 
+        // The patches are checked before anything is inserted so that the
+        // documents of this batch can be rejected without altering the update.
+        if self.patches_sorter.is_some() {
+            let result =
+                self.check_patches(wtxn, &mut cursor, &fields_index, &external_documents_ids)?;
+            if let Err(user_error) = result {
+                return Ok(Err(user_error));
+            }
+            cursor.reset();
+        }
+
         let mapping = create_fields_mapping(&mut self.fields_ids_map, &fields_index)?;
 
         let primary_key = cursor.primary_key().to_string();
@@ -159,6 +208,7 @@ impl<'a, 'i> Transform<'a, 'i> {
             self.fields_ids_map.insert(&primary_key).ok_or(UserError::AttributeLimitReached)?;
 
         let mut obkv_buffer = Vec::new();
+        let mut patch_buffer = Vec::new();
         let mut documents_count = 0;
         let mut docid_buffer: Vec<u8> = Vec::new();
         let mut field_buffer: Vec<(u16, Cow<[u8]>)> = Vec::new();
@@ -220,48 +270,20 @@ impl<'a, 'i> Transform<'a, 'i> {
                 }
             };
 
-            let mut skip_insertion = false;
-            if let Some(original_docid) = original_docid {
-                let original_key = BEU32::new(original_docid);
-                let base_obkv = self
-                    .index
-                    .documents
-                    .remap_data_type::<heed::types::ByteSlice>()
-                    .get(wtxn, &original_key)?
-                    .ok_or(InternalError::DatabaseMissingEntry {
-                        db_name: db_name::DOCUMENTS,
-                        key: None,
-                    })?;
-
-                // we check if the two documents are exactly equal. If it's the case we can skip this document entirely
-                if base_obkv == obkv_buffer {
-                    // we're not replacing anything
-                    self.replaced_documents_ids.remove(original_docid);
-                    // and we need to put back the original id as it was before
-                    self.new_external_documents_ids_builder.remove(external_id);
-                    skip_insertion = true;
-                } else {
-                    // we associate the base document with the new key, everything will get merged later.
-                    self.original_sorter.insert(&docid.to_be_bytes(), base_obkv)?;
-                    match self.flatten_from_fields_ids_map(KvReader::new(base_obkv))? {
-                        Some(buffer) => {
-                            self.flattened_sorter.insert(docid.to_be_bytes(), &buffer)?
-                        }
-                        None => self.flattened_sorter.insert(docid.to_be_bytes(), base_obkv)?,
-                    }
+            // The patches are applied once all the documents have been read, on top
+            // of the previous patches of the same document and of its stored version.
+            match self.patches_sorter.as_mut() {
+                Some(patches_sorter) => {
+                    DocumentPatches::encode(
+                        original_docid,
+                        external_id,
+                        &obkv_buffer,
+                        &mut patch_buffer,
+                    );
+                    patches_sorter.insert(&docid.to_be_bytes(), &patch_buffer)?;
                 }
-            }
-
-            if !skip_insertion {
-                self.new_documents_ids.insert(docid);
-                // We use the extracted/generated user id as the key for this document.
-                self.original_sorter.insert(&docid.to_be_bytes(), obkv_buffer.clone())?;
-
-                match self.flatten_from_fields_ids_map(KvReader::new(&obkv_buffer))? {
-                    Some(buffer) => self.flattened_sorter.insert(docid.to_be_bytes(), &buffer)?,
-                    None => {
-                        self.flattened_sorter.insert(docid.to_be_bytes(), obkv_buffer.clone())?
-                    }
+                None => {
+                    self.insert_document(wtxn, docid, original_docid, external_id, &obkv_buffer)?
                 }
             }
             documents_count += 1;
@@ -284,7 +306,193 @@ impl<'a, 'i> Transform<'a, 'i> {
         self.documents_count += documents_count;
         // Now that we have a valid sorter that contains the user id and the obkv we
         // give it to the last transforming function which returns the TransformOutput.
-        Ok(documents_count)
+        Ok(Ok(documents_count))
+    }
+
+    /// Applies the patches of the batch on the stored version of their document and checks
+    /// that the operations are valid and that the patched documents have a valid `_geo` and
+    /// `_vectors` fields.
+    ///
+    /// The documents already patched by this update are skipped, their patches are applied
+    /// on top of the previous ones when the update is executed, where they can still fail.
+    fn check_patches<R: Read + Seek>(
+        &self,
+        rtxn: &RoTxn,
+        cursor: &mut EnrichedDocumentsBatchCursor<R>,
+        fields_index: &DocumentsBatchIndex,
+        external_documents_ids: &ExternalDocumentsIds,
+    ) -> Result<StdResult<(), UserError>> {
+        let documents = self.index.documents.remap_data_type::<heed::types::ByteSlice>();
+        let validate_geo = self.index.sortable_fields(rtxn)?.contains("_geo");
+        let displayed: Vec<_> = self.fields_ids_map.ids().collect();
+
+        let mut patched_ids = HashSet::new();
+        while let Some(EnrichedDocument { document, document_id }) =
+            cursor.next_enriched_document()?
+        {
+            let external_id = document_id.value();
+            if self.new_external_documents_ids_builder.contains_key(external_id)
+                || !patched_ids.insert(external_id.to_string())
+            {
+                continue;
+            }
+
+            let mut patched = match external_documents_ids.get(external_id) {
+                Some(docid) => {
+                    let base = documents.get(rtxn, &BEU32::new(docid))?.ok_or(
+                        InternalError::DatabaseMissingEntry {
+                            db_name: db_name::DOCUMENTS,
+                            key: None,
+                        },
+                    )?;
+                    obkv_to_json(&displayed, &self.fields_ids_map, KvReader::new(base))?
+                }
+                None => Object::new(),
+            };
+
+            for (field, patch) in obkv_to_object(&document, fields_index)? {
+                let base = patched.remove(&field);
+                match apply_value_patch(base, patch) {
+                    Ok(Some(value)) => {
+                        patched.insert(field, value);
+                    }
+                    Ok(None) => (),
+                    Err(PatchOperationError { operation, reason }) => {
+                        return Ok(Err(UserError::InvalidDocumentPatch {
+                            document_id: external_id.to_string(),
+                            field,
+                            operation,
+                            reason,
+                        }));
+                    }
+                }
+            }
+
+            if let Some(geo) = patched.remove("_geo").filter(|_| validate_geo) {
+                if let Err(geo_error) = validate_geo_from_value(&document_id, geo) {
+                    return Ok(Err(UserError::from(geo_error)));
+                }
+            }
+
+            if let Some(value) = patched.remove("_vectors") {
+                if parse_vectors(&value).is_none() {
+                    let document_id = Value::from(external_id);
+                    return Ok(Err(UserError::InvalidVectorsType { document_id, value }));
+                }
+            }
+        }
+
+        Ok(Ok(()))
+    }
+
+    /// Inserts a new version of a document in the sorters, along with the version of the
+    /// database it replaces if any. The document is skipped if both versions are equal.
+    fn insert_document(
+        &mut self,
+        rtxn: &RoTxn,
+        docid: u32,
+        original_docid: Option<u32>,
+        external_id: &str,
+        obkv: &[u8],
+    ) -> Result<()> {
+        if let Some(original_docid) = original_docid {
+            let original_key = BEU32::new(original_docid);
+            let base_obkv = self
+                .index
+                .documents
+                .remap_data_type::<heed::types::ByteSlice>()
+                .get(rtxn, &original_key)?
+                .ok_or(InternalError::DatabaseMissingEntry {
+                    db_name: db_name::DOCUMENTS,
+                    key: None,
+                })?;
+
+            // we check if the two documents are exactly equal. If it's the case we can skip this document entirely
+            if base_obkv == obkv {
+                // we're not replacing anything
+                self.replaced_documents_ids.remove(original_docid);
+                // and we need to put back the original id as it was before
+                self.new_external_documents_ids_builder.remove(external_id);
+                return Ok(());
+            }
+
+            // we associate the base document with the new key, everything will get merged later.
+            self.original_sorter.insert(&docid.to_be_bytes(), base_obkv)?;
+            match self.flatten_from_fields_ids_map(KvReader::new(base_obkv))? {
+                Some(buffer) => self.flattened_sorter.insert(docid.to_be_bytes(), &buffer)?,
+                None => self.flattened_sorter.insert(docid.to_be_bytes(), base_obkv)?,
+            }
+        }
+
+        self.new_documents_ids.insert(docid);
+        // We use the extracted/generated user id as the key for this document.
+        self.original_sorter.insert(&docid.to_be_bytes(), obkv)?;
+
+        match self.flatten_from_fields_ids_map(KvReader::new(obkv))? {
+            Some(buffer) => self.flattened_sorter.insert(docid.to_be_bytes(), &buffer)?,
+            None => self.flattened_sorter.insert(docid.to_be_bytes(), obkv)?,
+        }
+
+        Ok(())
+    }
+
+    /// Applies the patches of this update, in order, on the stored version of the documents
+    /// and inserts the patched documents in the sorters like any other new version.
+    fn apply_patches(&mut self, rtxn: &RoTxn) -> Result<()> {
+        let patches_sorter = match self.patches_sorter.take() {
+            Some(patches_sorter) => patches_sorter,
+            None => return Ok(()),
+        };
+
+        let documents = self.index.documents.remap_data_type::<heed::types::ByteSlice>();
+        let mut obkv_buffer = Vec::new();
+        let mut patch_buffer = Vec::new();
+
+        let mut iter = patches_sorter.into_stream_merger_iter()?;
+        while let Some((key, value)) = iter.next()? {
+            let docid = key.try_into().map(DocumentId::from_be_bytes).unwrap();
+            let patches = DocumentPatches::decode(value)?;
+
+            let base = match patches.original_docid {
+                Some(original_docid) => {
+                    let base = documents.get(rtxn, &BEU32::new(original_docid))?.ok_or(
+                        InternalError::DatabaseMissingEntry {
+                            db_name: db_name::DOCUMENTS,
+                            key: None,
+                        },
+                    )?;
+                    Some(base)
+                }
+                None => None,
+            };
+
+            let mut patched = false;
+            for patch in patches.patches() {
+                let base = match patched {
+                    true => Some(KvReader::new(&obkv_buffer[..])),
+                    false => base.map(KvReader::new),
+                };
+                apply_document_patch(
+                    patches.external_id,
+                    base,
+                    KvReader::new(patch),
+                    &self.fields_ids_map,
+                    &mut patch_buffer,
+                )?;
+                std::mem::swap(&mut obkv_buffer, &mut patch_buffer);
+                patched = true;
+            }
+
+            self.insert_document(
+                rtxn,
+                docid,
+                patches.original_docid,
+                patches.external_id,
+                &obkv_buffer,
+            )?;
+        }
+
+        Ok(())
     }
 
     // Flatten a document from the fields ids map contained in self and insert the new
@@ -443,13 +651,15 @@ impl<'a, 'i> Transform<'a, 'i> {
     /// format like CSV, JSON or JSON stream. This sorter must contain a key that is the document
     /// id for the user side and the value must be an obkv where keys are valid fields ids.
     pub(crate) fn output_from_sorter<F>(
-        self,
+        mut self,
         wtxn: &mut heed::RwTxn,
         progress_callback: F,
     ) -> Result<TransformOutput>
     where
         F: Fn(UpdateIndexingStep) + Sync,
     {
+        self.apply_patches(wtxn)?;
+
         let primary_key = self
             .index
             .primary_key(wtxn)?
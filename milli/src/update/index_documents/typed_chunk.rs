@@ -17,7 +17,7 @@ use crate::heed_codec::facet::{decode_prefix_string, encode_prefix_string};
 use crate::update::index_documents::helpers::as_cloneable_grenad;
 use crate::{
     lat_lng_to_xyz, BoRoaringBitmapCodec, CboRoaringBitmapCodec, DocumentId, GeoPoint, Index,
-    Result,
+    InternalError, Result, UserError, BEU32,
 };
 
 pub(crate) enum TypedChunk {
@@ -37,6 +37,7 @@ pub(crate) enum TypedChunk {
     FieldIdFacetNumberDocids(grenad::Reader<File>),
     FieldIdFacetExistsDocids(grenad::Reader<File>),
     GeoPoints(grenad::Reader<File>),
+    VectorPoints(grenad::Reader<File>),
 }
 
 /// Write typed chunk in the corresponding LMDB database of the provided index.
@@ -217,18 +218,48 @@ pub(crate) fn write_typed_chunk_into_index(
                 // convert the key back to a u32 (4 bytes)
                 let docid = key.try_into().map(DocumentId::from_be_bytes).unwrap();
 
-                // convert the latitude and longitude back to a f64 (8 bytes)
-                let (lat, tail) = helpers::try_split_array_at::<u8, 8>(value).unwrap();
-                let (lng, _) = helpers::try_split_array_at::<u8, 8>(tail).unwrap();
-                let point = [f64::from_ne_bytes(lat), f64::from_ne_bytes(lng)];
-                let xyz_point = lat_lng_to_xyz(&point);
+                // a document can have many points, each one is a latitude
+                // and a longitude that we convert back to a f64 (8 bytes)
+                for point in value.chunks_exact(16) {
+                    let (lat, tail) = helpers::try_split_array_at::<u8, 8>(point).unwrap();
+                    let (lng, _) = helpers::try_split_array_at::<u8, 8>(tail).unwrap();
+                    let point = [f64::from_ne_bytes(lat), f64::from_ne_bytes(lng)];
+                    let xyz_point = lat_lng_to_xyz(&point);
 
-                rtree.insert(GeoPoint::new(xyz_point, (docid, point)));
+                    rtree.insert(GeoPoint::new(xyz_point, (docid, point)));
+                }
                 geo_faceted_docids.insert(docid);
             }
             index.put_geo_rtree(wtxn, &rtree)?;
             index.put_geo_faceted_documents_ids(wtxn, &geo_faceted_docids)?;
         }
+        TypedChunk::VectorPoints(vector_points) => {
+            // the chunks extracted in parallel must agree on the dimensions of the vectors.
+            // the replaced documents are already deleted at this point.
+            let mut dimensions = index.vector_dimensions(wtxn, &RoaringBitmap::new())?;
+            let mut cursor = vector_points.into_cursor()?;
+            while let Some((key, value)) = cursor.move_on_next()? {
+                // convert the key back to a u32 (4 bytes)
+                let docid = key.try_into().map(DocumentId::from_be_bytes).unwrap();
+
+                let vectors: Vec<Vec<f32>> =
+                    serde_json::from_slice(value).map_err(InternalError::SerdeJson)?;
+                for vector in &vectors {
+                    match dimensions {
+                        Some(expected) if expected != vector.len() => {
+                            let found = vector.len();
+                            let document_id = None;
+                            let error =
+                                UserError::InvalidVectorDimensions { document_id, expected, found };
+                            return Err(error.into());
+                        }
+                        Some(_) => (),
+                        None => dimensions = Some(vector.len()),
+                    }
+                }
+                index.vectors.put(wtxn, &BEU32::new(docid), &vectors)?;
+            }
+        }
     }
 
     Ok((RoaringBitmap::new(), is_merged_database))
@@ -1,7 +1,12 @@
 pub use self::available_documents_ids::AvailableDocumentsIds;
 pub use self::clear_documents::ClearDocuments;
 pub use self::delete_documents::{DeleteDocuments, DocumentDeletionResult};
+pub use self::edit_documents::{DocumentEditionResult, EditDocuments};
+pub(crate) use self::facets::compute_facet_strings_fst;
 pub use self::facets::Facets;
+pub(crate) use self::index_documents::{
+    fetch_matching_values_in_object, validate_document_id_value, validate_geo_from_value,
+};
 pub use self::index_documents::{
     DocumentAdditionResult, DocumentId, IndexDocuments, IndexDocumentsConfig, IndexDocumentsMethod,
 };
@@ -16,6 +21,7 @@ pub use self::words_prefixes_fst::WordsPrefixesFst;
 mod available_documents_ids;
 mod clear_documents;
 mod delete_documents;
+mod edit_documents;
 mod facets;
 mod index_documents;
 mod indexer_config;
@@ -1,4 +1,4 @@
-use std::collections::{BTreeSet, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::result::Result as StdResult;
 
 use charabia::{Tokenizer, TokenizerBuilder};
@@ -8,12 +8,13 @@ use time::OffsetDateTime;
 
 use super::index_documents::{IndexDocumentsConfig, Transform};
 use super::IndexerConfig;
-use crate::criterion::Criterion;
+use crate::criterion::{Criterion, CriterionError};
 use crate::error::UserError;
 use crate::index::{DEFAULT_MIN_WORD_LEN_ONE_TYPO, DEFAULT_MIN_WORD_LEN_TWO_TYPOS};
+use crate::synonyms::{build_synonyms_fst, NormalizedSynonymGroup};
 use crate::update::index_documents::IndexDocumentsMethod;
 use crate::update::{ClearDocuments, IndexDocuments, UpdateIndexingStep};
-use crate::{FieldsIdsMap, Index, Result};
+use crate::{FieldsIdsMap, Index, Result, SynonymGroup};
 
 #[derive(Debug, Clone, PartialEq, Eq, Copy)]
 pub enum Setting<T> {
@@ -90,13 +91,14 @@ pub struct Settings<'a, 't, 'u, 'i> {
     indexer_config: &'a IndexerConfig,
 
     searchable_fields: Setting<Vec<String>>,
+    attribute_weights: Setting<BTreeMap<String, u16>>,
     displayed_fields: Setting<Vec<String>>,
     filterable_fields: Setting<HashSet<String>>,
     sortable_fields: Setting<HashSet<String>>,
     criteria: Setting<Vec<String>>,
     stop_words: Setting<BTreeSet<String>>,
     distinct_field: Setting<String>,
-    synonyms: Setting<HashMap<String, Vec<String>>>,
+    synonyms: Setting<Vec<SynonymGroup>>,
     primary_key: Setting<String>,
     authorize_typos: Setting<bool>,
     min_word_len_two_typos: Setting<u8>,
@@ -118,6 +120,7 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
             wtxn,
             index,
             searchable_fields: Setting::NotSet,
+            attribute_weights: Setting::NotSet,
             displayed_fields: Setting::NotSet,
             filterable_fields: Setting::NotSet,
             sortable_fields: Setting::NotSet,
@@ -198,8 +201,19 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
         self.synonyms = Setting::Reset;
     }
 
+    /// Defines one-way synonyms, each word is expanded into its synonyms at search time.
     pub fn set_synonyms(&mut self, synonyms: HashMap<String, Vec<String>>) {
-        self.synonyms = if synonyms.is_empty() { Setting::Reset } else { Setting::Set(synonyms) }
+        let mut synonyms: Vec<_> = synonyms.into_iter().collect();
+        synonyms.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+        let groups = synonyms
+            .into_iter()
+            .map(|(input, synonyms)| SynonymGroup::OneWay { input, synonyms })
+            .collect();
+        self.set_synonym_groups(groups);
+    }
+
+    pub fn set_synonym_groups(&mut self, groups: Vec<SynonymGroup>) {
+        self.synonyms = if groups.is_empty() { Setting::Reset } else { Setting::Set(groups) }
     }
 
     pub fn reset_primary_key(&mut self) {
@@ -250,6 +264,14 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
         self.exact_attributes = Setting::Reset;
     }
 
+    pub fn set_attribute_weights(&mut self, weights: BTreeMap<String, u16>) {
+        self.attribute_weights = Setting::Set(weights);
+    }
+
+    pub fn reset_attribute_weights(&mut self) {
+        self.attribute_weights = Setting::Reset;
+    }
+
     pub fn set_max_values_per_facet(&mut self, value: usize) {
         self.max_values_per_facet = Setting::Set(value);
     }
@@ -401,7 +423,7 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
 
     fn update_synonyms(&mut self) -> Result<bool> {
         match self.synonyms {
-            Setting::Set(ref synonyms) => {
+            Setting::Set(ref groups) => {
                 fn normalize(tokenizer: &Tokenizer<&[u8]>, text: &str) -> Vec<String> {
                     tokenizer
                         .tokenize(text)
@@ -422,33 +444,41 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
                 }
                 let tokenizer = builder.build();
 
-                let mut new_synonyms = HashMap::new();
-                for (word, synonyms) in synonyms {
-                    // Normalize both the word and associated synonyms.
-                    let normalized_word = normalize(&tokenizer, word);
-                    let normalized_synonyms =
-                        synonyms.iter().map(|synonym| normalize(&tokenizer, synonym));
-
-                    // Store the normalized synonyms under the normalized word,
-                    // merging the possible duplicate words.
-                    let entry = new_synonyms.entry(normalized_word).or_insert_with(Vec::new);
-                    entry.extend(normalized_synonyms);
+                // Normalize both the inputs and the synonyms of the groups.
+                let normalized_groups: Vec<_> = groups
+                    .iter()
+                    .map(|group| match group {
+                        SynonymGroup::OneWay { input, synonyms } => NormalizedSynonymGroup {
+                            inputs: vec![normalize(&tokenizer, input)],
+                            synonyms: synonyms.iter().map(|s| normalize(&tokenizer, s)).collect(),
+                        },
+                        SynonymGroup::Bidirectional { synonyms } => {
+                            let synonyms: Vec<_> =
+                                synonyms.iter().map(|s| normalize(&tokenizer, s)).collect();
+                            NormalizedSynonymGroup { inputs: synonyms.clone(), synonyms }
+                        }
+                    })
+                    .collect();
+
+                let (fst, sets) = build_synonyms_fst(&normalized_groups)?;
+
+                // The normalized synonyms are compared as they depend on the stop words.
+                let old_groups = self.index.synonym_groups(self.wtxn)?;
+                let old_fst = self.index.synonyms_fst(self.wtxn)?.as_fst().as_bytes().to_vec();
+                if *groups == old_groups && fst.as_fst().as_bytes() == old_fst {
+                    let old_sets = self
+                        .index
+                        .synonym_sets
+                        .iter(self.wtxn)?
+                        .map(|result| result.map(|(_id, set)| set))
+                        .collect::<heed::Result<Vec<_>>>()?;
+                    if sets == old_sets {
+                        return Ok(false);
+                    }
                 }
 
-                // Make sure that we don't have duplicate synonyms.
-                new_synonyms.iter_mut().for_each(|(_, synonyms)| {
-                    synonyms.sort_unstable();
-                    synonyms.dedup();
-                });
-
-                let old_synonyms = self.index.synonyms(self.wtxn)?;
-
-                if new_synonyms != old_synonyms {
-                    self.index.put_synonyms(self.wtxn, &new_synonyms)?;
-                    Ok(true)
-                } else {
-                    Ok(false)
-                }
+                self.index.put_synonyms(self.wtxn, groups, &fst, &sets)?;
+                Ok(true)
             }
             Setting::Reset => Ok(self.index.delete_synonyms(self.wtxn)?),
             Setting::NotSet => Ok(false),
@@ -509,7 +539,21 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
             Setting::Set(ref fields) => {
                 let mut new_criteria = Vec::new();
                 for name in fields {
-                    let criterion: Criterion = name.parse()?;
+                    // the custom ranking rules can be written with or without their prefix.
+                    let criterion = match name.parse() {
+                        Ok(Criterion::Custom(rule))
+                            if self.index.custom_ranking_rule(&rule).is_none() =>
+                        {
+                            return Err(CriterionError::InvalidName { name: name.clone() }.into())
+                        }
+                        Ok(criterion) => criterion,
+                        Err(CriterionError::InvalidName { .. })
+                            if self.index.custom_ranking_rule(name).is_some() =>
+                        {
+                            Criterion::Custom(name.clone())
+                        }
+                        Err(error) => return Err(error.into()),
+                    };
                     new_criteria.push(criterion);
                 }
                 self.index.put_criteria(self.wtxn, &new_criteria)?;
@@ -629,6 +673,56 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
         Ok(())
     }
 
+    fn update_attribute_weights(&mut self) -> Result<()> {
+        match self.attribute_weights {
+            Setting::Set(ref weights) => {
+                if let Some(attribute) = weights.iter().find(|(_, &w)| w == 0).map(|(a, _)| a) {
+                    return Err(UserError::InvalidAttributeWeight(attribute.clone()).into());
+                }
+
+                // The searchable fields of this update are not written yet.
+                let searchable_fields: Option<Vec<String>> = match self.searchable_fields {
+                    Setting::Set(ref fields) => Some(fields.clone()),
+                    Setting::Reset => None,
+                    Setting::NotSet => self
+                        .index
+                        .user_defined_searchable_fields(self.wtxn)?
+                        .map(|fields| fields.into_iter().map(String::from).collect()),
+                };
+                if let Some(searchable_fields) = searchable_fields {
+                    if let Some(attribute) =
+                        weights.keys().find(|a| !crate::is_faceted(a, &searchable_fields))
+                    {
+                        return Err(UserError::InvalidSearchableAttribute {
+                            field: attribute.clone(),
+                            valid_fields: searchable_fields.into_iter().collect(),
+                        }
+                        .into());
+                    }
+                }
+                self.index.put_attribute_weights(self.wtxn, weights)?;
+            }
+            Setting::Reset => {
+                self.index.delete_attribute_weights(self.wtxn)?;
+            }
+            // the weights of the attributes that are no longer searchable are dropped.
+            Setting::NotSet => {
+                if let Setting::Set(ref searchable_fields) = self.searchable_fields {
+                    let mut weights = self.index.attribute_weights(self.wtxn)?;
+                    let len = weights.len();
+                    weights.retain(|attribute, _| crate::is_faceted(attribute, searchable_fields));
+                    if weights.is_empty() {
+                        self.index.delete_attribute_weights(self.wtxn)?;
+                    } else if weights.len() != len {
+                        self.index.put_attribute_weights(self.wtxn, &weights)?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     fn update_max_values_per_facet(&mut self) -> Result<()> {
         match self.max_values_per_facet {
             Setting::Set(max) => {
@@ -675,6 +769,7 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
         self.update_authorize_typos()?;
         self.update_min_typo_word_len()?;
         self.update_exact_words()?;
+        self.update_attribute_weights()?;
         self.update_max_values_per_facet()?;
         self.update_pagination_max_total_hits()?;
 
@@ -705,11 +800,12 @@ impl<'a, 't, 'u, 'i> Settings<'a, 't, 'u, 'i> {
 #[cfg(test)]
 mod tests {
     use big_s::S;
-    use heed::types::ByteSlice;
+    use heed::types::{ByteSlice, SerdeBincode, Str};
     use maplit::{btreeset, hashmap, hashset};
 
     use super::*;
     use crate::error::Error;
+    use crate::index::main_key;
     use crate::index::tests::TempIndex;
     use crate::{Criterion, Filter, SearchResult};
 
@@ -1214,6 +1310,87 @@ mod tests {
         assert!(result.documents_ids.is_empty());
     }
 
+    #[test]
+    fn legacy_synonyms() {
+        let index = TempIndex::new();
+        index
+            .add_documents(documents!([
+                { "id": 0, "maxim": "I love dogs" },
+                { "id": 1, "maxim": "The crepes are really good" },
+            ]))
+            .unwrap();
+
+        // The synonyms as they were written by the previous versions of the engine.
+        let mut wtxn = index.write_txn().unwrap();
+        let legacy: HashMap<Vec<String>, Vec<Vec<String>>> = hashmap! {
+            vec![S("blini")] => vec![vec![S("crepes")]],
+            vec![S("puppies")] => vec![vec![S("dogs")], vec![S("doggos")]],
+        };
+        index
+            .main
+            .put::<_, Str, SerdeBincode<_>>(&mut wtxn, main_key::SYNONYMS_KEY, &legacy)
+            .unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert_eq!(index.synonyms(&rtxn).unwrap(), legacy);
+        assert_eq!(
+            index.synonym_groups(&rtxn).unwrap(),
+            vec![
+                SynonymGroup::OneWay { input: S("blini"), synonyms: vec![S("crepes")] },
+                SynonymGroup::OneWay {
+                    input: S("puppies"),
+                    synonyms: vec![S("dogs"), S("doggos")]
+                },
+            ]
+        );
+        let result = index.search(&rtxn).query("blini").execute().unwrap();
+        assert_eq!(result.documents_ids, vec![1]);
+        drop(rtxn);
+
+        // Updating the synonyms replaces the legacy ones.
+        index
+            .update_settings(|settings| {
+                settings.set_synonyms(hashmap! { S("puppies") => vec![S("dogs")] });
+            })
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        assert!(index
+            .main
+            .get::<_, Str, ByteSlice>(&rtxn, main_key::SYNONYMS_KEY)
+            .unwrap()
+            .is_none());
+        let result = index.search(&rtxn).query("blini").execute().unwrap();
+        assert!(result.documents_ids.is_empty());
+        let result = index.search(&rtxn).query("puppies").execute().unwrap();
+        assert_eq!(result.documents_ids, vec![0]);
+    }
+
+    #[test]
+    fn synonyms_normalized_with_new_stop_words() {
+        let index = TempIndex::new();
+        let synonyms = hashmap! { S("the blini") => vec![S("crepes")] };
+        index.update_settings(|settings| settings.set_synonyms(synonyms.clone())).unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let expected = hashmap! { vec![S("the"), S("blini")] => vec![vec![S("crepes")]] };
+        assert_eq!(index.synonyms(&rtxn).unwrap(), expected);
+        drop(rtxn);
+
+        // The same synonyms are sent along with new stop words.
+        index
+            .update_settings(|settings| {
+                settings.set_stop_words(btreeset! { S("the") });
+                settings.set_synonyms(synonyms);
+            })
+            .unwrap();
+
+        let rtxn = index.read_txn().unwrap();
+        let expected = hashmap! { vec![S("blini")] => vec![vec![S("crepes")]] };
+        assert_eq!(index.synonyms(&rtxn).unwrap(), expected);
+    }
+
     #[test]
     fn setting_searchable_recomputes_other_settings() {
         let index = TempIndex::new();
@@ -1451,6 +1628,7 @@ mod tests {
                     index: _,
                     indexer_config: _,
                     searchable_fields,
+                    attribute_weights,
                     displayed_fields,
                     filterable_fields,
                     sortable_fields,
@@ -1468,6 +1646,7 @@ mod tests {
                     pagination_max_total_hits,
                 } = settings;
                 assert!(matches!(searchable_fields, Setting::NotSet));
+                assert!(matches!(attribute_weights, Setting::NotSet));
                 assert!(matches!(displayed_fields, Setting::NotSet));
                 assert!(matches!(filterable_fields, Setting::NotSet));
                 assert!(matches!(sortable_fields, Setting::NotSet));
@@ -5,7 +5,7 @@ use heed::EnvOpenOptions;
 use maplit::hashset;
 use milli::documents::{DocumentsBatchBuilder, DocumentsBatchReader};
 use milli::update::{IndexDocuments, IndexDocumentsConfig, IndexerConfig, Settings};
-use milli::{FacetDistribution, Index, Object};
+use milli::{FacetDistribution, FacetSearch, FacetValueHit, Index, Object, OrderBy};
 use serde_json::Deserializer;
 
 #[test]
@@ -71,3 +71,162 @@ fn test_facet_distribution_with_no_facet_values() {
     let result = distrib.execute().unwrap();
     assert_eq!(result["tags"].len(), 2);
 }
+
+#[test]
+fn test_facet_search() {
+    let path = tempfile::tempdir().unwrap();
+    let mut options = EnvOpenOptions::new();
+    options.map_size(10 * 1024 * 1024); // 10 MB
+    let index = Index::new(options, &path).unwrap();
+
+    let mut wtxn = index.write_txn().unwrap();
+    let config = IndexerConfig::default();
+    let mut builder = Settings::new(&mut wtxn, &index, &config);
+
+    builder.set_filterable_fields(hashset! { S("brand") });
+    builder.execute(|_| ()).unwrap();
+
+    // index documents
+    let config = IndexerConfig { max_memory: Some(10 * 1024 * 1024), ..Default::default() };
+    let indexing_config = IndexDocumentsConfig { autogenerate_docids: true, ..Default::default() };
+
+    let builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ()).unwrap();
+    let mut documents_builder = DocumentsBatchBuilder::new(Vec::new());
+    let reader = Cursor::new(
+        r#"{ "id": 0, "brand": "Nike" }
+        { "id": 1, "brand": "Nikon" }
+        { "id": 2, "brand": "Nintendo" }
+        { "id": 3, "brand": "Adidas" }
+        { "id": 4, "brand": "nike" }
+        { "id": 5, "title": "no brand" }"#,
+    );
+
+    for result in Deserializer::from_reader(reader).into_iter::<Object>() {
+        let object = result.unwrap();
+        documents_builder.append_json_object(&object).unwrap();
+    }
+
+    let vector = documents_builder.into_inner().unwrap();
+
+    // index documents
+    let content = DocumentsBatchReader::from_reader(Cursor::new(vector)).unwrap();
+    let (builder, user_error) = builder.add_documents(content).unwrap();
+    user_error.unwrap();
+    builder.execute().unwrap();
+
+    wtxn.commit().unwrap();
+
+    let txn = index.read_txn().unwrap();
+    let hit = |value: &str, count| FacetValueHit { value: value.to_string(), count };
+
+    // the query is a prefix of the values.
+    let mut search = FacetSearch::new(&txn, &index, "brand");
+    search.query("NIK");
+    let values: Vec<_> = search.execute().unwrap().into_iter().map(|h| h.value).collect();
+    assert_eq!(values.len(), 2);
+    assert!(values.contains(&S("Nikon")));
+
+    // the query can contain typos.
+    let mut search = FacetSearch::new(&txn, &index, "brand");
+    search.query("adidsa");
+    assert_eq!(search.execute().unwrap(), vec![hit("Adidas", 1)]);
+    search.authorize_typos(false);
+    assert!(search.execute().unwrap().is_empty());
+
+    // only the candidates are counted.
+    let mut search = FacetSearch::new(&txn, &index, "brand");
+    search.query("nike");
+    assert_eq!(search.execute().unwrap()[0].count, 2);
+    search.candidates((0..4).collect());
+    assert_eq!(search.execute().unwrap()[0].count, 1);
+    search.candidates((1..4).collect());
+    assert!(search.execute().unwrap().is_empty());
+
+    // an empty query returns all the values.
+    let mut search = FacetSearch::new(&txn, &index, "brand");
+    assert_eq!(search.execute().unwrap().len(), 4);
+    search.max_values(2);
+    assert_eq!(search.execute().unwrap().len(), 2);
+
+    // the field must be filterable.
+    let search = FacetSearch::new(&txn, &index, "title");
+    assert!(search.execute().is_err());
+}
+
+#[test]
+fn test_facet_distribution_order_by_and_stats() {
+    let path = tempfile::tempdir().unwrap();
+    let mut options = EnvOpenOptions::new();
+    options.map_size(10 * 1024 * 1024); // 10 MB
+    let index = Index::new(options, &path).unwrap();
+
+    let mut wtxn = index.write_txn().unwrap();
+    let config = IndexerConfig::default();
+    let mut builder = Settings::new(&mut wtxn, &index, &config);
+
+    builder.set_filterable_fields(hashset! {
+        S("color"),
+        S("price"),
+    });
+    builder.execute(|_| ()).unwrap();
+
+    // index documents
+    let config = IndexerConfig { max_memory: Some(10 * 1024 * 1024), ..Default::default() };
+    let indexing_config = IndexDocumentsConfig { autogenerate_docids: true, ..Default::default() };
+
+    let builder = IndexDocuments::new(&mut wtxn, &index, &config, indexing_config, |_| ()).unwrap();
+    let mut documents_builder = DocumentsBatchBuilder::new(Vec::new());
+    let reader = Cursor::new(
+        r#"{ "id": 0, "color": "blue", "price": 12.5 }
+        { "id": 1, "color": "red", "price": 3 }
+        { "id": 2, "color": "red", "price": 40 }
+        { "id": 3, "color": "yellow", "price": 7 }
+        { "id": 4, "color": "red" }
+        { "id": 5, "color": "yellow", "price": -2 }"#,
+    );
+
+    for result in Deserializer::from_reader(reader).into_iter::<Object>() {
+        let object = result.unwrap();
+        documents_builder.append_json_object(&object).unwrap();
+    }
+
+    let vector = documents_builder.into_inner().unwrap();
+
+    // index documents
+    let content = DocumentsBatchReader::from_reader(Cursor::new(vector)).unwrap();
+    let (builder, user_error) = builder.add_documents(content).unwrap();
+    user_error.unwrap();
+    builder.execute().unwrap();
+
+    wtxn.commit().unwrap();
+
+    let txn = index.read_txn().unwrap();
+
+    let mut distrib = FacetDistribution::new(&txn, &index);
+    distrib.facets(vec!["color"]);
+    let result = distrib.execute().unwrap();
+    let colors: Vec<_> = result["color"].iter().map(|(v, c)| (v.as_str(), *c)).collect();
+    assert_eq!(colors, vec![("blue", 1), ("red", 3), ("yellow", 2)]);
+
+    // the most frequent values come first and are kept when truncating.
+    distrib.order_by("color", OrderBy::Count);
+    distrib.max_values_per_facet(2);
+    let result = distrib.execute().unwrap();
+    let colors: Vec<_> = result["color"].iter().map(|(v, c)| (v.as_str(), *c)).collect();
+    assert_eq!(colors, vec![("red", 3), ("yellow", 2)]);
+
+    distrib.candidates((0..4).collect());
+    let result = distrib.execute().unwrap();
+    let colors: Vec<_> = result["color"].iter().map(|(v, c)| (v.as_str(), *c)).collect();
+    assert_eq!(colors, vec![("red", 2), ("blue", 1)]);
+
+    // only the numeric facets have stats.
+    let mut distrib = FacetDistribution::new(&txn, &index);
+    let stats = distrib.compute_stats().unwrap();
+    assert_eq!(stats.len(), 1);
+    assert_eq!(stats["price"], (-2.0, 40.0));
+
+    distrib.candidates((0..2).collect());
+    let stats = distrib.compute_stats().unwrap();
+    assert_eq!(stats["price"], (3.0, 12.5));
+}
@@ -7,7 +7,9 @@ use itertools::Itertools;
 use maplit::hashset;
 use milli::documents::{DocumentsBatchBuilder, DocumentsBatchReader};
 use milli::update::{IndexDocuments, IndexDocumentsConfig, IndexerConfig, Settings};
-use milli::{AscDesc, Criterion, Index, Member, Search, SearchResult, TermsMatchingStrategy};
+use milli::{
+    AscDesc, Criterion, Index, Member, ScoreDetails, Search, SearchResult, TermsMatchingStrategy,
+};
 use rand::Rng;
 use Criterion::*;
 
@@ -467,3 +469,84 @@ fn criteria_ascdesc() {
         assert_eq!(documents_ids, expected_document_ids);
     }
 }
+
+#[test]
+fn document_scores() {
+    let criteria = vec![Words, Typo, Proximity, Attribute, Exactness];
+    let index = search::setup_search_index_with_criteria(&criteria);
+    let rtxn = index.read_txn().unwrap();
+
+    let mut search = Search::new(&rtxn, &index);
+    search.query(search::TEST_QUERY);
+    search.limit(EXTERNAL_DOCUMENTS_IDS.len());
+    search.terms_matching_strategy(ALLOW_OPTIONAL_WORDS);
+
+    let SearchResult { documents_ids, document_scores, .. } = search.execute().unwrap();
+    assert_eq!(documents_ids.len(), document_scores.len());
+
+    // every document is ranked by every ranking rule, in order.
+    for score_details in &document_scores {
+        assert!(matches!(
+            score_details[..],
+            [
+                ScoreDetails::Words(_),
+                ScoreDetails::Typo(_),
+                ScoreDetails::Proximity(_),
+                ScoreDetails::Attribute(_),
+                ScoreDetails::Exactness(_),
+            ]
+        ));
+    }
+
+    // the documents are returned from the most relevant to the least relevant.
+    let global_scores: Vec<_> =
+        document_scores.iter().map(|details| ScoreDetails::global_score(details.iter())).collect();
+    assert!(global_scores.iter().all(|score| (0.0..=1.0).contains(score)));
+    assert!(global_scores.windows(2).all(|scores| scores[0] >= scores[1]), "{:?}", global_scores);
+}
+
+#[test]
+fn ranking_score_threshold() {
+    let criteria = vec![Words, Typo, Proximity, Attribute, Exactness];
+    let index = search::setup_search_index_with_criteria(&criteria);
+    let rtxn = index.read_txn().unwrap();
+
+    let mut search = Search::new(&rtxn, &index);
+    search.query(search::TEST_QUERY);
+    search.limit(EXTERNAL_DOCUMENTS_IDS.len());
+    search.terms_matching_strategy(ALLOW_OPTIONAL_WORDS);
+
+    let SearchResult { document_scores, .. } = search.execute().unwrap();
+    let global_scores: Vec<_> =
+        document_scores.iter().map(|details| ScoreDetails::global_score(details.iter())).collect();
+    let best_score = global_scores.iter().cloned().fold(0.0, f64::max);
+
+    // only the documents with the best score are kept.
+    search.ranking_score_threshold(best_score);
+    let SearchResult { documents_ids, document_scores, candidates, .. } = search.execute().unwrap();
+
+    let expected = global_scores.iter().filter(|&&score| score >= best_score).count();
+    assert_eq!(documents_ids.len(), expected);
+    assert_eq!(candidates.len(), expected as u64);
+    assert!(document_scores
+        .iter()
+        .all(|details| ScoreDetails::global_score(details.iter()) >= best_score));
+
+    // the search stopped at the limit, the documents of the following buckets are not counted.
+    search.limit(1).ranking_score_threshold(0.0);
+    let SearchResult { documents_ids, candidates, candidates_estimated, .. } =
+        search.execute().unwrap();
+    assert_eq!(documents_ids.len(), 1);
+    assert!(candidates_estimated);
+    assert!(candidates.contains(documents_ids[0]));
+    assert!(candidates.len() <= expected as u64);
+    search.limit(EXTERNAL_DOCUMENTS_IDS.len());
+
+    // a threshold above the maximum score drops every document.
+    search.ranking_score_threshold(1.1);
+    let SearchResult { documents_ids, candidates, candidates_estimated, .. } =
+        search.execute().unwrap();
+    assert!(documents_ids.is_empty());
+    assert!(candidates.is_empty());
+    assert!(!candidates_estimated);
+}